@@ -0,0 +1,35 @@
+//! Errors of the allocator
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Thrown when a request is for another domain than the allocator
+    #[error("Request domain does not match the allocator domain")]
+    DomainMismatch,
+
+    /// Thrown when a request is for another vault than the allocator
+    #[error("Request vault does not match the allocator vault")]
+    VaultMismatch,
+
+    /// Thrown when a key cannot be read
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    /// Thrown when a key does not belong to the expected signer
+    #[error("Key does not match the signer {0}")]
+    KeyMismatch(Pubkey),
+
+    /// Thrown when a returned signature does not verify
+    #[error("Invalid signature from the signer {0}")]
+    InvalidSignature(Pubkey),
+
+    /// Thrown when a keystore file cannot be read
+    #[error("Keystore error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Thrown when a remote signer cannot be reached or fails
+    #[error("Remote signer error: {0}")]
+    Remote(String),
+}
@@ -0,0 +1,317 @@
+//! Signing of relay depository transfer requests by the allocator
+//!
+//! The relay depository executes a `TransferRequest` only if the instruction
+//! preceding `execute_transfer` is an ed25519 precompile instruction verifying
+//! the signature of the allocator over the request hash, see
+//! `relay_depository_client::ed25519` for its layout. The `Allocator` builds
+//! the requests of a vault, signs them through a `RequestSigner` and produces
+//! these instructions.
+
+pub mod error;
+pub mod remote;
+pub mod signer;
+
+use anchor_lang::solana_program::{instruction::Instruction, pubkey::Pubkey};
+use relay_depository::{
+    create_domain_separator, pda::find_relay_depository_address, TransferRequest, DOMAIN_NAME,
+    DOMAIN_VERSION,
+};
+use relay_depository_client::{
+    depository::{self, RelayVault},
+    ed25519::new_ed25519_instruction,
+};
+
+pub use error::Error;
+pub use remote::RemoteSigner;
+pub use signer::{FileSigner, KeypairSigner, RequestSigner};
+
+/// Returns the domain separator of a relay depository of a relay depository deployment
+///
+/// The verifying contract is the relay depository program for the default relay
+/// depository, and the relay depository itself for instances.
+pub fn domain_separator(
+    chain_id: &str,
+    relay_depository_program: &Pubkey,
+    relay_depository: &Pubkey,
+) -> [u8; 32] {
+    let verifying_contract =
+        if *relay_depository == find_relay_depository_address(relay_depository_program).0 {
+            *relay_depository_program
+        } else {
+            *relay_depository
+        };
+
+    create_domain_separator(
+        DOMAIN_NAME,
+        DOMAIN_VERSION,
+        chain_id.as_bytes(),
+        &verifying_contract,
+    )
+}
+
+/// Allocator of a vault, signing its transfer requests
+pub struct Allocator<S> {
+    /// The signer holding the allocator key
+    signer: S,
+    /// The domain separator of the relay depository
+    domain: [u8; 32],
+    /// The vault funding the transfers
+    vault: RelayVault,
+}
+
+impl<S: RequestSigner> Allocator<S> {
+    pub fn new(signer: S, domain: [u8; 32], vault: RelayVault) -> Self {
+        Self {
+            signer,
+            domain,
+            vault,
+        }
+    }
+
+    /// Returns the public key of the allocator
+    pub fn pubkey(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
+
+    /// Builds a transfer request from the vault of the allocator
+    pub fn transfer_request(
+        &self,
+        recipient: Pubkey,
+        token: Option<Pubkey>,
+        amount: u64,
+        nonce: u64,
+        expiration: i64,
+        reference: [u8; 32],
+    ) -> TransferRequest {
+        TransferRequest {
+            domain: self.domain,
+            recipient,
+            token,
+            amount,
+            nonce,
+            expiration,
+            vault_address: self.vault.address,
+            reference,
+        }
+    }
+
+    /// Signs the hash of a transfer request
+    ///
+    /// Requests for another domain or vault are rejected, since the relay
+    /// depository would reject them as well.
+    pub fn sign(&self, request: TransferRequest) -> Result<SignedTransferRequest, Error> {
+        if request.domain != self.domain {
+            return Err(Error::DomainMismatch);
+        }
+        if request.vault_address != self.vault.address {
+            return Err(Error::VaultMismatch);
+        }
+
+        let signature = self.signer.sign_message(&request.get_hash().to_bytes())?;
+
+        Ok(SignedTransferRequest {
+            request,
+            vault: self.vault,
+            allocator: self.signer.pubkey(),
+            signature,
+        })
+    }
+}
+
+/// Transfer request signed by the allocator
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SignedTransferRequest {
+    /// The signed transfer request
+    pub request: TransferRequest,
+    /// The vault of the request, with the relay depository it belongs to
+    pub vault: RelayVault,
+    /// The allocator who signed the request
+    pub allocator: Pubkey,
+    /// The signature of the request hash
+    pub signature: [u8; 64],
+}
+
+impl SignedTransferRequest {
+    /// Returns the ed25519 instruction verifying the signature of the request
+    pub fn ed25519_instruction(&self) -> Instruction {
+        new_ed25519_instruction(
+            &self.allocator,
+            &self.signature,
+            &self.request.get_hash().to_bytes(),
+        )
+    }
+
+    /// Returns the ed25519 instruction followed by the `execute_transfer` instruction
+    /// of the request
+    pub fn instructions(&self, executor: &Pubkey, token_program: &Pubkey) -> [Instruction; 2] {
+        depository::execute_transfer_with_signature(
+            &self.vault,
+            executor,
+            &self.request,
+            token_program,
+            &self.allocator,
+            &self.signature,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{PublicKey, Signature, Verifier};
+
+    fn allocator() -> Allocator<KeypairSigner> {
+        let signer = KeypairSigner::from_seed(&[1; 32]).unwrap();
+        let vault = RelayVault::default_vault();
+        let domain = domain_separator("solana-mainnet", &vault.program_id, &vault.relay_depository);
+        Allocator::new(signer, domain, vault)
+    }
+
+    #[test]
+    fn test_domain_separator() {
+        let domain = domain_separator(
+            "solana-mainnet",
+            &relay_depository::ID,
+            &find_relay_depository_address(&relay_depository::ID).0,
+        );
+
+        assert_eq!(
+            domain,
+            create_domain_separator(
+                b"RelayDepository",
+                b"1",
+                b"solana-mainnet",
+                &relay_depository::ID
+            )
+        );
+        assert_ne!(
+            domain,
+            domain_separator(
+                "solana-devnet",
+                &relay_depository::ID,
+                &find_relay_depository_address(&relay_depository::ID).0
+            )
+        );
+
+        // The default relay depository of another deployment is verified by its program
+        let program_id = Pubkey::new_unique();
+        assert_eq!(
+            domain_separator(
+                "solana-mainnet",
+                &program_id,
+                &find_relay_depository_address(&program_id).0
+            ),
+            create_domain_separator(b"RelayDepository", b"1", b"solana-mainnet", &program_id)
+        );
+
+        // Instances are the verifying contract of their domain separator
+        let instance = Pubkey::new_unique();
+        assert_eq!(
+            domain_separator("solana-mainnet", &relay_depository::ID, &instance),
+            create_domain_separator(b"RelayDepository", b"1", b"solana-mainnet", &instance)
+        );
+    }
+
+    #[test]
+    fn test_sign_transfer_request() {
+        let allocator = allocator();
+        let request = allocator.transfer_request(
+            Pubkey::new_unique(),
+            None,
+            1_000,
+            1,
+            1_700_000_000,
+            [2; 32],
+        );
+
+        let signed = allocator.sign(request).unwrap();
+
+        let public_key = PublicKey::from_bytes(allocator.pubkey().as_ref()).unwrap();
+        let signature = Signature::try_from(&signed.signature[..]).unwrap();
+        assert!(public_key
+            .verify(&request.get_hash().to_bytes(), &signature)
+            .is_ok());
+
+        let ix = signed.ed25519_instruction();
+        assert_eq!(ix.data.len(), 144);
+        assert_eq!(&ix.data[16..48], allocator.pubkey().as_ref());
+        assert_eq!(&ix.data[48..112], &signed.signature);
+        assert_eq!(&ix.data[112..], &request.get_hash().to_bytes());
+
+        let [signature_ix, execute_ix] = signed.instructions(
+            &Pubkey::new_unique(),
+            &anchor_lang::solana_program::system_program::ID,
+        );
+        assert_eq!(signature_ix, ix);
+        assert_eq!(execute_ix.program_id, relay_depository::ID);
+    }
+
+    #[test]
+    fn test_instance_vault_instructions() {
+        let instance = relay_depository::pda::find_relay_depository_instance_address(
+            &relay_depository::ID,
+            &Pubkey::new_unique(),
+            1,
+        )
+        .0;
+        let vault = RelayVault::new(&relay_depository::ID, &instance, Some(2));
+        let allocator = Allocator::new(
+            KeypairSigner::from_seed(&[1; 32]).unwrap(),
+            domain_separator("solana-mainnet", &relay_depository::ID, &instance),
+            vault,
+        );
+        let request = allocator.transfer_request(
+            Pubkey::new_unique(),
+            None,
+            1_000,
+            1,
+            1_700_000_000,
+            [2; 32],
+        );
+
+        let [_, execute_ix] = allocator.sign(request).unwrap().instructions(
+            &Pubkey::new_unique(),
+            &anchor_lang::solana_program::system_program::ID,
+        );
+
+        // The transfer is executed by the instance, from the vault of the request
+        assert_eq!(execute_ix.accounts[0].pubkey, instance);
+        assert_eq!(execute_ix.accounts[3].pubkey, request.vault_address);
+        assert_eq!(execute_ix.accounts[14].pubkey, vault.config.unwrap());
+    }
+
+    #[test]
+    fn test_sign_rejects_foreign_requests() {
+        let allocator = allocator();
+        let request = allocator.transfer_request(
+            Pubkey::new_unique(),
+            None,
+            1_000,
+            1,
+            1_700_000_000,
+            [2; 32],
+        );
+
+        let other_domain = TransferRequest {
+            domain: domain_separator(
+                "solana-devnet",
+                &relay_depository::ID,
+                &find_relay_depository_address(&relay_depository::ID).0,
+            ),
+            ..request
+        };
+        assert!(matches!(
+            allocator.sign(other_domain),
+            Err(Error::DomainMismatch)
+        ));
+
+        let other_vault = TransferRequest {
+            vault_address: Pubkey::new_unique(),
+            ..request
+        };
+        assert!(matches!(
+            allocator.sign(other_vault),
+            Err(Error::VaultMismatch)
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+//! Signer delegating to a remote signing service over HTTP
+//!
+//! The service is sent a JSON `POST` request with the base58 public key of the
+//! signer and the hex encoded request hash:
+//!
+//! ```json
+//! { "pubkey": "<base58>", "message": "<hex>" }
+//! ```
+//!
+//! and must answer with the hex encoded signature:
+//!
+//! ```json
+//! { "signature": "<hex>" }
+//! ```
+
+use std::time::Duration;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, RequestSigner};
+
+/// Default timeout of the requests to the signing service
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize)]
+struct SignRequest {
+    pubkey: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+/// Signer delegating to a remote signing service
+///
+/// The returned signatures are verified against the public key of the signer,
+/// so a misbehaving service cannot produce instructions failing on chain.
+pub struct RemoteSigner {
+    /// The URL of the signing endpoint
+    url: String,
+    /// The public key of the remote signer
+    pubkey: Pubkey,
+    agent: ureq::Agent,
+}
+
+impl RemoteSigner {
+    pub fn new(url: impl Into<String>, pubkey: Pubkey) -> Self {
+        Self::with_timeout(url, pubkey, DEFAULT_TIMEOUT)
+    }
+
+    pub fn with_timeout(url: impl Into<String>, pubkey: Pubkey, timeout: Duration) -> Self {
+        Self {
+            url: url.into(),
+            pubkey,
+            agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+        }
+    }
+}
+
+impl RequestSigner for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message(&self, message: &[u8; 32]) -> Result<[u8; 64], Error> {
+        let response: SignResponse = self
+            .agent
+            .post(&self.url)
+            .send_json(SignRequest {
+                pubkey: self.pubkey.to_string(),
+                message: hex::encode(message),
+            })
+            .map_err(|e| Error::Remote(e.to_string()))?
+            .into_json()
+            .map_err(|e| Error::Remote(e.to_string()))?;
+
+        let signature: [u8; 64] = hex::decode(&response.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(Error::InvalidSignature(self.pubkey))?;
+
+        let public_key = PublicKey::from_bytes(self.pubkey.as_ref())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+        let verified = Signature::try_from(&signature[..])
+            .and_then(|parsed| public_key.verify(message, &parsed))
+            .is_ok();
+        if !verified {
+            return Err(Error::InvalidSignature(self.pubkey));
+        }
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::JoinHandle;
+
+    use super::*;
+    use crate::KeypairSigner;
+
+    /// Serves a single signing request with the given signer, returning the URL
+    /// of the mock signing service
+    fn serve_once(signer: KeypairSigner, status: u16) -> (String, JoinHandle<Pubkey>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/sign", server.server_addr().to_ip().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let body: serde_json::Value = serde_json::from_reader(request.as_reader()).unwrap();
+
+            let message: [u8; 32] = hex::decode(body["message"].as_str().unwrap())
+                .unwrap()
+                .try_into()
+                .unwrap();
+            let signature = signer.sign_message(&message).unwrap();
+
+            let response = tiny_http::Response::from_string(
+                serde_json::json!({ "signature": hex::encode(signature) }).to_string(),
+            )
+            .with_status_code(status);
+            request.respond(response).unwrap();
+
+            body["pubkey"].as_str().unwrap().parse().unwrap()
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn test_remote_signer() {
+        let local = KeypairSigner::from_seed(&[1; 32]).unwrap();
+        let expected = local.sign_message(&[2; 32]).unwrap();
+        let pubkey = local.pubkey();
+
+        let (url, handle) = serve_once(local, 200);
+        let signer = RemoteSigner::new(url, pubkey);
+
+        assert_eq!(signer.sign_message(&[2; 32]).unwrap(), expected);
+        assert_eq!(handle.join().unwrap(), pubkey);
+    }
+
+    #[test]
+    fn test_remote_signer_rejects_wrong_signer() {
+        let pubkey = KeypairSigner::from_seed(&[1; 32]).unwrap().pubkey();
+
+        let (url, handle) = serve_once(KeypairSigner::from_seed(&[3; 32]).unwrap(), 200);
+        let signer = RemoteSigner::new(url, pubkey);
+
+        assert!(matches!(
+            signer.sign_message(&[2; 32]),
+            Err(Error::InvalidSignature(key)) if key == pubkey
+        ));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_remote_signer_error_status() {
+        let local = KeypairSigner::from_seed(&[1; 32]).unwrap();
+        let pubkey = local.pubkey();
+
+        let (url, handle) = serve_once(local, 500);
+        let signer = RemoteSigner::new(url, pubkey);
+
+        assert!(matches!(
+            signer.sign_message(&[2; 32]),
+            Err(Error::Remote(_))
+        ));
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+//! Signers of request hashes holding the allocator key
+
+use std::path::{Path, PathBuf};
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+
+use crate::Error;
+
+/// Signer of the 32-byte request hashes verified by the relay depository
+pub trait RequestSigner {
+    /// Returns the public key of the signer
+    fn pubkey(&self) -> Pubkey;
+
+    /// Signs a request hash
+    fn sign_message(&self, message: &[u8; 32]) -> Result<[u8; 64], Error>;
+}
+
+/// Signer holding the allocator keypair in memory
+pub struct KeypairSigner {
+    keypair: Keypair,
+}
+
+impl KeypairSigner {
+    pub fn new(keypair: Keypair) -> Self {
+        Self { keypair }
+    }
+
+    /// Creates a signer from a 32-byte secret key
+    pub fn from_seed(seed: &[u8; 32]) -> Result<Self, Error> {
+        let secret = SecretKey::from_bytes(seed).map_err(|e| Error::InvalidKey(e.to_string()))?;
+        let public = PublicKey::from(&secret);
+
+        Ok(Self::new(Keypair { secret, public }))
+    }
+
+    /// Creates a signer from a 64-byte keypair, the secret key followed by the public key
+    ///
+    /// The public key must be the one of the secret key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let keypair = Keypair::from_bytes(bytes).map_err(|e| Error::InvalidKey(e.to_string()))?;
+        if PublicKey::from(&keypair.secret) != keypair.public {
+            return Err(Error::InvalidKey(
+                "public key does not match the secret key".to_string(),
+            ));
+        }
+
+        Ok(Self::new(keypair))
+    }
+}
+
+impl RequestSigner for KeypairSigner {
+    fn pubkey(&self) -> Pubkey {
+        Pubkey::new_from_array(self.keypair.public.to_bytes())
+    }
+
+    fn sign_message(&self, message: &[u8; 32]) -> Result<[u8; 64], Error> {
+        Ok(self.keypair.sign(message).to_bytes())
+    }
+}
+
+/// Signer reading the allocator keypair from a keystore file for each signature
+///
+/// The keystore file uses the format of the Solana CLI, a JSON array of the 64
+/// bytes of the keypair, and the key isn't kept in memory. The signer is bound to
+/// the key read when opened, so signing fails with `Error::KeyMismatch` once the
+/// keystore file holds another key, and rotating the key means reopening the signer
+/// (and updating the allocator of the relay depository).
+pub struct FileSigner {
+    /// The path of the keystore file
+    path: PathBuf,
+    /// The public key expected in the keystore file
+    pubkey: Pubkey,
+}
+
+impl FileSigner {
+    /// Opens a keystore file, using its current key as the expected signer
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let pubkey = read_keypair_file(&path)?.pubkey();
+
+        Ok(Self { path, pubkey })
+    }
+}
+
+impl RequestSigner for FileSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message(&self, message: &[u8; 32]) -> Result<[u8; 64], Error> {
+        let signer = read_keypair_file(&self.path)?;
+        if signer.pubkey() != self.pubkey {
+            return Err(Error::KeyMismatch(self.pubkey));
+        }
+
+        signer.sign_message(message)
+    }
+}
+
+/// Reads a keypair from a keystore file in the format of the Solana CLI
+pub fn read_keypair_file(path: &Path) -> Result<KeypairSigner, Error> {
+    let contents = std::fs::read_to_string(path)?;
+    let bytes: Vec<u8> =
+        serde_json::from_str(&contents).map_err(|e| Error::InvalidKey(e.to_string()))?;
+
+    KeypairSigner::from_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair_bytes(seed: &[u8; 32]) -> Vec<u8> {
+        let signer = KeypairSigner::from_seed(seed).unwrap();
+        signer.keypair.to_bytes().to_vec()
+    }
+
+    fn write_keystore(path: &Path, seed: &[u8; 32]) {
+        std::fs::write(path, serde_json::to_string(&keypair_bytes(seed)).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_keypair_signer() {
+        let signer = KeypairSigner::from_seed(&[1; 32]).unwrap();
+        let signature = signer.sign_message(&[2; 32]).unwrap();
+
+        let from_bytes = KeypairSigner::from_bytes(&keypair_bytes(&[1; 32])).unwrap();
+        assert_eq!(from_bytes.pubkey(), signer.pubkey());
+        // Ed25519 signatures are deterministic
+        assert_eq!(from_bytes.sign_message(&[2; 32]).unwrap(), signature);
+    }
+
+    #[test]
+    fn test_keypair_signer_rejects_inconsistent_keypair() {
+        let mut bytes = keypair_bytes(&[1; 32]);
+        bytes[32..].copy_from_slice(&keypair_bytes(&[2; 32])[32..]);
+
+        assert!(matches!(
+            KeypairSigner::from_bytes(&bytes),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_file_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allocator.json");
+        write_keystore(&path, &[1; 32]);
+
+        let signer = FileSigner::open(&path).unwrap();
+        let expected = KeypairSigner::from_seed(&[1; 32]).unwrap();
+        assert_eq!(signer.pubkey(), expected.pubkey());
+        assert_eq!(
+            signer.sign_message(&[2; 32]).unwrap(),
+            expected.sign_message(&[2; 32]).unwrap()
+        );
+
+        // A replaced key is not used for the expected signer
+        write_keystore(&path, &[3; 32]);
+        assert!(matches!(
+            signer.sign_message(&[2; 32]),
+            Err(Error::KeyMismatch(pubkey)) if pubkey == expected.pubkey()
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(signer.sign_message(&[2; 32]), Err(Error::Io(_))));
+    }
+}
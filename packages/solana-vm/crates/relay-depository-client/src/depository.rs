@@ -0,0 +1,1011 @@
+//! Instruction builders for the relay depository program
+
+use anchor_lang::{
+    prelude::AccountMeta,
+    solana_program::{
+        bpf_loader_upgradeable, instruction::Instruction, pubkey::Pubkey, system_program, sysvar,
+    },
+    InstructionData, ToAccountMetas,
+};
+use anchor_spl::token::spl_token;
+use relay_depository::{accounts, instruction, pda::*, TransferRequest};
+
+use crate::ed25519::new_ed25519_instruction;
+
+/// A vault of a relay depository, the accounts resolved by the builders moving funds
+/// in or out of a vault
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RelayVault {
+    /// The program id of the relay depository deployment
+    pub program_id: Pubkey,
+    /// The relay depository the vault belongs to
+    pub relay_depository: Pubkey,
+    /// The vault PDA
+    pub address: Pubkey,
+    /// The configuration of the vault (None for the main vault of the relay depository)
+    pub config: Option<Pubkey>,
+}
+
+impl RelayVault {
+    /// Returns the main vault of a relay depository of a deployment if `vault_index` is
+    /// None, and one of its additional vaults otherwise
+    pub fn new(program_id: &Pubkey, relay_depository: &Pubkey, vault_index: Option<u16>) -> Self {
+        let (address, config) =
+            find_relay_vault_addresses(program_id, relay_depository, vault_index);
+
+        Self {
+            program_id: *program_id,
+            relay_depository: *relay_depository,
+            address,
+            config,
+        }
+    }
+
+    /// Returns the main vault of the default relay depository of the main deployment
+    pub fn default_vault() -> Self {
+        Self::new(
+            &relay_depository::ID,
+            &find_relay_depository_address(&relay_depository::ID).0,
+            None,
+        )
+    }
+}
+
+/// Returns the account metas of the accounts of an instruction of a relay depository
+/// deployment
+///
+/// The generated accounts replace the absent optional accounts by the id the
+/// `relay_depository` crate was compiled with, which must be the program id of the
+/// deployment for the program to read them as absent.
+fn to_account_metas(accounts: impl ToAccountMetas, program_id: &Pubkey) -> Vec<AccountMeta> {
+    let mut account_metas = accounts.to_account_metas(None);
+    for account_meta in &mut account_metas {
+        if account_meta.pubkey == relay_depository::ID {
+            account_meta.pubkey = *program_id;
+        }
+    }
+    account_metas
+}
+
+/// Returns the program data account of an upgradeable program
+pub fn find_program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+/// Builds an `initialize` instruction of a deployment, signed by the upgrade authority
+/// of the program who becomes the owner
+pub fn initialize(
+    program_id: &Pubkey,
+    upgrade_authority: &Pubkey,
+    allocator: &Pubkey,
+    chain_id: String,
+) -> Instruction {
+    let accounts = accounts::Initialize {
+        relay_depository: find_relay_depository_address(program_id).0,
+        vault: find_vault_address(program_id).0,
+        owner: *upgrade_authority,
+        allocator: *allocator,
+        program: *program_id,
+        program_data: find_program_data_address(program_id),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::Initialize { chain_id }.data(),
+    }
+}
+
+/// Builds a `set_owner` instruction for a relay depository, signed by the current owner
+pub fn set_owner(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    owner: &Pubkey,
+    new_owner: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::SetOwner {
+        relay_depository: *relay_depository,
+        owner: *owner,
+        event_authority: find_event_authority_address(program_id).0,
+        program: *program_id,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::SetOwner {
+            new_owner: *new_owner,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `set_allocator` instruction for a relay depository, signed by the owner
+pub fn set_allocator(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    owner: &Pubkey,
+    new_allocator: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::SetAllocator {
+        relay_depository: *relay_depository,
+        owner: *owner,
+        event_authority: find_event_authority_address(program_id).0,
+        program: *program_id,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::SetAllocator {
+            new_allocator: *new_allocator,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `create_instance` instruction creating a relay depository instance of a
+/// deployment, signed by the creator who pays for it
+pub fn create_instance(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    instance_id: u64,
+    owner: &Pubkey,
+    allocator: &Pubkey,
+    chain_id: String,
+) -> Instruction {
+    let relay_depository =
+        find_relay_depository_instance_address(program_id, creator, instance_id).0;
+    let accounts = accounts::CreateInstance {
+        relay_depository,
+        vault: find_instance_vault_address(program_id, &relay_depository).0,
+        creator: *creator,
+        system_program: system_program::ID,
+        event_authority: find_event_authority_address(program_id).0,
+        program: *program_id,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::CreateInstance {
+            instance_id,
+            owner: *owner,
+            allocator: *allocator,
+            chain_id,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `create_vault` instruction creating an additional vault of a relay
+/// depository, signed by the owner who pays for its configuration
+pub fn create_vault(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    owner: &Pubkey,
+    index: u16,
+    allocator: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::CreateVault {
+        relay_depository: *relay_depository,
+        vault_config: find_vault_config_address(program_id, relay_depository, index).0,
+        vault: find_indexed_vault_address(program_id, relay_depository, index).0,
+        owner: *owner,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::CreateVault {
+            index,
+            allocator: *allocator,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `set_vault_allocator` instruction for an additional vault of a relay
+/// depository, signed by the owner
+pub fn set_vault_allocator(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    owner: &Pubkey,
+    index: u16,
+    new_allocator: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::SetVaultAllocator {
+        relay_depository: *relay_depository,
+        vault_config: find_vault_config_address(program_id, relay_depository, index).0,
+        owner: *owner,
+        event_authority: find_event_authority_address(program_id).0,
+        program: *program_id,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::SetVaultAllocator {
+            new_allocator: *new_allocator,
+        }
+        .data(),
+    }
+}
+
+/// Builds an `open_ledger` instruction opening the ledger of a vault for a token (None
+/// for native SOL), signed by the owner who pays for it
+pub fn open_ledger(
+    vault: &RelayVault,
+    owner: &Pubkey,
+    mint: Option<&Pubkey>,
+    token_program: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::OpenLedger {
+        relay_depository: vault.relay_depository,
+        owner: *owner,
+        vault: vault.address,
+        mint: mint.copied(),
+        vault_token_account: mint
+            .map(|mint| find_vault_token_address(&vault.address, mint, token_program)),
+        vault_ledger: find_vault_ledger_address(&vault.program_id, &vault.address, mint).0,
+        system_program: system_program::ID,
+        vault_config: vault.config,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::OpenLedger {}.data(),
+    }
+}
+
+/// Builds a `deposit_native` instruction into a vault, crediting `depositor` with the
+/// deposit of `sender`
+pub fn deposit_native(
+    vault: &RelayVault,
+    sender: &Pubkey,
+    depositor: &Pubkey,
+    amount: u64,
+    id: [u8; 32],
+) -> Instruction {
+    let accounts = accounts::DepositNative {
+        relay_depository: vault.relay_depository,
+        sender: *sender,
+        depositor: *depositor,
+        vault: vault.address,
+        vault_ledger: find_vault_ledger_address(&vault.program_id, &vault.address, None).0,
+        system_program: system_program::ID,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::DepositNative { amount, id }.data(),
+    }
+}
+
+/// Builds a `deposit_token` instruction into a vault, crediting `depositor` with the
+/// deposit of `sender` from its associated token account
+pub fn deposit_token(
+    vault: &RelayVault,
+    sender: &Pubkey,
+    depositor: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+    id: [u8; 32],
+) -> Instruction {
+    let accounts = accounts::DepositToken {
+        relay_depository: vault.relay_depository,
+        sender: *sender,
+        depositor: *depositor,
+        vault: vault.address,
+        mint: *mint,
+        sender_token_account: find_associated_token_address(sender, mint, token_program),
+        vault_token_account: find_vault_token_address(&vault.address, mint, token_program),
+        vault_ledger: find_vault_ledger_address(&vault.program_id, &vault.address, Some(mint)).0,
+        token_program: *token_program,
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: system_program::ID,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::DepositToken { amount, id }.data(),
+    }
+}
+
+/// Builds an `execute_transfer` instruction for a transfer request out of a vault
+///
+/// The `vault_address` of the request must be the address of the vault. The
+/// instruction must directly follow the ed25519 instruction carrying the
+/// signature of the request by the allocator, see `execute_transfer_with_signature`.
+/// The token program is only used for token transfers.
+pub fn execute_transfer(
+    vault: &RelayVault,
+    executor: &Pubkey,
+    request: &TransferRequest,
+    token_program: &Pubkey,
+) -> Instruction {
+    let token_accounts = request.token.map(|mint| {
+        (
+            find_associated_token_address(&request.recipient, &mint, token_program),
+            find_vault_token_address(&vault.address, &mint, token_program),
+        )
+    });
+
+    let accounts = accounts::ExecuteTransfer {
+        relay_depository: vault.relay_depository,
+        executor: *executor,
+        recipient: request.recipient,
+        vault: vault.address,
+        mint: request.token,
+        recipient_token_account: token_accounts
+            .map(|(recipient_token_account, _)| recipient_token_account),
+        vault_token_account: token_accounts.map(|(_, vault_token_account)| vault_token_account),
+        used_request: find_used_request_address(&vault.program_id, &request.get_hash().to_bytes())
+            .0,
+        vault_ledger: find_vault_ledger_address(
+            &vault.program_id,
+            &vault.address,
+            request.token.as_ref(),
+        )
+        .0,
+        ix_sysvar: sysvar::instructions::ID,
+        token_program: if request.token.is_some() {
+            *token_program
+        } else {
+            spl_token::ID
+        },
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: system_program::ID,
+        memo_program: None,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::ExecuteTransfer { request: *request }.data(),
+    }
+}
+
+/// Builds the ed25519 instruction carrying the allocator signature of a transfer request,
+/// followed by the `execute_transfer` instruction
+pub fn execute_transfer_with_signature(
+    vault: &RelayVault,
+    executor: &Pubkey,
+    request: &TransferRequest,
+    token_program: &Pubkey,
+    allocator: &Pubkey,
+    signature: &[u8; 64],
+) -> [Instruction; 2] {
+    [
+        new_ed25519_instruction(allocator, signature, &request.get_hash().to_bytes()),
+        execute_transfer(vault, executor, request, token_program),
+    ]
+}
+
+/// Builds a `set_treasury` instruction requesting a treasury update of a relay
+/// depository, signed by the owner who pays for the treasury configuration on first use
+pub fn set_treasury(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    owner: &Pubkey,
+    treasury: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::SetTreasury {
+        relay_depository: *relay_depository,
+        treasury_config: find_treasury_config_address(program_id, relay_depository).0,
+        owner: *owner,
+        system_program: system_program::ID,
+        event_authority: find_event_authority_address(program_id).0,
+        program: *program_id,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::SetTreasury {
+            treasury: *treasury,
+        }
+        .data(),
+    }
+}
+
+/// Builds an `execute_treasury_update` instruction applying the pending treasury update
+/// of a relay depository, signed by the owner
+pub fn execute_treasury_update(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::ExecuteTreasuryUpdate {
+        relay_depository: *relay_depository,
+        treasury_config: find_treasury_config_address(program_id, relay_depository).0,
+        owner: *owner,
+        event_authority: find_event_authority_address(program_id).0,
+        program: *program_id,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::ExecuteTreasuryUpdate {}.data(),
+    }
+}
+
+/// Builds a `cancel_treasury_update` instruction dropping the pending treasury update
+/// of a relay depository, signed by the owner
+pub fn cancel_treasury_update(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::CancelTreasuryUpdate {
+        relay_depository: *relay_depository,
+        treasury_config: find_treasury_config_address(program_id, relay_depository).0,
+        owner: *owner,
+        event_authority: find_event_authority_address(program_id).0,
+        program: *program_id,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: to_account_metas(accounts, program_id),
+        data: instruction::CancelTreasuryUpdate {}.data(),
+    }
+}
+
+/// Builds a `request_sweep` instruction requesting a sweep of a token (None for native
+/// SOL) out of a vault to the treasury, signed by the owner who pays for the pending sweep
+pub fn request_sweep(
+    vault: &RelayVault,
+    owner: &Pubkey,
+    token: Option<&Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let accounts = accounts::RequestSweep {
+        relay_depository: vault.relay_depository,
+        treasury_config: find_treasury_config_address(&vault.program_id, &vault.relay_depository).0,
+        vault: vault.address,
+        pending_sweep: find_pending_sweep_address(&vault.program_id, &vault.address).0,
+        owner: *owner,
+        system_program: system_program::ID,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::RequestSweep {
+            token: token.copied(),
+            amount,
+        }
+        .data(),
+    }
+}
+
+/// Builds an `execute_sweep` instruction executing the pending sweep of a vault, signed
+/// by the owner
+///
+/// The `treasury` and `token` must be the ones of the pending sweep. The token program
+/// is only used for token sweeps.
+pub fn execute_sweep(
+    vault: &RelayVault,
+    owner: &Pubkey,
+    treasury: &Pubkey,
+    token: Option<&Pubkey>,
+    token_program: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::ExecuteSweep {
+        relay_depository: vault.relay_depository,
+        owner: *owner,
+        vault: vault.address,
+        pending_sweep: find_pending_sweep_address(&vault.program_id, &vault.address).0,
+        treasury: *treasury,
+        mint: token.copied(),
+        treasury_token_account: token
+            .map(|mint| find_associated_token_address(treasury, mint, token_program)),
+        vault_token_account: token
+            .map(|mint| find_vault_token_address(&vault.address, mint, token_program)),
+        vault_ledger: find_vault_ledger_address(&vault.program_id, &vault.address, token).0,
+        token_program: if token.is_some() {
+            *token_program
+        } else {
+            spl_token::ID
+        },
+        system_program: system_program::ID,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::ExecuteSweep {}.data(),
+    }
+}
+
+/// Builds a `cancel_sweep` instruction dropping the pending sweep of a vault, signed by
+/// the owner
+pub fn cancel_sweep(vault: &RelayVault, owner: &Pubkey) -> Instruction {
+    let accounts = accounts::CancelSweep {
+        relay_depository: vault.relay_depository,
+        owner: *owner,
+        vault: vault.address,
+        pending_sweep: find_pending_sweep_address(&vault.program_id, &vault.address).0,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::CancelSweep {}.data(),
+    }
+}
+
+/// Builds an `announce_emergency_withdraw` instruction announcing the emergency
+/// withdraw of a vault to `recipient`, signed by the owner who pays for the announcement
+pub fn announce_emergency_withdraw(
+    vault: &RelayVault,
+    owner: &Pubkey,
+    recipient: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::AnnounceEmergencyWithdraw {
+        relay_depository: vault.relay_depository,
+        vault: vault.address,
+        pending_emergency_withdraw: find_pending_emergency_withdraw_address(
+            &vault.program_id,
+            &vault.address,
+        )
+        .0,
+        owner: *owner,
+        system_program: system_program::ID,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::AnnounceEmergencyWithdraw {
+            recipient: *recipient,
+        }
+        .data(),
+    }
+}
+
+/// Builds an `emergency_withdraw` instruction withdrawing the full vault balance of a
+/// token (None for native SOL), signed by the owner
+///
+/// The `recipient` and `announced_at` must be the ones of the pending emergency
+/// withdraw. The token program is only used for token withdraws.
+pub fn emergency_withdraw(
+    vault: &RelayVault,
+    owner: &Pubkey,
+    recipient: &Pubkey,
+    announced_at: i64,
+    mint: Option<&Pubkey>,
+    token_program: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::EmergencyWithdraw {
+        relay_depository: vault.relay_depository,
+        owner: *owner,
+        vault: vault.address,
+        pending_emergency_withdraw: find_pending_emergency_withdraw_address(
+            &vault.program_id,
+            &vault.address,
+        )
+        .0,
+        recipient: *recipient,
+        mint: mint.copied(),
+        recipient_token_account: mint
+            .map(|mint| find_associated_token_address(recipient, mint, token_program)),
+        vault_token_account: mint
+            .map(|mint| find_vault_token_address(&vault.address, mint, token_program)),
+        vault_ledger: find_vault_ledger_address(&vault.program_id, &vault.address, mint).0,
+        executed_emergency_withdraw: find_executed_emergency_withdraw_address(
+            &vault.program_id,
+            &vault.address,
+            mint,
+            announced_at,
+        )
+        .0,
+        token_program: if mint.is_some() {
+            *token_program
+        } else {
+            spl_token::ID
+        },
+        system_program: system_program::ID,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::EmergencyWithdraw {}.data(),
+    }
+}
+
+/// Builds a `cancel_emergency_withdraw` instruction ending the emergency withdraw of a
+/// vault, signed by the owner
+pub fn cancel_emergency_withdraw(vault: &RelayVault, owner: &Pubkey) -> Instruction {
+    let accounts = accounts::CancelEmergencyWithdraw {
+        relay_depository: vault.relay_depository,
+        owner: *owner,
+        vault: vault.address,
+        pending_emergency_withdraw: find_pending_emergency_withdraw_address(
+            &vault.program_id,
+            &vault.address,
+        )
+        .0,
+        vault_config: vault.config,
+        event_authority: find_event_authority_address(&vault.program_id).0,
+        program: vault.program_id,
+    };
+
+    Instruction {
+        program_id: vault.program_id,
+        accounts: to_account_metas(accounts, &vault.program_id),
+        data: instruction::CancelEmergencyWithdraw {}.data(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::ed25519_program;
+
+    fn transfer_request(token: Option<Pubkey>) -> TransferRequest {
+        TransferRequest {
+            domain: [1; 32],
+            recipient: Pubkey::new_unique(),
+            token,
+            amount: 1_000,
+            nonce: 1,
+            expiration: 1_700_000_000,
+            vault_address: find_vault_address(&relay_depository::ID).0,
+            reference: [2; 32],
+        }
+    }
+
+    #[test]
+    fn test_pda_program_ids() {
+        assert_eq!(RELAY_DEPOSITORY_PROGRAM_ID, relay_depository::ID);
+        assert_eq!(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            anchor_spl::associated_token::ID
+        );
+    }
+
+    #[test]
+    fn test_deposit_native_accounts() {
+        let sender = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+
+        let ix = deposit_native(
+            &RelayVault::default_vault(),
+            &sender,
+            &depositor,
+            1_000,
+            [3; 32],
+        );
+        let vault = find_vault_address(&relay_depository::ID).0;
+
+        assert_eq!(ix.program_id, relay_depository::ID);
+        assert_eq!(
+            ix.accounts[0].pubkey,
+            find_relay_depository_address(&relay_depository::ID).0
+        );
+        assert_eq!(ix.accounts[1].pubkey, sender);
+        assert!(ix.accounts[1].is_signer && ix.accounts[1].is_writable);
+        assert_eq!(ix.accounts[2].pubkey, depositor);
+        assert!(!ix.accounts[2].is_signer);
+        assert_eq!(ix.accounts[3].pubkey, vault);
+        assert_eq!(
+            ix.accounts[4].pubkey,
+            find_vault_ledger_address(&relay_depository::ID, &vault, None).0
+        );
+        // The missing vault config is replaced by the program id
+        assert_eq!(ix.accounts[6].pubkey, relay_depository::ID);
+    }
+
+    #[test]
+    fn test_execute_native_transfer_accounts() {
+        let executor = Pubkey::new_unique();
+        let request = transfer_request(None);
+
+        let ix = execute_transfer(
+            &RelayVault::default_vault(),
+            &executor,
+            &request,
+            &spl_token::ID,
+        );
+
+        assert_eq!(ix.accounts[2].pubkey, request.recipient);
+        // No mint nor token accounts for native transfers
+        for account in &ix.accounts[4..7] {
+            assert_eq!(account.pubkey, relay_depository::ID);
+        }
+        assert_eq!(
+            ix.accounts[7].pubkey,
+            find_used_request_address(&relay_depository::ID, &request.get_hash().to_bytes()).0
+        );
+    }
+
+    #[test]
+    fn test_execute_token_transfer_accounts() {
+        let executor = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let request = transfer_request(Some(mint));
+
+        let ix = execute_transfer(
+            &RelayVault::default_vault(),
+            &executor,
+            &request,
+            &spl_token::ID,
+        );
+
+        assert_eq!(ix.accounts[4].pubkey, mint);
+        assert_eq!(
+            ix.accounts[5].pubkey,
+            anchor_spl::associated_token::get_associated_token_address(&request.recipient, &mint)
+        );
+        assert_eq!(
+            ix.accounts[6].pubkey,
+            anchor_spl::associated_token::get_associated_token_address(
+                &request.vault_address,
+                &mint
+            )
+        );
+        assert_eq!(
+            ix.accounts[8].pubkey,
+            find_vault_ledger_address(&relay_depository::ID, &request.vault_address, Some(&mint)).0
+        );
+    }
+
+    #[test]
+    fn test_relay_vault_addresses() {
+        let default_vault = RelayVault::default_vault();
+        assert_eq!(
+            default_vault.address,
+            find_vault_address(&relay_depository::ID).0
+        );
+        assert_eq!(default_vault.config, None);
+
+        let instance =
+            find_relay_depository_instance_address(&relay_depository::ID, &Pubkey::new_unique(), 1)
+                .0;
+        let instance_vault = RelayVault::new(&relay_depository::ID, &instance, None);
+        assert_eq!(instance_vault.relay_depository, instance);
+        assert_eq!(
+            instance_vault.address,
+            find_instance_vault_address(&relay_depository::ID, &instance).0
+        );
+        assert_eq!(instance_vault.config, None);
+
+        let indexed_vault = RelayVault::new(&relay_depository::ID, &instance, Some(2));
+        assert_eq!(
+            indexed_vault.address,
+            find_indexed_vault_address(&relay_depository::ID, &instance, 2).0
+        );
+        assert_eq!(
+            indexed_vault.config,
+            Some(find_vault_config_address(&relay_depository::ID, &instance, 2).0)
+        );
+    }
+
+    #[test]
+    fn test_execute_transfer_from_instance_vault() {
+        let executor = Pubkey::new_unique();
+        let instance =
+            find_relay_depository_instance_address(&relay_depository::ID, &Pubkey::new_unique(), 1)
+                .0;
+        let vault = RelayVault::new(&relay_depository::ID, &instance, Some(2));
+        let request = TransferRequest {
+            vault_address: vault.address,
+            ..transfer_request(None)
+        };
+
+        let ix = execute_transfer(&vault, &executor, &request, &spl_token::ID);
+
+        assert_eq!(ix.accounts[0].pubkey, instance);
+        assert_eq!(ix.accounts[3].pubkey, vault.address);
+        assert_eq!(
+            ix.accounts[8].pubkey,
+            find_vault_ledger_address(&vault.program_id, &vault.address, None).0
+        );
+        assert_eq!(
+            ix.accounts[14].pubkey,
+            find_vault_config_address(&relay_depository::ID, &instance, 2).0
+        );
+    }
+
+    #[test]
+    fn test_deposit_native_into_other_deployment() {
+        let program_id = Pubkey::new_unique();
+        let instance =
+            find_relay_depository_instance_address(&program_id, &Pubkey::new_unique(), 1).0;
+        let vault = RelayVault::new(&program_id, &instance, None);
+
+        let ix = deposit_native(
+            &vault,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1_000,
+            [3; 32],
+        );
+
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(
+            vault.address,
+            find_instance_vault_address(&program_id, &instance).0
+        );
+        assert_eq!(
+            ix.accounts[4].pubkey,
+            find_vault_ledger_address(&program_id, &vault.address, None).0
+        );
+        // The missing vault config is replaced by the program id of the deployment
+        assert_eq!(ix.accounts[6].pubkey, program_id);
+        assert_eq!(
+            ix.accounts[7].pubkey,
+            find_event_authority_address(&program_id).0
+        );
+        assert_eq!(ix.accounts[8].pubkey, program_id);
+        assert!(ix
+            .accounts
+            .iter()
+            .all(|account| account.pubkey != relay_depository::ID));
+    }
+
+    #[test]
+    fn test_create_instance_accounts() {
+        let creator = Pubkey::new_unique();
+
+        let ix = create_instance(
+            &relay_depository::ID,
+            &creator,
+            7,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            "solana-mainnet".to_string(),
+        );
+        let instance = find_relay_depository_instance_address(&relay_depository::ID, &creator, 7).0;
+
+        assert_eq!(ix.accounts[0].pubkey, instance);
+        assert_eq!(
+            ix.accounts[1].pubkey,
+            find_instance_vault_address(&relay_depository::ID, &instance).0
+        );
+        assert_eq!(ix.accounts[2].pubkey, creator);
+        assert!(ix.accounts[2].is_signer && ix.accounts[2].is_writable);
+        assert_eq!(
+            RelayVault::new(&relay_depository::ID, &instance, None).address,
+            ix.accounts[1].pubkey
+        );
+    }
+
+    #[test]
+    fn test_execute_token_sweep_from_indexed_vault() {
+        let owner = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let relay_depository = find_relay_depository_address(&relay_depository::ID).0;
+        let vault = RelayVault::new(&relay_depository::ID, &relay_depository, Some(1));
+
+        let ix = execute_sweep(&vault, &owner, &treasury, Some(&mint), &spl_token::ID);
+
+        assert_eq!(ix.accounts[2].pubkey, vault.address);
+        assert_eq!(
+            ix.accounts[3].pubkey,
+            find_pending_sweep_address(&relay_depository::ID, &vault.address).0
+        );
+        assert_eq!(ix.accounts[4].pubkey, treasury);
+        assert_eq!(ix.accounts[5].pubkey, mint);
+        assert_eq!(
+            ix.accounts[6].pubkey,
+            anchor_spl::associated_token::get_associated_token_address(&treasury, &mint)
+        );
+        assert_eq!(
+            ix.accounts[7].pubkey,
+            anchor_spl::associated_token::get_associated_token_address(&vault.address, &mint)
+        );
+        assert_eq!(
+            ix.accounts[8].pubkey,
+            find_vault_ledger_address(&relay_depository::ID, &vault.address, Some(&mint)).0
+        );
+        assert_eq!(ix.accounts[11].pubkey, vault.config.unwrap());
+    }
+
+    #[test]
+    fn test_native_emergency_withdraw_accounts() {
+        let owner = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let vault = RelayVault::default_vault();
+
+        let ix = emergency_withdraw(
+            &vault,
+            &owner,
+            &recipient,
+            1_700_000_000,
+            None,
+            &spl_token::ID,
+        );
+
+        assert_eq!(
+            ix.accounts[3].pubkey,
+            find_pending_emergency_withdraw_address(&relay_depository::ID, &vault.address).0
+        );
+        assert_eq!(ix.accounts[4].pubkey, recipient);
+        assert!(ix.accounts[4].is_writable);
+        // No mint nor token accounts for native withdraws
+        for account in &ix.accounts[5..8] {
+            assert_eq!(account.pubkey, relay_depository::ID);
+        }
+        // The marker commits to the announcement
+        assert_eq!(
+            ix.accounts[9].pubkey,
+            find_executed_emergency_withdraw_address(
+                &relay_depository::ID,
+                &vault.address,
+                None,
+                1_700_000_000
+            )
+            .0
+        );
+        assert_ne!(
+            ix.accounts[9].pubkey,
+            emergency_withdraw(
+                &vault,
+                &owner,
+                &recipient,
+                1_700_000_001,
+                None,
+                &spl_token::ID
+            )
+            .accounts[9]
+                .pubkey
+        );
+    }
+
+    #[test]
+    fn test_execute_transfer_with_signature() {
+        let executor = Pubkey::new_unique();
+        let allocator = Pubkey::new_unique();
+        let request = transfer_request(None);
+
+        let [signature_ix, execute_ix] = execute_transfer_with_signature(
+            &RelayVault::default_vault(),
+            &executor,
+            &request,
+            &spl_token::ID,
+            &allocator,
+            &[4; 64],
+        );
+
+        assert_eq!(signature_ix.program_id, ed25519_program::ID);
+        assert_eq!(&signature_ix.data[16..48], allocator.as_ref());
+        assert_eq!(&signature_ix.data[112..], &request.get_hash().to_bytes());
+        assert_eq!(execute_ix.program_id, relay_depository::ID);
+    }
+}
@@ -0,0 +1,85 @@
+//! Ed25519 precompile instructions carrying allocator signatures
+//!
+//! The relay depository only accepts a single signature over a 32-byte message,
+//! with all the data inlined in the instruction: the header is followed by the
+//! public key at offset 16, the signature at offset 48 and the message at offset
+//! 112, for a total of 144 bytes.
+
+use anchor_lang::solana_program::{ed25519_program, instruction::Instruction, pubkey::Pubkey};
+
+/// Offset of the public key in the instruction data
+pub const PUBKEY_OFFSET: u16 = 16;
+
+/// Offset of the signature in the instruction data
+pub const SIGNATURE_OFFSET: u16 = 48;
+
+/// Offset of the message in the instruction data
+pub const MESSAGE_OFFSET: u16 = 112;
+
+/// Length of the signed message, a request hash
+pub const MESSAGE_LEN: u16 = 32;
+
+/// Length of the instruction data
+pub const DATA_LEN: usize = 144;
+
+/// Instruction index referring to the instruction itself
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Builds the ed25519 instruction verifying the signature of a message by a signer
+pub fn new_ed25519_instruction(
+    signer: &Pubkey,
+    signature: &[u8; 64],
+    message: &[u8; 32],
+) -> Instruction {
+    let mut data = Vec::with_capacity(DATA_LEN);
+
+    // Header
+    data.push(1); // Number of signatures
+    data.push(0); // Padding
+    data.extend_from_slice(&SIGNATURE_OFFSET.to_le_bytes());
+    data.extend_from_slice(&CURRENT_INSTRUCTION.to_le_bytes());
+    data.extend_from_slice(&PUBKEY_OFFSET.to_le_bytes());
+    data.extend_from_slice(&CURRENT_INSTRUCTION.to_le_bytes());
+    data.extend_from_slice(&MESSAGE_OFFSET.to_le_bytes());
+    data.extend_from_slice(&MESSAGE_LEN.to_le_bytes());
+    data.extend_from_slice(&CURRENT_INSTRUCTION.to_le_bytes());
+
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let signer = Pubkey::new_unique();
+        let signature = [7u8; 64];
+        let message = [9u8; 32];
+
+        let ix = new_ed25519_instruction(&signer, &signature, &message);
+
+        assert_eq!(ix.program_id, ed25519_program::ID);
+        assert!(ix.accounts.is_empty());
+        assert_eq!(ix.data.len(), DATA_LEN);
+        assert_eq!(&ix.data[..2], &[1, 0]);
+        assert_eq!(&ix.data[2..4], &SIGNATURE_OFFSET.to_le_bytes());
+        assert_eq!(&ix.data[4..6], &u16::MAX.to_le_bytes());
+        assert_eq!(&ix.data[6..8], &PUBKEY_OFFSET.to_le_bytes());
+        assert_eq!(&ix.data[8..10], &u16::MAX.to_le_bytes());
+        assert_eq!(&ix.data[10..12], &MESSAGE_OFFSET.to_le_bytes());
+        assert_eq!(&ix.data[12..14], &MESSAGE_LEN.to_le_bytes());
+        assert_eq!(&ix.data[14..16], &u16::MAX.to_le_bytes());
+        assert_eq!(&ix.data[16..48], signer.as_ref());
+        assert_eq!(&ix.data[48..112], &signature);
+        assert_eq!(&ix.data[112..], &message);
+    }
+}
@@ -0,0 +1,842 @@
+//! Instruction builders for the relay forwarder program
+//!
+//! The built instructions forward from (or recover out of) a `Forwarder` to the main
+//! vault of the relay depository it forwards to, either the forwarder of a relay
+//! depository of a relay depository deployment or the legacy forwarder. The
+//! configuration and forward minimum builders take the accounts they apply to.
+
+use anchor_lang::{
+    prelude::AccountMeta,
+    solana_program::{instruction::Instruction, pubkey::Pubkey, system_program},
+    InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    token::{spl_token, ID as TOKEN_PROGRAM_ID},
+    token_2022::{spl_token_2022, ID as TOKEN_2022_PROGRAM_ID},
+};
+use relay_forwarder::{accounts, instruction, pda::*, CrankReward};
+
+use crate::depository::find_program_data_address;
+
+/// A forwarder, and the relay depository and main vault it forwards to, the accounts
+/// resolved by the forward builders
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Forwarder {
+    /// The forwarder PDA
+    pub address: Pubkey,
+    /// The program id of the relay depository deployment
+    pub relay_depository_program: Pubkey,
+    /// The relay depository the forwarder forwards to
+    pub relay_depository: Pubkey,
+    /// The main vault of the relay depository
+    pub relay_vault: Pubkey,
+}
+
+impl Forwarder {
+    /// Returns the forwarder of a relay depository of a deployment
+    pub fn new(relay_depository_program: &Pubkey, relay_depository: &Pubkey) -> Self {
+        Self {
+            address: find_forwarder_address(
+                &relay_forwarder::ID,
+                relay_depository_program,
+                relay_depository,
+            )
+            .0,
+            relay_depository_program: *relay_depository_program,
+            relay_depository: *relay_depository,
+            relay_vault: find_relay_vault_addresses(
+                relay_depository_program,
+                relay_depository,
+                None,
+            )
+            .0,
+        }
+    }
+
+    /// Returns the legacy forwarder, whose seeds don't commit to a relay depository and
+    /// which forwards to the default relay depository of the main deployment
+    ///
+    /// Balances sent to the legacy forwarder address can only be forwarded from it.
+    pub fn legacy() -> Self {
+        let relay_depository = find_relay_depository_address(&relay_depository::ID).0;
+
+        Self {
+            address: find_legacy_forwarder_address(&relay_forwarder::ID).0,
+            ..Self::new(&relay_depository::ID, &relay_depository)
+        }
+    }
+}
+
+/// A token forwarded by `forward_token` or `forward_all`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ForwardedToken {
+    /// The mint of the token
+    pub mint: Pubkey,
+    /// The token program of the mint
+    pub token_program: Pubkey,
+    /// Whether the mint has the Token-2022 transfer fee extension, in which case it is
+    /// passed as writable so that the withheld fees can be harvested before closing the
+    /// forwarder token account
+    pub has_transfer_fee: bool,
+}
+
+impl ForwardedToken {
+    /// Returns the account meta of the mint, writable only for transfer fee mints
+    fn mint_meta(&self) -> AccountMeta {
+        if self.has_transfer_fee {
+            AccountMeta::new(self.mint, false)
+        } else {
+            AccountMeta::new_readonly(self.mint, false)
+        }
+    }
+}
+
+/// Returns the address and bump of the forwarder of the default relay depository of a
+/// relay depository deployment
+pub fn find_default_forwarder_address(relay_depository_program: &Pubkey) -> (Pubkey, u8) {
+    find_forwarder_address(
+        &relay_forwarder::ID,
+        relay_depository_program,
+        &find_relay_depository_address(relay_depository_program).0,
+    )
+}
+
+/// Returns the mint a forwarded mint is deposited as, None for wrapped native mints
+/// which are deposited as native tokens
+fn deposited_mint(mint: &Pubkey) -> Option<&Pubkey> {
+    let is_native_mint =
+        *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID;
+
+    (!is_native_mint).then_some(mint)
+}
+
+/// Returns the ledger of the vault credited when forwarding a mint, the native
+/// ledger for wrapped native mints
+fn find_forwarded_ledger_address(forwarder: &Forwarder, mint: &Pubkey) -> Pubkey {
+    find_vault_ledger_address(
+        &forwarder.relay_depository_program,
+        &forwarder.relay_vault,
+        deposited_mint(mint),
+    )
+    .0
+}
+
+/// Returns the forward minimum applying when forwarding a mint, the native forward
+/// minimum for wrapped native mints
+fn find_forwarded_minimum_address(forwarder: &Forwarder, mint: &Pubkey) -> Pubkey {
+    find_forward_minimum_address(
+        &relay_forwarder::ID,
+        &forwarder.relay_depository,
+        deposited_mint(mint),
+    )
+    .0
+}
+
+/// Builds a `forward_native` instruction, crediting `depositor` with the native
+/// balance of a forwarder
+pub fn forward_native(
+    forwarder: &Forwarder,
+    sender: &Pubkey,
+    depositor: &Pubkey,
+    id: [u8; 32],
+) -> Instruction {
+    let accounts = accounts::ForwardNative {
+        sender: *sender,
+        depositor: *depositor,
+        forwarder: forwarder.address,
+        relay_depository: forwarder.relay_depository,
+        relay_vault: forwarder.relay_vault,
+        relay_vault_ledger: find_vault_ledger_address(
+            &forwarder.relay_depository_program,
+            &forwarder.relay_vault,
+            None,
+        )
+        .0,
+        config: find_forwarder_config_address(&relay_forwarder::ID, &forwarder.address).0,
+        forward_minimum: find_forward_minimum_address(
+            &relay_forwarder::ID,
+            &forwarder.relay_depository,
+            None,
+        )
+        .0,
+        relay_depository_program: forwarder.relay_depository_program,
+        relay_depository_event_authority: find_event_authority_address(
+            &forwarder.relay_depository_program,
+        )
+        .0,
+        system_program: system_program::ID,
+        event_authority: find_forwarder_event_authority_address(&relay_forwarder::ID).0,
+        program: relay_forwarder::ID,
+    };
+
+    Instruction {
+        program_id: relay_forwarder::ID,
+        accounts: accounts.to_account_metas(None),
+        data: instruction::ForwardNative { id }.data(),
+    }
+}
+
+/// Builds a `forward_token` instruction, crediting `depositor` with the balance of
+/// the associated token account of a forwarder
+///
+/// The `rent_destination` must match the configuration of the forwarder (None for the
+/// depositor), and the `sender_token_account` is only needed if a crank reward
+/// is paid.
+pub fn forward_token(
+    forwarder: &Forwarder,
+    sender: &Pubkey,
+    depositor: &Pubkey,
+    token: &ForwardedToken,
+    rent_destination: Option<&Pubkey>,
+    sender_token_account: Option<Pubkey>,
+    id: [u8; 32],
+) -> Instruction {
+    let ForwardedToken {
+        mint,
+        token_program,
+        ..
+    } = token;
+    let accounts = accounts::ForwardToken {
+        sender: *sender,
+        depositor: *depositor,
+        forwarder: forwarder.address,
+        relay_depository: forwarder.relay_depository,
+        relay_vault: forwarder.relay_vault,
+        mint: *mint,
+        forwarder_token_account: find_forwarder_token_address(
+            &forwarder.address,
+            mint,
+            token_program,
+        ),
+        relay_vault_token_account: find_vault_token_address(
+            &forwarder.relay_vault,
+            mint,
+            token_program,
+        ),
+        config: find_forwarder_config_address(&relay_forwarder::ID, &forwarder.address).0,
+        rent_recipient: rent_destination.copied().unwrap_or(*depositor),
+        relay_vault_ledger: find_forwarded_ledger_address(forwarder, mint),
+        sender_token_account,
+        forward_minimum: find_forwarded_minimum_address(forwarder, mint),
+        relay_depository_program: forwarder.relay_depository_program,
+        relay_depository_event_authority: find_event_authority_address(
+            &forwarder.relay_depository_program,
+        )
+        .0,
+        token_program: *token_program,
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: system_program::ID,
+        event_authority: find_forwarder_event_authority_address(&relay_forwarder::ID).0,
+        program: relay_forwarder::ID,
+    };
+
+    let mut account_metas = accounts.to_account_metas(None);
+    account_metas[5] = token.mint_meta();
+
+    Instruction {
+        program_id: relay_forwarder::ID,
+        accounts: account_metas,
+        data: instruction::ForwardToken { id }.data(),
+    }
+}
+
+/// Builds a `forward_all` instruction, crediting `depositor` with the balances of the
+/// associated token accounts of a forwarder for the given tokens, and its native
+/// balance if `include_native` is set
+///
+/// The `rent_destination` must match the configuration of the forwarder (None for the
+/// depositor), and the crank rewards are paid to the associated token accounts
+/// of the sender.
+pub fn forward_all(
+    forwarder: &Forwarder,
+    sender: &Pubkey,
+    depositor: &Pubkey,
+    tokens: &[ForwardedToken],
+    include_native: bool,
+    rent_destination: Option<&Pubkey>,
+    id: [u8; 32],
+) -> Instruction {
+    let accounts = accounts::ForwardAll {
+        sender: *sender,
+        depositor: *depositor,
+        forwarder: forwarder.address,
+        relay_depository: forwarder.relay_depository,
+        relay_vault: forwarder.relay_vault,
+        relay_vault_ledger: find_vault_ledger_address(
+            &forwarder.relay_depository_program,
+            &forwarder.relay_vault,
+            None,
+        )
+        .0,
+        config: find_forwarder_config_address(&relay_forwarder::ID, &forwarder.address).0,
+        rent_recipient: rent_destination.copied().unwrap_or(*depositor),
+        native_forward_minimum: find_forward_minimum_address(
+            &relay_forwarder::ID,
+            &forwarder.relay_depository,
+            None,
+        )
+        .0,
+        relay_depository_program: forwarder.relay_depository_program,
+        relay_depository_event_authority: find_event_authority_address(
+            &forwarder.relay_depository_program,
+        )
+        .0,
+        token_program: TOKEN_PROGRAM_ID,
+        token_2022_program: TOKEN_2022_PROGRAM_ID,
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: system_program::ID,
+        event_authority: find_forwarder_event_authority_address(&relay_forwarder::ID).0,
+        program: relay_forwarder::ID,
+    };
+
+    let mut account_metas = accounts.to_account_metas(None);
+    for token in tokens {
+        let ForwardedToken {
+            mint,
+            token_program,
+            ..
+        } = token;
+        account_metas.extend([
+            token.mint_meta(),
+            AccountMeta::new(
+                find_forwarder_token_address(&forwarder.address, mint, token_program),
+                false,
+            ),
+            AccountMeta::new(
+                find_vault_token_address(&forwarder.relay_vault, mint, token_program),
+                false,
+            ),
+            AccountMeta::new(find_forwarded_ledger_address(forwarder, mint), false),
+            AccountMeta::new(
+                find_associated_token_address(sender, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(find_forwarded_minimum_address(forwarder, mint), false),
+        ]);
+    }
+
+    Instruction {
+        program_id: relay_forwarder::ID,
+        accounts: account_metas,
+        data: instruction::ForwardAll { id, include_native }.data(),
+    }
+}
+
+/// Builds a `set_config` instruction configuring a forwarder, signed by the upgrade
+/// authority of the forwarder program who pays for the configuration on first use
+pub fn set_config(
+    upgrade_authority: &Pubkey,
+    forwarder: &Pubkey,
+    rent_destination: Option<&Pubkey>,
+    crank_reward: CrankReward,
+) -> Instruction {
+    let accounts = accounts::SetConfig {
+        authority: *upgrade_authority,
+        forwarder: *forwarder,
+        config: find_forwarder_config_address(&relay_forwarder::ID, forwarder).0,
+        program: relay_forwarder::ID,
+        program_data: find_program_data_address(&relay_forwarder::ID),
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: relay_forwarder::ID,
+        accounts: accounts.to_account_metas(None),
+        data: instruction::SetConfig {
+            rent_destination: rent_destination.copied(),
+            crank_reward,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `set_forward_minimum` instruction setting the forward minimum of a mint for
+/// a relay depository of a deployment, signed by the owner of the relay depository who
+/// pays for it on first use
+pub fn set_forward_minimum(
+    relay_depository_program: &Pubkey,
+    relay_depository: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    minimum_amount: u64,
+    fixed_token_reward: u64,
+) -> Instruction {
+    let accounts = accounts::SetForwardMinimum {
+        authority: *owner,
+        relay_depository_program: *relay_depository_program,
+        relay_depository: *relay_depository,
+        forward_minimum: find_forward_minimum_address(
+            &relay_forwarder::ID,
+            relay_depository,
+            Some(mint),
+        )
+        .0,
+        system_program: system_program::ID,
+    };
+
+    Instruction {
+        program_id: relay_forwarder::ID,
+        accounts: accounts.to_account_metas(None),
+        data: instruction::SetForwardMinimum {
+            mint: *mint,
+            minimum_amount,
+            fixed_token_reward,
+        }
+        .data(),
+    }
+}
+
+/// Builds an `announce_recover` instruction announcing the recovery of a token account
+/// of a forwarder to `recipient`, signed by the owner of the relay depository or by the
+/// recipient, who pays for the pending recovery
+pub fn announce_recover(
+    forwarder: &Forwarder,
+    authority: &Pubkey,
+    forwarder_token_account: &Pubkey,
+    recipient: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::AnnounceRecover {
+        authority: *authority,
+        forwarder: forwarder.address,
+        relay_depository_program: forwarder.relay_depository_program,
+        relay_depository: forwarder.relay_depository,
+        forwarder_token_account: *forwarder_token_account,
+        pending_recover: find_pending_recover_address(
+            &relay_forwarder::ID,
+            forwarder_token_account,
+        )
+        .0,
+        system_program: system_program::ID,
+        event_authority: find_forwarder_event_authority_address(&relay_forwarder::ID).0,
+        program: relay_forwarder::ID,
+    };
+
+    Instruction {
+        program_id: relay_forwarder::ID,
+        accounts: accounts.to_account_metas(None),
+        data: instruction::AnnounceRecover {
+            recipient: *recipient,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `cancel_recover` instruction dropping the pending recovery of a token
+/// account of a forwarder, signed by the owner of the relay depository or by the
+/// recipient
+pub fn cancel_recover(
+    forwarder: &Forwarder,
+    authority: &Pubkey,
+    forwarder_token_account: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::CancelRecover {
+        authority: *authority,
+        forwarder: forwarder.address,
+        relay_depository_program: forwarder.relay_depository_program,
+        relay_depository: forwarder.relay_depository,
+        forwarder_token_account: *forwarder_token_account,
+        pending_recover: find_pending_recover_address(
+            &relay_forwarder::ID,
+            forwarder_token_account,
+        )
+        .0,
+        event_authority: find_forwarder_event_authority_address(&relay_forwarder::ID).0,
+        program: relay_forwarder::ID,
+    };
+
+    Instruction {
+        program_id: relay_forwarder::ID,
+        accounts: accounts.to_account_metas(None),
+        data: instruction::CancelRecover {}.data(),
+    }
+}
+
+/// Builds a `recover` instruction sending the balance of a token account of a
+/// forwarder to the associated token account of the announced `recipient`, signed by
+/// the owner of the relay depository or by the recipient
+pub fn recover(
+    forwarder: &Forwarder,
+    authority: &Pubkey,
+    forwarder_token_account: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let accounts = accounts::Recover {
+        authority: *authority,
+        forwarder: forwarder.address,
+        relay_depository_program: forwarder.relay_depository_program,
+        relay_depository: forwarder.relay_depository,
+        mint: *mint,
+        forwarder_token_account: *forwarder_token_account,
+        pending_recover: find_pending_recover_address(
+            &relay_forwarder::ID,
+            forwarder_token_account,
+        )
+        .0,
+        recipient: *recipient,
+        recipient_token_account: find_associated_token_address(recipient, mint, token_program),
+        token_program: *token_program,
+        event_authority: find_forwarder_event_authority_address(&relay_forwarder::ID).0,
+        program: relay_forwarder::ID,
+    };
+
+    Instruction {
+        program_id: relay_forwarder::ID,
+        accounts: accounts.to_account_metas(None),
+        data: instruction::Recover {}.data(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pda_program_id() {
+        assert_eq!(RELAY_FORWARDER_PROGRAM_ID, relay_forwarder::ID);
+    }
+
+    #[test]
+    fn test_forward_native_accounts() {
+        let sender = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+
+        let ix = forward_native(
+            &Forwarder::new(
+                &relay_depository::ID,
+                &find_relay_depository_address(&relay_depository::ID).0,
+            ),
+            &sender,
+            &depositor,
+            [1; 32],
+        );
+
+        assert_eq!(ix.program_id, relay_forwarder::ID);
+        assert_eq!(ix.accounts[0].pubkey, sender);
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(
+            ix.accounts[2].pubkey,
+            find_default_forwarder_address(&relay_depository::ID).0
+        );
+        assert!(ix.accounts[2].is_writable);
+        assert_eq!(
+            ix.accounts[4].pubkey,
+            find_vault_address(&relay_depository::ID).0
+        );
+        assert_eq!(
+            ix.accounts[5].pubkey,
+            find_vault_ledger_address(
+                &relay_depository::ID,
+                &find_vault_address(&relay_depository::ID).0,
+                None
+            )
+            .0
+        );
+        assert_eq!(ix.accounts[8].pubkey, relay_depository::ID);
+        assert_eq!(
+            ix.accounts[9].pubkey,
+            find_event_authority_address(&relay_depository::ID).0
+        );
+        assert_eq!(
+            ix.accounts[11].pubkey,
+            find_forwarder_event_authority_address(&relay_forwarder::ID).0
+        );
+        assert_eq!(ix.accounts[12].pubkey, relay_forwarder::ID);
+    }
+
+    #[test]
+    fn test_forward_native_from_instance_forwarder() {
+        let instance =
+            find_relay_depository_instance_address(&relay_depository::ID, &Pubkey::new_unique(), 1)
+                .0;
+
+        let ix = forward_native(
+            &Forwarder::new(&relay_depository::ID, &instance),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            [1; 32],
+        );
+
+        assert_eq!(
+            ix.accounts[2].pubkey,
+            find_forwarder_address(&relay_forwarder::ID, &relay_depository::ID, &instance).0
+        );
+        assert_eq!(ix.accounts[3].pubkey, instance);
+        assert_eq!(
+            ix.accounts[4].pubkey,
+            find_instance_vault_address(&relay_depository::ID, &instance).0
+        );
+    }
+
+    #[test]
+    fn test_forward_from_legacy_forwarder() {
+        let sender = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let forwarder = Forwarder::legacy();
+
+        let ix = forward_token(
+            &forwarder,
+            &sender,
+            &Pubkey::new_unique(),
+            &ForwardedToken {
+                mint,
+                token_program: TOKEN_PROGRAM_ID,
+                has_transfer_fee: false,
+            },
+            None,
+            None,
+            [1; 32],
+        );
+
+        // The legacy forwarder forwards to the default relay depository
+        let legacy_forwarder = find_legacy_forwarder_address(&relay_forwarder::ID).0;
+        assert_eq!(ix.accounts[2].pubkey, legacy_forwarder);
+        assert_eq!(
+            ix.accounts[3].pubkey,
+            find_relay_depository_address(&relay_depository::ID).0
+        );
+        assert_eq!(
+            ix.accounts[4].pubkey,
+            find_vault_address(&relay_depository::ID).0
+        );
+        assert_eq!(
+            ix.accounts[6].pubkey,
+            find_forwarder_token_address(&legacy_forwarder, &mint, &TOKEN_PROGRAM_ID)
+        );
+        assert_eq!(
+            ix.accounts[8].pubkey,
+            find_forwarder_config_address(&relay_forwarder::ID, &legacy_forwarder).0
+        );
+        assert_eq!(ix.accounts[13].pubkey, relay_depository::ID);
+
+        let ix = forward_native(&forwarder, &sender, &Pubkey::new_unique(), [1; 32]);
+        assert_eq!(ix.accounts[2].pubkey, legacy_forwarder);
+        assert_eq!(
+            ix.accounts[6].pubkey,
+            find_forwarder_config_address(&relay_forwarder::ID, &legacy_forwarder).0
+        );
+    }
+
+    #[test]
+    fn test_forward_token_into_other_deployment() {
+        let program_id = Pubkey::new_unique();
+        let instance =
+            find_relay_depository_instance_address(&program_id, &Pubkey::new_unique(), 1).0;
+        let mint = Pubkey::new_unique();
+
+        let ix = forward_token(
+            &Forwarder::new(&program_id, &instance),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &ForwardedToken {
+                mint,
+                token_program: TOKEN_PROGRAM_ID,
+                has_transfer_fee: false,
+            },
+            None,
+            None,
+            [1; 32],
+        );
+
+        let vault = find_instance_vault_address(&program_id, &instance).0;
+        assert_eq!(
+            ix.accounts[2].pubkey,
+            find_forwarder_address(&relay_forwarder::ID, &program_id, &instance).0
+        );
+        assert_eq!(ix.accounts[4].pubkey, vault);
+        assert_eq!(
+            ix.accounts[10].pubkey,
+            find_vault_ledger_address(&program_id, &vault, Some(&mint)).0
+        );
+        assert_eq!(ix.accounts[13].pubkey, program_id);
+        assert_eq!(
+            ix.accounts[14].pubkey,
+            find_event_authority_address(&program_id).0
+        );
+    }
+
+    #[test]
+    fn test_forward_wrapped_native_uses_native_ledger() {
+        let sender = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+        let relay_depository = find_relay_depository_address(&relay_depository::ID).0;
+
+        let ix = forward_token(
+            &Forwarder::new(&relay_depository::ID, &relay_depository),
+            &sender,
+            &depositor,
+            &ForwardedToken {
+                mint: spl_token::native_mint::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                has_transfer_fee: false,
+            },
+            None,
+            None,
+            [1; 32],
+        );
+
+        assert_eq!(ix.accounts[5].pubkey, spl_token::native_mint::ID);
+        assert!(!ix.accounts[5].is_writable);
+        // The rent goes to the depositor without a configured rent destination
+        assert_eq!(ix.accounts[9].pubkey, depositor);
+        assert_eq!(
+            ix.accounts[10].pubkey,
+            find_vault_ledger_address(
+                &relay_depository::ID,
+                &find_vault_address(&relay_depository::ID).0,
+                None
+            )
+            .0
+        );
+        // The missing sender token account is replaced by the program id
+        assert_eq!(ix.accounts[11].pubkey, relay_forwarder::ID);
+        // Wrapped native tokens are forwarded under the native forward minimum
+        assert_eq!(
+            ix.accounts[12].pubkey,
+            find_forward_minimum_address(&relay_forwarder::ID, &relay_depository, None).0
+        );
+    }
+
+    #[test]
+    fn test_forward_token_mint_writability() {
+        let forwarder = Forwarder::new(
+            &relay_depository::ID,
+            &find_relay_depository_address(&relay_depository::ID).0,
+        );
+        let forward = |token_program, has_transfer_fee| {
+            forward_token(
+                &forwarder,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &ForwardedToken {
+                    mint: Pubkey::new_unique(),
+                    token_program,
+                    has_transfer_fee,
+                },
+                None,
+                None,
+                [1; 32],
+            )
+        };
+
+        // Only transfer fee mints are write-locked
+        assert!(!forward(TOKEN_PROGRAM_ID, false).accounts[5].is_writable);
+        assert!(!forward(TOKEN_2022_PROGRAM_ID, false).accounts[5].is_writable);
+        assert!(forward(TOKEN_2022_PROGRAM_ID, true).accounts[5].is_writable);
+    }
+
+    #[test]
+    fn test_set_config_accounts() {
+        let upgrade_authority = Pubkey::new_unique();
+        let forwarder = find_default_forwarder_address(&relay_depository::ID).0;
+
+        let ix = set_config(
+            &upgrade_authority,
+            &forwarder,
+            None,
+            CrankReward::Fixed(5_000),
+        );
+
+        assert_eq!(ix.accounts[0].pubkey, upgrade_authority);
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(ix.accounts[1].pubkey, forwarder);
+        assert_eq!(
+            ix.accounts[2].pubkey,
+            find_forwarder_config_address(&relay_forwarder::ID, &forwarder).0
+        );
+        assert_eq!(ix.accounts[3].pubkey, relay_forwarder::ID);
+        assert_eq!(
+            ix.accounts[4].pubkey,
+            find_program_data_address(&relay_forwarder::ID)
+        );
+    }
+
+    #[test]
+    fn test_recover_accounts() {
+        let instance =
+            find_relay_depository_instance_address(&relay_depository::ID, &Pubkey::new_unique(), 1)
+                .0;
+        let forwarder = Forwarder::new(&relay_depository::ID, &instance);
+        let recipient = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let forwarder_token_account =
+            find_forwarder_token_address(&forwarder.address, &mint, &TOKEN_PROGRAM_ID);
+        let pending_recover =
+            find_pending_recover_address(&relay_forwarder::ID, &forwarder_token_account).0;
+
+        // A depositor announces the recovery to itself
+        let ix = announce_recover(&forwarder, &recipient, &forwarder_token_account, &recipient);
+        assert_eq!(ix.accounts[1].pubkey, forwarder.address);
+        assert_eq!(ix.accounts[2].pubkey, relay_depository::ID);
+        assert_eq!(ix.accounts[3].pubkey, instance);
+        assert_eq!(ix.accounts[5].pubkey, pending_recover);
+
+        let ix = recover(
+            &forwarder,
+            &recipient,
+            &forwarder_token_account,
+            &recipient,
+            &mint,
+            &TOKEN_PROGRAM_ID,
+        );
+        assert_eq!(ix.accounts[4].pubkey, mint);
+        assert_eq!(ix.accounts[5].pubkey, forwarder_token_account);
+        assert_eq!(ix.accounts[6].pubkey, pending_recover);
+        assert_eq!(ix.accounts[7].pubkey, recipient);
+        assert_eq!(
+            ix.accounts[8].pubkey,
+            anchor_spl::associated_token::get_associated_token_address(&recipient, &mint)
+        );
+        assert_eq!(
+            ix.accounts[10].pubkey,
+            find_forwarder_event_authority_address(&relay_forwarder::ID).0
+        );
+    }
+
+    #[test]
+    fn test_forward_all_token_groups() {
+        let sender = Pubkey::new_unique();
+        let depositor = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mint_2022 = Pubkey::new_unique();
+
+        let ix = forward_all(
+            &Forwarder::new(
+                &relay_depository::ID,
+                &find_relay_depository_address(&relay_depository::ID).0,
+            ),
+            &sender,
+            &depositor,
+            &[
+                ForwardedToken {
+                    mint,
+                    token_program: TOKEN_PROGRAM_ID,
+                    has_transfer_fee: false,
+                },
+                ForwardedToken {
+                    mint: mint_2022,
+                    token_program: TOKEN_2022_PROGRAM_ID,
+                    has_transfer_fee: true,
+                },
+            ],
+            true,
+            None,
+            [1; 32],
+        );
+
+        assert_eq!(ix.accounts.len(), 17 + 2 * 6);
+        assert_eq!(ix.accounts[17].pubkey, mint);
+        assert!(!ix.accounts[17].is_writable);
+        assert_eq!(ix.accounts[23].pubkey, mint_2022);
+        assert!(ix.accounts[23].is_writable);
+        assert_eq!(
+            ix.accounts[24].pubkey,
+            find_forwarder_token_address(
+                &find_default_forwarder_address(&relay_depository::ID).0,
+                &mint_2022,
+                &TOKEN_2022_PROGRAM_ID
+            )
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! Typed instruction builders for the relay depository and relay forwarder programs
+//!
+//! Every account of the built instructions is resolved from the seeds of the
+//! `pda` modules of the programs, for the relay depository and vault given to the
+//! builders (see `depository::RelayVault`).
+//!
+//! The builders cover the relay depository setup, deposits, transfers and the
+//! treasury, sweep and emergency withdraw flows, and every forwarder instruction.
+//! The quoted deposits, swaps, direct deposit registrations and the domain
+//! separator migration still have to be built from the `accounts` and
+//! `instruction` modules of the program.
+
+pub mod depository;
+pub mod ed25519;
+pub mod forwarder;
+
+pub use relay_depository;
+pub use relay_forwarder;
@@ -0,0 +1,36 @@
+//! Errors of the event decoding and indexing
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Thrown when the transaction metadata cannot be parsed
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+
+    /// Thrown when a `Program data:` log is not valid base64
+    #[error("Invalid log data: {0}")]
+    InvalidLogData(String),
+
+    /// Thrown when the data of an instruction is not valid base58
+    #[error("Invalid instruction data: {0}")]
+    InvalidInstructionData(String),
+
+    /// Thrown when the data of an event does not match its discriminator
+    #[error("Invalid {name}: {message}")]
+    InvalidEvent { name: &'static str, message: String },
+
+    /// Thrown when a sink fails to store events
+    #[error("Sink error: {0}")]
+    Sink(String),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::InvalidTransaction(error.to_string())
+    }
+}
@@ -0,0 +1,210 @@
+//! Typed events of the relay depository and relay forwarder programs
+
+use anchor_lang::{prelude::Pubkey, AnchorDeserialize, Discriminator, Event};
+use relay_depository::{
+    AllocatorUpdatedEvent, DepositEvent, EmergencyWithdrawAnnouncedEvent,
+    EmergencyWithdrawCancelledEvent, EmergencyWithdrawExecutedEvent, InstanceCreatedEvent,
+    OwnerUpdatedEvent, SwapExecutedEvent, SweepCancelledEvent, SweepExecutedEvent,
+    SweepRequestedEvent, TransferExecutedEvent, TreasuryUpdateCancelledEvent,
+    TreasuryUpdateRequestedEvent, TreasuryUpdatedEvent,
+};
+use relay_forwarder::{ForwardedEvent, RecoverAnnouncedEvent, RecoverCancelledEvent, RecoverEvent};
+
+use crate::Error;
+
+/// Length of the discriminator prefixing the event data
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// Relay program emitting events
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RelayProgram {
+    RelayDepository,
+    RelayForwarder,
+}
+
+impl RelayProgram {
+    /// Returns the relay program of a program id, the relay depository being the
+    /// deployment of `relay_depository_program`
+    pub fn from_program_id(relay_depository_program: &Pubkey, program_id: &Pubkey) -> Option<Self> {
+        if program_id == relay_depository_program {
+            Some(Self::RelayDepository)
+        } else if *program_id == relay_forwarder::ID {
+            Some(Self::RelayForwarder)
+        } else {
+            None
+        }
+    }
+}
+
+macro_rules! relay_events {
+    ($($program:ident { $($variant:ident($event:ident),)* })*) => {
+        /// Event of the relay depository or relay forwarder program
+        #[derive(Clone, PartialEq, Debug)]
+        pub enum RelayEvent {
+            $($($variant($event),)*)*
+        }
+
+        impl RelayEvent {
+            /// Decodes the data of an event emitted by a relay program, the event
+            /// discriminator followed by the serialized event
+            ///
+            /// Returns None for unknown discriminators, and an error if the data
+            /// does not match the event of its discriminator.
+            pub fn decode(program: RelayProgram, data: &[u8]) -> Result<Option<Self>, Error> {
+                if data.len() < DISCRIMINATOR_LEN {
+                    return Ok(None);
+                }
+                let (discriminator, payload) = data.split_at(DISCRIMINATOR_LEN);
+
+                $(
+                    if program == RelayProgram::$program {
+                        $(
+                            if discriminator == $event::DISCRIMINATOR {
+                                return $event::try_from_slice(payload)
+                                    .map(|event| Some(Self::$variant(event)))
+                                    .map_err(|e| Error::InvalidEvent {
+                                        name: stringify!($event),
+                                        message: e.to_string(),
+                                    });
+                            }
+                        )*
+                    }
+                )*
+
+                Ok(None)
+            }
+
+            /// Returns the name of the event
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($(Self::$variant(_) => stringify!($event),)*)*
+                }
+            }
+
+            /// Returns the relay program emitting the event
+            pub fn program(&self) -> RelayProgram {
+                match self {
+                    $($(Self::$variant(_) => RelayProgram::$program,)*)*
+                }
+            }
+
+            /// Returns the data of the event, the event discriminator followed by
+            /// the serialized event
+            pub fn data(&self) -> Vec<u8> {
+                match self {
+                    $($(Self::$variant(event) => event.data(),)*)*
+                }
+            }
+        }
+    };
+}
+
+relay_events! {
+    RelayDepository {
+        InstanceCreated(InstanceCreatedEvent),
+        OwnerUpdated(OwnerUpdatedEvent),
+        AllocatorUpdated(AllocatorUpdatedEvent),
+        TransferExecuted(TransferExecutedEvent),
+        SwapExecuted(SwapExecutedEvent),
+        Deposit(DepositEvent),
+        TreasuryUpdateRequested(TreasuryUpdateRequestedEvent),
+        TreasuryUpdated(TreasuryUpdatedEvent),
+        TreasuryUpdateCancelled(TreasuryUpdateCancelledEvent),
+        SweepRequested(SweepRequestedEvent),
+        SweepExecuted(SweepExecutedEvent),
+        SweepCancelled(SweepCancelledEvent),
+        EmergencyWithdrawAnnounced(EmergencyWithdrawAnnouncedEvent),
+        EmergencyWithdrawExecuted(EmergencyWithdrawExecutedEvent),
+        EmergencyWithdrawCancelled(EmergencyWithdrawCancelledEvent),
+    }
+    RelayForwarder {
+        Forwarded(ForwardedEvent),
+        RecoverAnnounced(RecoverAnnouncedEvent),
+        Recover(RecoverEvent),
+        RecoverCancelled(RecoverCancelledEvent),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit_event() -> DepositEvent {
+        DepositEvent {
+            depositor: Pubkey::new_unique(),
+            token: Some(Pubkey::new_unique()),
+            amount: 1_000,
+            id: [1; 32],
+            relay_depository: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn test_decode_event() {
+        let event = deposit_event();
+
+        let decoded = RelayEvent::decode(RelayProgram::RelayDepository, &event.data())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(decoded, RelayEvent::Deposit(event.clone()));
+        assert_eq!(decoded.name(), "DepositEvent");
+        assert_eq!(decoded.program(), RelayProgram::RelayDepository);
+        assert_eq!(decoded.data(), event.data());
+    }
+
+    #[test]
+    fn test_decode_checks_program() {
+        let event = deposit_event();
+
+        assert_eq!(
+            RelayEvent::decode(RelayProgram::RelayForwarder, &event.data()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_relay_program_from_program_id() {
+        let relay_depository_program = Pubkey::new_unique();
+
+        assert_eq!(
+            RelayProgram::from_program_id(&relay_depository_program, &relay_depository_program),
+            Some(RelayProgram::RelayDepository)
+        );
+        assert_eq!(
+            RelayProgram::from_program_id(&relay_depository_program, &relay_forwarder::ID),
+            Some(RelayProgram::RelayForwarder)
+        );
+        // Only the given deployment of the relay depository is indexed
+        assert_eq!(
+            RelayProgram::from_program_id(&relay_depository_program, &relay_depository::ID),
+            None
+        );
+    }
+
+    #[test]
+    fn test_decode_unknown_or_malformed_event() {
+        let mut data = deposit_event().data();
+
+        assert_eq!(
+            RelayEvent::decode(RelayProgram::RelayDepository, &data[..4]).unwrap(),
+            None
+        );
+
+        data.push(0);
+        assert!(matches!(
+            RelayEvent::decode(RelayProgram::RelayDepository, &data),
+            Err(Error::InvalidEvent {
+                name: "DepositEvent",
+                ..
+            })
+        ));
+
+        data[0] ^= 1;
+        assert_eq!(
+            RelayEvent::decode(RelayProgram::RelayDepository, &data).unwrap(),
+            None
+        );
+    }
+}
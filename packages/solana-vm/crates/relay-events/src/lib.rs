@@ -0,0 +1,20 @@
+//! Decoding and indexing of the relay depository and relay forwarder events
+//!
+//! Events are decoded from the metadata of transactions, either from the
+//! `Program data:` logs or from the event CPI instructions of the programs,
+//! into typed `RelayEvent`s after checking their discriminator, and can be
+//! streamed into an `EventSink` such as the `SqliteSink`.
+
+pub mod error;
+pub mod event;
+pub mod sink;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod transaction;
+
+pub use error::Error;
+pub use event::{RelayEvent, RelayProgram};
+pub use sink::{index, EventSink, TransactionEvents};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSink;
+pub use transaction::{DecodedEvent, EncodedTransaction, EventSource};
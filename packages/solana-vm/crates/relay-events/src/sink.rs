@@ -0,0 +1,135 @@
+//! Sinks storing the decoded events of transactions
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::{DecodedEvent, EncodedTransaction, Error};
+
+/// Events decoded from a transaction
+#[derive(Clone, PartialEq, Debug)]
+pub struct TransactionEvents {
+    /// The signature of the transaction
+    pub signature: String,
+    /// The slot of the transaction
+    pub slot: u64,
+    /// The decoded events, in emission order
+    pub events: Vec<DecodedEvent>,
+}
+
+impl TransactionEvents {
+    /// Decodes the events of a transaction, the relay depository being the deployment
+    /// of `relay_depository_program`
+    pub fn decode(
+        transaction: &EncodedTransaction,
+        relay_depository_program: &Pubkey,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            signature: transaction.signature()?.to_string(),
+            slot: transaction.slot,
+            events: transaction.decode_events(relay_depository_program)?,
+        })
+    }
+}
+
+/// Destination of the decoded events
+///
+/// Sinks may be written the same transaction several times, for example when
+/// indexing is resumed, and should only store its events once.
+pub trait EventSink {
+    /// Stores the events of a transaction, returning the number of events written,
+    /// which excludes the events already stored
+    fn write(&mut self, transaction: &TransactionEvents) -> Result<usize, Error>;
+}
+
+/// Sink keeping the transactions with events in memory
+impl EventSink for Vec<TransactionEvents> {
+    fn write(&mut self, transaction: &TransactionEvents) -> Result<usize, Error> {
+        if transaction.events.is_empty()
+            || self.iter().any(|t| t.signature == transaction.signature)
+        {
+            return Ok(0);
+        }
+
+        self.push(transaction.clone());
+        Ok(transaction.events.len())
+    }
+}
+
+/// Decodes the events of transactions into a sink, returning the number of events
+/// written, which excludes the events the sink already stored
+///
+/// The relay depository events are only decoded from the deployment of
+/// `relay_depository_program`.
+pub fn index<S, I>(
+    sink: &mut S,
+    relay_depository_program: &Pubkey,
+    transactions: I,
+) -> Result<usize, Error>
+where
+    S: EventSink + ?Sized,
+    I: IntoIterator<Item = EncodedTransaction>,
+{
+    let mut count = 0;
+    for transaction in transactions {
+        let events = TransactionEvents::decode(&transaction, relay_depository_program)?;
+        count += sink.write(&events)?;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RelayEvent;
+    use anchor_lang::Event;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use relay_depository::DepositEvent;
+    use serde_json::json;
+
+    fn transaction(signature: &str, logs: Vec<String>) -> EncodedTransaction {
+        serde_json::from_value(json!({
+            "slot": 42,
+            "transaction": {
+                "signatures": [signature],
+                "message": { "accountKeys": [] },
+            },
+            "meta": { "err": null, "logMessages": logs },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_index() {
+        let deposit = DepositEvent {
+            depositor: Pubkey::new_unique(),
+            token: None,
+            amount: 1_000,
+            id: [1; 32],
+            relay_depository: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+        };
+        let logs = vec![
+            format!("Program {} invoke [1]", relay_depository::ID),
+            format!("Program data: {}", STANDARD.encode(deposit.data())),
+            format!("Program {} success", relay_depository::ID),
+        ];
+
+        let mut sink: Vec<TransactionEvents> = vec![];
+        let count = index(
+            &mut sink,
+            &relay_depository::ID,
+            [
+                transaction("first", logs.clone()),
+                transaction("second", vec![]),
+                transaction("first", logs),
+            ],
+        )
+        .unwrap();
+
+        // The events of the duplicated transaction are only written once
+        assert_eq!(count, 1);
+        assert_eq!(sink.len(), 1);
+        assert_eq!(sink[0].signature, "first");
+        assert_eq!(sink[0].slot, 42);
+        assert_eq!(sink[0].events[0].event, RelayEvent::Deposit(deposit));
+    }
+}
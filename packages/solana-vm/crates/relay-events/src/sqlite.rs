@@ -0,0 +1,357 @@
+//! SQLite sink for local indexing
+//!
+//! Every event is stored in the `events` table with its serialized data, and
+//! the deposits and executed transfers are also stored in the `deposits` and
+//! `transfers` tables with a column per field. Events are keyed by the
+//! signature of their transaction and their index in it, in emission order.
+//! The deposits are indexed by id and depositor for lookups.
+//!
+//! Amounts and nonces are `u64`s, which don't fit the signed 64-bit integers
+//! of SQLite, so they are stored as `TEXT` of 20 zero-padded decimal digits
+//! (the width of `u64::MAX`), which compares and sorts like the numbers.
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use rusqlite::{params, Connection};
+
+use crate::{Error, EventSink, RelayEvent, TransactionEvents};
+
+/// Width of the decimal text of the `u64` amounts and nonces
+const U64_WIDTH: usize = 20;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    program_id TEXT NOT NULL,
+    source TEXT NOT NULL,
+    name TEXT NOT NULL,
+    data BLOB NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE TABLE IF NOT EXISTS deposits (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    depositor TEXT NOT NULL,
+    token TEXT,
+    amount TEXT NOT NULL,
+    id TEXT NOT NULL,
+    relay_depository TEXT NOT NULL,
+    vault TEXT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE TABLE IF NOT EXISTS transfers (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    recipient TEXT NOT NULL,
+    token TEXT,
+    amount TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    vault TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    executor TEXT NOT NULL,
+    request_id TEXT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE INDEX IF NOT EXISTS deposits_id ON deposits (id);
+
+CREATE INDEX IF NOT EXISTS deposits_depositor ON deposits (depositor);
+";
+
+/// Sink storing the events in a SQLite database
+pub struct SqliteSink {
+    connection: Connection,
+}
+
+impl SqliteSink {
+    /// Opens the database at `path`, creating the tables if needed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::new(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> Result<Self, Error> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+/// Encodes a `u64` as fixed-width decimal text, so that comparing and sorting the
+/// text in SQLite matches comparing and sorting the numbers
+pub fn encode_u64(value: u64) -> String {
+    format!("{value:0U64_WIDTH$}")
+}
+
+impl EventSink for SqliteSink {
+    fn write(&mut self, transaction: &TransactionEvents) -> Result<usize, Error> {
+        let tx = self.connection.transaction()?;
+        let mut written = 0;
+
+        for (index, decoded) in transaction.events.iter().enumerate() {
+            let event = &decoded.event;
+            let slot = transaction.slot as i64;
+
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO events
+                    (signature, event_index, slot, program_id, source, name, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    transaction.signature,
+                    index,
+                    slot,
+                    decoded.program_id.to_string(),
+                    decoded.source.as_str(),
+                    event.name(),
+                    event.data(),
+                ],
+            )?;
+            // The event was already stored
+            if inserted == 0 {
+                continue;
+            }
+            written += 1;
+
+            match event {
+                RelayEvent::Deposit(deposit) => {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO deposits
+                            (signature, event_index, slot, depositor, token, amount, id,
+                             relay_depository, vault)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            transaction.signature,
+                            index,
+                            slot,
+                            deposit.depositor.to_string(),
+                            deposit.token.as_ref().map(Pubkey::to_string),
+                            encode_u64(deposit.amount),
+                            hex::encode(deposit.id),
+                            deposit.relay_depository.to_string(),
+                            deposit.vault.to_string(),
+                        ],
+                    )?;
+                }
+                RelayEvent::TransferExecuted(transfer) => {
+                    let request = &transfer.request;
+                    tx.execute(
+                        "INSERT OR IGNORE INTO transfers
+                            (signature, event_index, slot, recipient, token, amount, nonce,
+                             vault, reference, executor, request_id)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                        params![
+                            transaction.signature,
+                            index,
+                            slot,
+                            request.recipient.to_string(),
+                            request.token.as_ref().map(Pubkey::to_string),
+                            encode_u64(request.amount),
+                            encode_u64(request.nonce),
+                            request.vault_address.to_string(),
+                            hex::encode(request.reference),
+                            transfer.executor.to_string(),
+                            transfer.id.to_string(),
+                        ],
+                    )?;
+                }
+                _ => {}
+            }
+        }
+
+        tx.commit()?;
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DecodedEvent, EventSource};
+    use relay_depository::{DepositEvent, TransferExecutedEvent, TransferRequest};
+
+    fn transaction_events() -> TransactionEvents {
+        let request = TransferRequest {
+            domain: [1; 32],
+            recipient: Pubkey::new_unique(),
+            token: Some(Pubkey::new_unique()),
+            amount: 2_000,
+            nonce: 7,
+            expiration: 1_700_000_000,
+            vault_address: Pubkey::new_unique(),
+            reference: [2; 32],
+        };
+
+        TransactionEvents {
+            signature: "signature".to_string(),
+            slot: 42,
+            events: vec![
+                DecodedEvent {
+                    program_id: relay_depository::ID,
+                    source: EventSource::Log,
+                    event: RelayEvent::Deposit(DepositEvent {
+                        depositor: Pubkey::new_unique(),
+                        token: None,
+                        amount: 1_000,
+                        id: [3; 32],
+                        relay_depository: Pubkey::new_unique(),
+                        vault: Pubkey::new_unique(),
+                    }),
+                },
+                DecodedEvent {
+                    program_id: relay_depository::ID,
+                    source: EventSource::Cpi { index: 1 },
+                    event: RelayEvent::TransferExecuted(TransferExecutedEvent {
+                        request,
+                        executor: Pubkey::new_unique(),
+                        id: Pubkey::new_unique(),
+                    }),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_write_events() {
+        let mut sink = SqliteSink::open_in_memory().unwrap();
+        let transaction = transaction_events();
+
+        assert_eq!(sink.write(&transaction).unwrap(), 2);
+        // Writing a transaction again does not duplicate its events
+        assert_eq!(sink.write(&transaction).unwrap(), 0);
+
+        let connection = sink.connection();
+        let names: Vec<(String, String)> = connection
+            .prepare("SELECT name, source FROM events ORDER BY event_index")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            names,
+            vec![
+                ("DepositEvent".to_string(), "log".to_string()),
+                ("TransferExecutedEvent".to_string(), "cpi".to_string()),
+            ]
+        );
+
+        let RelayEvent::Deposit(deposit) = &transaction.events[0].event else {
+            unreachable!()
+        };
+        let (token, amount, id, vault): (Option<String>, String, String, String) = connection
+            .query_row("SELECT token, amount, id, vault FROM deposits", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap();
+        assert_eq!(token, None);
+        assert_eq!(amount, "00000000000000001000");
+        assert_eq!(id, hex::encode([3; 32]));
+        assert_eq!(vault, deposit.vault.to_string());
+
+        let RelayEvent::TransferExecuted(transfer) = &transaction.events[1].event else {
+            unreachable!()
+        };
+        let (event_index, token, nonce, request_id): (u32, Option<String>, String, String) =
+            connection
+                .query_row(
+                    "SELECT event_index, token, nonce, request_id FROM transfers",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .unwrap();
+        assert_eq!(event_index, 1);
+        assert_eq!(token, transfer.request.token.map(|t| t.to_string()));
+        assert_eq!(nonce, encode_u64(7));
+        assert_eq!(request_id, transfer.id.to_string());
+    }
+
+    #[test]
+    fn test_deposit_lookups_use_indexes() {
+        let sink = SqliteSink::open_in_memory().unwrap();
+
+        for column in ["id", "depositor"] {
+            let plan: String = sink
+                .connection()
+                .query_row(
+                    &format!("EXPLAIN QUERY PLAN SELECT * FROM deposits WHERE {column} = ?1"),
+                    ["value"],
+                    |row| row.get(3),
+                )
+                .unwrap();
+            assert!(
+                plan.contains(&format!("USING INDEX deposits_{column}")),
+                "{plan}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_max_amounts() {
+        let mut sink = SqliteSink::open_in_memory().unwrap();
+        let mut transaction = transaction_events();
+        for decoded in &mut transaction.events {
+            match &mut decoded.event {
+                RelayEvent::Deposit(deposit) => deposit.amount = u64::MAX,
+                RelayEvent::TransferExecuted(transfer) => {
+                    transfer.request.amount = u64::MAX;
+                    transfer.request.nonce = u64::MAX;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        sink.write(&transaction).unwrap();
+
+        let connection = sink.connection();
+        let amount: String = connection
+            .query_row("SELECT amount FROM deposits", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(amount.parse::<u64>().unwrap(), u64::MAX);
+
+        let (amount, nonce): (String, String) = connection
+            .query_row("SELECT amount, nonce FROM transfers", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(amount.parse::<u64>().unwrap(), u64::MAX);
+        assert_eq!(nonce.parse::<u64>().unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_sort_amounts() {
+        let mut sink = SqliteSink::open_in_memory().unwrap();
+        let amounts = [u64::MAX, 9, 1_000, 10, (i64::MAX as u64) + 1];
+        for (index, amount) in amounts.into_iter().enumerate() {
+            let mut transaction = transaction_events();
+            transaction.signature = format!("signature-{index}");
+            let RelayEvent::Deposit(deposit) = &mut transaction.events[0].event else {
+                unreachable!()
+            };
+            deposit.amount = amount;
+            sink.write(&transaction).unwrap();
+        }
+
+        let connection = sink.connection();
+        let sorted: Vec<u64> = connection
+            .prepare("SELECT amount FROM deposits WHERE amount > ?1 ORDER BY amount")
+            .unwrap()
+            .query_map([encode_u64(9)], |row| row.get::<_, String>(0))
+            .unwrap()
+            .map(|amount| amount.unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(sorted, vec![10, 1_000, (i64::MAX as u64) + 1, u64::MAX]);
+    }
+}
@@ -0,0 +1,712 @@
+//! Extraction of events from the metadata of a transaction
+//!
+//! Transactions are read in the `json` encoding of the `getTransaction` RPC method.
+//! Events are emitted either in `Program data:` logs with `emit!`, attributed
+//! to the program at the top of the invocation stack, or in self CPI
+//! instructions with `emit_cpi!`, signed by the event authority of the program.
+//! The invoke logs of the inner instructions place the event CPI instructions
+//! among the logs, so that both kinds of events are decoded in emission order.
+
+use anchor_lang::{event::EVENT_IX_TAG_LE, prelude::Pubkey};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use relay_depository::pda::EVENT_AUTHORITY_SEED;
+use serde::Deserialize;
+
+use crate::{Error, RelayEvent, RelayProgram};
+
+/// Prefix of the logs carrying event data
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Log replacing the logs exceeding the log limit of the runtime
+const LOG_TRUNCATED: &str = "Log truncated";
+
+/// Programs whose instructions are verified by the runtime without being invoked,
+/// so without invoke logs
+const PRECOMPILES: [&str; 3] = [
+    "Ed25519SigVerify111111111111111111111111111",
+    "KeccakSecp256k11111111111111111111111111111",
+    "Secp256r1SigVerify1111111111111111111111111",
+];
+
+/// Transaction in the `json` encoding of the `getTransaction` RPC method
+#[derive(Clone, Debug, Deserialize)]
+pub struct EncodedTransaction {
+    pub slot: u64,
+    pub transaction: Transaction,
+    pub meta: Option<TransactionMeta>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Transaction {
+    pub signatures: Vec<String>,
+    pub message: Message,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    pub account_keys: Vec<String>,
+    #[serde(default)]
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionMeta {
+    pub err: Option<serde_json::Value>,
+    pub log_messages: Option<Vec<String>>,
+    pub inner_instructions: Option<Vec<InnerInstructions>>,
+    pub loaded_addresses: Option<LoadedAddresses>,
+}
+
+/// Instructions invoked by the instruction at `index` of the transaction
+#[derive(Clone, Debug, Deserialize)]
+pub struct InnerInstructions {
+    pub index: u8,
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompiledInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    /// The base58 encoded instruction data
+    pub data: String,
+}
+
+/// Accounts loaded from address lookup tables, following the static account keys
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct LoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>,
+}
+
+/// Origin of a decoded event
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventSource {
+    /// A `Program data:` log
+    Log,
+    /// An event CPI instruction, invoked by the instruction at `index` of the transaction
+    Cpi { index: u8 },
+}
+
+impl EventSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Log => "log",
+            Self::Cpi { .. } => "cpi",
+        }
+    }
+}
+
+/// Event decoded from a transaction
+#[derive(Clone, PartialEq, Debug)]
+pub struct DecodedEvent {
+    /// The program emitting the event
+    pub program_id: Pubkey,
+    pub source: EventSource,
+    pub event: RelayEvent,
+}
+
+impl EncodedTransaction {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Returns the signature of the transaction
+    pub fn signature(&self) -> Result<&str, Error> {
+        self.transaction
+            .signatures
+            .first()
+            .map(String::as_str)
+            .ok_or_else(|| Error::InvalidTransaction("missing signature".to_string()))
+    }
+
+    /// Returns the account keys of the transaction, including the loaded addresses
+    pub fn account_keys(&self) -> Result<Vec<Pubkey>, Error> {
+        let loaded = self
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.loaded_addresses.clone())
+            .unwrap_or_default();
+
+        self.transaction
+            .message
+            .account_keys
+            .iter()
+            .chain(&loaded.writable)
+            .chain(&loaded.readonly)
+            .map(|key| parse_pubkey(key))
+            .collect()
+    }
+
+    /// Decodes the events of the relay programs emitted by the transaction, the relay
+    /// depository being the deployment of `relay_depository_program`
+    ///
+    /// Failed transactions emit no events, since their changes are reverted.
+    /// The events are returned in emission order, the CPI events at the invoke log
+    /// of their instruction. The CPI events whose invoke log was truncated follow,
+    /// in execution order.
+    pub fn decode_events(
+        &self,
+        relay_depository_program: &Pubkey,
+    ) -> Result<Vec<DecodedEvent>, Error> {
+        let Some(meta) = &self.meta else {
+            return Ok(vec![]);
+        };
+        if meta.err.is_some() {
+            return Ok(vec![]);
+        }
+
+        let account_keys = self.account_keys()?;
+
+        // The CPI events, keyed by the index of their instruction and their position
+        // among its inner instructions
+        let mut cpi_events = vec![];
+        for inner in meta.inner_instructions.as_deref().unwrap_or_default() {
+            for (position, instruction) in inner.instructions.iter().enumerate() {
+                let Some((program_id, program, data)) =
+                    cpi_event(relay_depository_program, &account_keys, instruction)?
+                else {
+                    continue;
+                };
+                if let Some(event) = RelayEvent::decode(program, &data)? {
+                    let event = DecodedEvent {
+                        program_id,
+                        source: EventSource::Cpi { index: inner.index },
+                        event,
+                    };
+                    cpi_events.push(((inner.index, position), event));
+                }
+            }
+        }
+
+        let mut events = vec![];
+
+        let logs = meta.log_messages.as_deref().unwrap_or_default();
+        let invoked_instructions = self.invoked_instruction_indexes(&account_keys)?;
+        for entry in log_entries(relay_depository_program, logs, invoked_instructions)? {
+            match entry {
+                LogEntry::Event(program_id, program, data) => {
+                    if let Some(event) = RelayEvent::decode(program, &data)? {
+                        events.push(DecodedEvent {
+                            program_id,
+                            source: EventSource::Log,
+                            event,
+                        });
+                    }
+                }
+                LogEntry::InnerInstruction { index, position } => {
+                    if let Some(i) = cpi_events
+                        .iter()
+                        .position(|(key, _)| *key == (index, position))
+                    {
+                        events.push(cpi_events.remove(i).1);
+                    }
+                }
+            }
+        }
+
+        events.extend(cpi_events.into_iter().map(|(_, event)| event));
+
+        Ok(events)
+    }
+
+    /// Returns the indexes of the instructions of the transaction which are invoked,
+    /// and so logged, in order, skipping the precompiles
+    ///
+    /// Without the instructions in the transaction, every instruction is assumed to be
+    /// invoked.
+    fn invoked_instruction_indexes(&self, account_keys: &[Pubkey]) -> Result<Vec<u8>, Error> {
+        let instructions = &self.transaction.message.instructions;
+        if instructions.is_empty() {
+            return Ok((0..=u8::MAX).collect());
+        }
+
+        let mut indexes = vec![];
+        for (index, instruction) in instructions.iter().enumerate() {
+            let program_id = account_key(account_keys, instruction.program_id_index)?;
+            if !PRECOMPILES.contains(&program_id.to_string().as_str()) {
+                indexes.push(index as u8);
+            }
+        }
+        Ok(indexes)
+    }
+}
+
+/// Returns the event authority of a program
+pub fn find_event_authority_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id).0
+}
+
+/// Entry of the logs of a transaction placing its events
+enum LogEntry {
+    /// Event data of a `Program data:` log of a relay program, with the program
+    /// emitting it
+    Event(Pubkey, RelayProgram, Vec<u8>),
+    /// Invocation of the inner instruction at `position` of the instruction at `index`
+    InnerInstruction { index: u8, position: usize },
+}
+
+/// Returns the event data of the `Program data:` logs of the relay programs and the
+/// invocations of the inner instructions, in order
+///
+/// The emitting program is tracked from the invoke and exit logs, so other
+/// programs logging event data are not attributed to the relay programs. The
+/// logs following a truncation cannot be attributed and are ignored. Only the
+/// exact invoke and exit logs of the runtime are tracked, other lines are skipped.
+/// The top level invocations are the instructions at `invoked_instructions`, and
+/// the nested ones their inner instructions, in execution order.
+fn log_entries(
+    relay_depository_program: &Pubkey,
+    logs: &[String],
+    invoked_instructions: Vec<u8>,
+) -> Result<Vec<LogEntry>, Error> {
+    let mut invocations: Vec<Pubkey> = vec![];
+    let mut invoked_instructions = invoked_instructions.into_iter();
+    // The index of the executing instruction, and the position of its next inner instruction
+    let mut instruction: Option<(u8, usize)> = None;
+    let mut entries = vec![];
+
+    for log in logs {
+        if log == LOG_TRUNCATED {
+            break;
+        }
+
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA_PREFIX) {
+            let Some(program_id) = invocations.last() else {
+                continue;
+            };
+            let Some(program) = RelayProgram::from_program_id(relay_depository_program, program_id)
+            else {
+                continue;
+            };
+
+            let data = STANDARD
+                .decode(data)
+                .map_err(|e| Error::InvalidLogData(e.to_string()))?;
+            entries.push(LogEntry::Event(*program_id, program, data));
+            continue;
+        }
+
+        match parse_program_log(log) {
+            Some(ProgramLog::Invoke(program_id, depth)) => {
+                invocations.push(program_id);
+                if depth == 1 {
+                    instruction = invoked_instructions.next().map(|index| (index, 0));
+                } else if let Some((index, position)) = &mut instruction {
+                    entries.push(LogEntry::InnerInstruction {
+                        index: *index,
+                        position: *position,
+                    });
+                    *position += 1;
+                }
+            }
+            Some(ProgramLog::Exit) => {
+                invocations.pop();
+            }
+            None => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Invoke or exit log of a program, as logged by the runtime
+enum ProgramLog {
+    /// `Program <program id> invoke [<depth>]`
+    Invoke(Pubkey, u8),
+    /// `Program <program id> success` or `Program <program id> failed: <error>`
+    Exit,
+}
+
+/// Parses an invoke or exit log of a program, returning None for any other log
+fn parse_program_log(log: &str) -> Option<ProgramLog> {
+    let (program_id, action) = log.strip_prefix("Program ")?.split_once(' ')?;
+    let program_id = program_id.parse().ok()?;
+
+    if let Some(depth) = action
+        .strip_prefix("invoke [")
+        .and_then(|depth| depth.strip_suffix(']'))
+    {
+        return depth
+            .parse::<u8>()
+            .ok()
+            .map(|depth| ProgramLog::Invoke(program_id, depth));
+    }
+
+    (action == "success" || action.starts_with("failed: ")).then_some(ProgramLog::Exit)
+}
+
+/// Returns the event data of an event CPI instruction of a relay program, with
+/// the program emitting it
+fn cpi_event(
+    relay_depository_program: &Pubkey,
+    account_keys: &[Pubkey],
+    instruction: &CompiledInstruction,
+) -> Result<Option<(Pubkey, RelayProgram, Vec<u8>)>, Error> {
+    let program_id = account_key(account_keys, instruction.program_id_index)?;
+    let Some(program) = RelayProgram::from_program_id(relay_depository_program, &program_id) else {
+        return Ok(None);
+    };
+
+    let data = bs58::decode(&instruction.data)
+        .into_vec()
+        .map_err(|e| Error::InvalidInstructionData(e.to_string()))?;
+    let Some(event_data) = data.strip_prefix(&EVENT_IX_TAG_LE) else {
+        return Ok(None);
+    };
+
+    // Only the program can sign for its event authority
+    let event_authority = match instruction.accounts.first() {
+        Some(index) => account_key(account_keys, *index)?,
+        None => return Ok(None),
+    };
+    if event_authority != find_event_authority_address(&program_id) {
+        return Ok(None);
+    }
+
+    Ok(Some((program_id, program, event_data.to_vec())))
+}
+
+fn account_key(account_keys: &[Pubkey], index: u8) -> Result<Pubkey, Error> {
+    account_keys
+        .get(index as usize)
+        .copied()
+        .ok_or_else(|| Error::InvalidTransaction(format!("missing account key {}", index)))
+}
+
+fn parse_pubkey(key: &str) -> Result<Pubkey, Error> {
+    key.parse()
+        .map_err(|_| Error::InvalidTransaction(format!("invalid account key {}", key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+    use relay_depository::DepositEvent;
+    use relay_forwarder::ForwardedEvent;
+    use serde_json::json;
+
+    fn deposit_event() -> DepositEvent {
+        DepositEvent {
+            depositor: Pubkey::new_unique(),
+            token: None,
+            amount: 1_000,
+            id: [1; 32],
+            relay_depository: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+        }
+    }
+
+    fn forwarded_event() -> ForwardedEvent {
+        ForwardedEvent {
+            forwarder: Pubkey::new_unique(),
+            cranker: Pubkey::new_unique(),
+            depositor: Pubkey::new_unique(),
+            id: [2; 32],
+            mint: None,
+            amount: 1_000,
+            rent_retained: 0,
+            crank_reward: 0,
+        }
+    }
+
+    fn program_data(data: &[u8]) -> String {
+        format!("Program data: {}", STANDARD.encode(data))
+    }
+
+    fn transaction(
+        account_keys: &[Pubkey],
+        logs: Vec<String>,
+        inner_instructions: serde_json::Value,
+    ) -> EncodedTransaction {
+        serde_json::from_value(json!({
+            "slot": 42,
+            "transaction": {
+                "signatures": ["5h6xBEauJ3PK6SWCZ1PGjBvj8vDdWG3KpwATGy1ARAXFSDwt8GFXM7W5Ncn16wmqokgpiKRLuS83KUxyZyv2sUYv"],
+                "message": {
+                    "accountKeys": account_keys.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+                },
+            },
+            "meta": {
+                "err": null,
+                "logMessages": logs,
+                "innerInstructions": inner_instructions,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_decode_log_events() {
+        let deposit = deposit_event();
+        let forwarded = forwarded_event();
+        let other_program = Pubkey::new_unique();
+
+        let logs = vec![
+            format!("Program {} invoke [1]", relay_forwarder::ID),
+            format!("Program {} invoke [2]", relay_depository::ID),
+            program_data(&deposit.data()),
+            format!(
+                "Program {} consumed 100 of 200 compute units",
+                relay_depository::ID
+            ),
+            format!("Program {} success", relay_depository::ID),
+            // Event data logged by another program is ignored
+            format!("Program {} invoke [2]", other_program),
+            program_data(&deposit.data()),
+            format!("Program {} success", other_program),
+            program_data(&forwarded.data()),
+            format!("Program {} success", relay_forwarder::ID),
+        ];
+        let tx = transaction(&[], logs, json!([]));
+
+        assert_eq!(
+            tx.decode_events(&relay_depository::ID).unwrap(),
+            vec![
+                DecodedEvent {
+                    program_id: relay_depository::ID,
+                    source: EventSource::Log,
+                    event: RelayEvent::Deposit(deposit),
+                },
+                DecodedEvent {
+                    program_id: relay_forwarder::ID,
+                    source: EventSource::Log,
+                    event: RelayEvent::Forwarded(forwarded),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_other_deployment_events() {
+        let deposit = deposit_event();
+        let relay_depository_program = Pubkey::new_unique();
+        let event_authority = find_event_authority_address(&relay_depository_program);
+
+        let logs = vec![
+            format!("Program {} invoke [1]", relay_depository_program),
+            program_data(&deposit.data()),
+            format!("Program {} success", relay_depository_program),
+            // Events of the main deployment aren't attributed to the indexed deployment
+            format!("Program {} invoke [1]", relay_depository::ID),
+            program_data(&deposit.data()),
+            format!("Program {} success", relay_depository::ID),
+        ];
+        let account_keys = [
+            Pubkey::new_unique(),
+            relay_depository_program,
+            event_authority,
+        ];
+        let data = bs58::encode([&EVENT_IX_TAG_LE[..], &deposit.data()].concat()).into_string();
+        let inner_instructions = json!([{
+            "index": 0,
+            "instructions": [{ "programIdIndex": 1, "accounts": [2], "data": data }],
+        }]);
+        let tx = transaction(&account_keys, logs, inner_instructions);
+
+        assert_eq!(
+            tx.decode_events(&relay_depository_program).unwrap(),
+            vec![
+                DecodedEvent {
+                    program_id: relay_depository_program,
+                    source: EventSource::Log,
+                    event: RelayEvent::Deposit(deposit.clone()),
+                },
+                DecodedEvent {
+                    program_id: relay_depository_program,
+                    source: EventSource::Cpi { index: 0 },
+                    event: RelayEvent::Deposit(deposit),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_truncated_logs() {
+        let deposit = deposit_event();
+
+        let logs = vec![
+            format!("Program {} invoke [1]", relay_depository::ID),
+            program_data(&deposit.data()),
+            LOG_TRUNCATED.to_string(),
+            program_data(&deposit.data()),
+        ];
+        let tx = transaction(&[], logs, json!([]));
+
+        assert_eq!(tx.decode_events(&relay_depository::ID).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_decode_skips_malformed_program_logs() {
+        let deposit = deposit_event();
+
+        let logs = vec![
+            format!("Program {} invoke [1]", relay_depository::ID),
+            "Program not-a-program invoke [2]".to_string(),
+            format!("Program {} invoke", Pubkey::new_unique()),
+            format!("Program {} invoke [2] extra", Pubkey::new_unique()),
+            "Program log: success".to_string(),
+            program_data(&deposit.data()),
+            format!("Program {} success", relay_depository::ID),
+        ];
+        let tx = transaction(&[], logs, json!([]));
+
+        assert_eq!(
+            tx.decode_events(&relay_depository::ID).unwrap(),
+            vec![DecodedEvent {
+                program_id: relay_depository::ID,
+                source: EventSource::Log,
+                event: RelayEvent::Deposit(deposit),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decode_cpi_events() {
+        let deposit = deposit_event();
+        let event_authority = find_event_authority_address(&relay_depository::ID);
+        let spoofed_authority = Pubkey::new_unique();
+
+        let account_keys = [
+            Pubkey::new_unique(),
+            relay_depository::ID,
+            event_authority,
+            spoofed_authority,
+        ];
+        let data = bs58::encode([&EVENT_IX_TAG_LE[..], &deposit.data()].concat()).into_string();
+        let inner_instructions = json!([{
+            "index": 0,
+            "instructions": [
+                { "programIdIndex": 1, "accounts": [2], "data": data },
+                // Not signed by the event authority of the program
+                { "programIdIndex": 1, "accounts": [3], "data": data },
+                // Not an event instruction
+                { "programIdIndex": 1, "accounts": [2], "data": bs58::encode(deposit.data()).into_string() },
+            ],
+        }]);
+        let tx = transaction(&account_keys, vec![], inner_instructions);
+
+        assert_eq!(
+            tx.decode_events(&relay_depository::ID).unwrap(),
+            vec![DecodedEvent {
+                program_id: relay_depository::ID,
+                source: EventSource::Cpi { index: 0 },
+                event: RelayEvent::Deposit(deposit),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decode_events_in_emission_order() {
+        let deposit = deposit_event();
+        let forwarded = (1..=3)
+            .map(|amount| ForwardedEvent {
+                amount,
+                ..forwarded_event()
+            })
+            .collect::<Vec<_>>();
+
+        let account_keys = [
+            Pubkey::new_unique(),
+            "Ed25519SigVerify111111111111111111111111111"
+                .parse()
+                .unwrap(),
+            relay_forwarder::ID,
+            relay_depository::ID,
+            find_event_authority_address(&relay_depository::ID),
+            find_event_authority_address(&relay_forwarder::ID),
+        ];
+        let event_data =
+            |data: Vec<u8>| bs58::encode([&EVENT_IX_TAG_LE[..], &data].concat()).into_string();
+        // The precompile at index 0 is not invoked, so it has no invoke log
+        let logs = vec![
+            format!("Program {} invoke [1]", relay_forwarder::ID),
+            program_data(&forwarded[0].data()),
+            format!("Program {} invoke [2]", relay_depository::ID),
+            format!("Program {} invoke [3]", relay_depository::ID),
+            format!("Program {} success", relay_depository::ID),
+            format!("Program {} success", relay_depository::ID),
+            format!("Program {} invoke [2]", relay_forwarder::ID),
+            format!("Program {} success", relay_forwarder::ID),
+            program_data(&forwarded[2].data()),
+            format!("Program {} success", relay_forwarder::ID),
+        ];
+        let inner_instructions = json!([{
+            "index": 1,
+            "instructions": [
+                { "programIdIndex": 3, "accounts": [], "data": bs58::encode([0; 8]).into_string() },
+                { "programIdIndex": 3, "accounts": [4], "data": event_data(deposit.data()) },
+                { "programIdIndex": 2, "accounts": [5], "data": event_data(forwarded[1].data()) },
+            ],
+        }]);
+        let mut tx = transaction(&account_keys, logs, inner_instructions);
+        tx.transaction.message.instructions = serde_json::from_value(json!([
+            { "programIdIndex": 1, "accounts": [], "data": "" },
+            { "programIdIndex": 2, "accounts": [], "data": "" },
+        ]))
+        .unwrap();
+
+        let events = tx.decode_events(&relay_depository::ID).unwrap();
+        assert_eq!(
+            events
+                .iter()
+                .map(|decoded| (decoded.source, decoded.event.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    EventSource::Log,
+                    RelayEvent::Forwarded(forwarded[0].clone())
+                ),
+                (EventSource::Cpi { index: 1 }, RelayEvent::Deposit(deposit)),
+                (
+                    EventSource::Cpi { index: 1 },
+                    RelayEvent::Forwarded(forwarded[1].clone())
+                ),
+                (
+                    EventSource::Log,
+                    RelayEvent::Forwarded(forwarded[2].clone())
+                ),
+            ]
+        );
+
+        // Without the invoke logs, the CPI events follow the log events
+        tx.meta.as_mut().unwrap().log_messages = Some(vec![
+            format!("Program {} invoke [1]", relay_forwarder::ID),
+            program_data(&forwarded[0].data()),
+            LOG_TRUNCATED.to_string(),
+        ]);
+        assert_eq!(
+            tx.decode_events(&relay_depository::ID)
+                .unwrap()
+                .iter()
+                .map(|decoded| decoded.source)
+                .collect::<Vec<_>>(),
+            vec![
+                EventSource::Log,
+                EventSource::Cpi { index: 1 },
+                EventSource::Cpi { index: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_transaction_has_no_events() {
+        let logs = vec![
+            format!("Program {} invoke [1]", relay_depository::ID),
+            program_data(&deposit_event().data()),
+            format!(
+                "Program {} failed: custom program error: 0x1",
+                relay_depository::ID
+            ),
+        ];
+        let mut tx = transaction(&[], logs, json!([]));
+        assert_eq!(tx.decode_events(&relay_depository::ID).unwrap().len(), 1);
+
+        tx.meta.as_mut().unwrap().err = Some(json!({ "InstructionError": [0, { "Custom": 1 }] }));
+        assert!(tx.decode_events(&relay_depository::ID).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,361 @@
+//! Seeds and address derivations of the relay depository accounts
+
+use solana_program::{pubkey, pubkey::Pubkey};
+
+/// Program id of the relay depository, as declared by the program
+///
+/// The derivations take the program id of the relay depository deployment they
+/// derive for, which is this one for the main deployment.
+pub const RELAY_DEPOSITORY_PROGRAM_ID: Pubkey =
+    pubkey!("99vQwtBwYtrqqD9YSXbdum3KBdxPAVxYTaQ3cfnJSrN2");
+
+/// Program id of the associated token account program
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+pub const RELAY_DEPOSITORY_SEED: &[u8] = b"relay_depository";
+
+pub const USED_REQUEST_SEED: &[u8] = b"used_request";
+
+pub const USED_QUOTE_SEED: &[u8] = b"used_quote";
+
+pub const USED_DIRECT_DEPOSIT_SEED: &[u8] = b"used_direct_deposit";
+
+pub const VAULT_SEED: &[u8] = b"vault";
+
+pub const VAULT_CONFIG_SEED: &[u8] = b"vault_config";
+
+pub const VAULT_LEDGER_SEED: &[u8] = b"vault_ledger";
+
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+pub const PENDING_SWEEP_SEED: &[u8] = b"pending_sweep";
+
+pub const PENDING_EMERGENCY_WITHDRAW_SEED: &[u8] = b"pending_emergency_withdraw";
+
+pub const EXECUTED_EMERGENCY_WITHDRAW_SEED: &[u8] = b"executed_emergency_withdraw";
+
+/// Seed of the event authority signing the event CPI instructions, as set by `#[event_cpi]`
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+/// Returns the address and bump of the default relay depository
+pub fn find_relay_depository_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RELAY_DEPOSITORY_SEED], program_id)
+}
+
+/// Returns the address and bump of a relay depository instance
+pub fn find_relay_depository_instance_address(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    instance_id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            RELAY_DEPOSITORY_SEED,
+            creator.as_ref(),
+            &instance_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Returns the address and bump of the vault of the default relay depository
+pub fn find_vault_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED], program_id)
+}
+
+/// Returns the address and bump of the vault of a relay depository instance
+pub fn find_instance_vault_address(program_id: &Pubkey, relay_depository: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, relay_depository.as_ref()], program_id)
+}
+
+/// Returns the address and bump of an additional vault of a relay depository
+pub fn find_indexed_vault_address(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    index: u16,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VAULT_SEED, relay_depository.as_ref(), &index.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Returns the address of a vault of a relay depository, and of its configuration
+///
+/// The vault is the main vault of the relay depository if `vault_index` is None, which
+/// has no configuration, and an additional vault otherwise.
+pub fn find_relay_vault_addresses(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    vault_index: Option<u16>,
+) -> (Pubkey, Option<Pubkey>) {
+    match vault_index {
+        Some(index) => (
+            find_indexed_vault_address(program_id, relay_depository, index).0,
+            Some(find_vault_config_address(program_id, relay_depository, index).0),
+        ),
+        None if *relay_depository == find_relay_depository_address(program_id).0 => {
+            (find_vault_address(program_id).0, None)
+        }
+        None => (
+            find_instance_vault_address(program_id, relay_depository).0,
+            None,
+        ),
+    }
+}
+
+/// Returns the address and bump of the configuration of an additional vault
+pub fn find_vault_config_address(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    index: u16,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            VAULT_CONFIG_SEED,
+            relay_depository.as_ref(),
+            &index.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Returns the address and bump of the ledger of a vault for a token (None for native)
+pub fn find_vault_ledger_address(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    token: Option<&Pubkey>,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            VAULT_LEDGER_SEED,
+            vault.as_ref(),
+            token.copied().unwrap_or_default().as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Returns the associated token account of a vault
+pub fn find_vault_token_address(vault: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    find_associated_token_address(vault, mint, token_program)
+}
+
+/// Returns the address and bump of the account marking a transfer request as used
+pub fn find_used_request_address(program_id: &Pubkey, request_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USED_REQUEST_SEED, request_hash], program_id)
+}
+
+/// Returns the address and bump of the account marking a deposit quote as used
+pub fn find_used_quote_address(program_id: &Pubkey, quote_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USED_QUOTE_SEED, quote_hash], program_id)
+}
+
+/// Returns the address and bump of the account marking a direct deposit id as used by a vault
+pub fn find_used_direct_deposit_address(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    id: &[u8; 32],
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USED_DIRECT_DEPOSIT_SEED, vault.as_ref(), id], program_id)
+}
+
+/// Returns the address and bump of the treasury configuration of a relay depository
+pub fn find_treasury_config_address(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED, relay_depository.as_ref()], program_id)
+}
+
+/// Returns the address and bump of the pending sweep of a vault
+pub fn find_pending_sweep_address(program_id: &Pubkey, vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PENDING_SWEEP_SEED, vault.as_ref()], program_id)
+}
+
+/// Returns the address and bump of the pending emergency withdraw of a vault
+pub fn find_pending_emergency_withdraw_address(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PENDING_EMERGENCY_WITHDRAW_SEED, vault.as_ref()],
+        program_id,
+    )
+}
+
+/// Returns the address and bump of the marker of the emergency withdraw of a token (None
+/// for native) out of a vault, under the announcement made at `announced_at`
+pub fn find_executed_emergency_withdraw_address(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    token: Option<&Pubkey>,
+    announced_at: i64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            EXECUTED_EMERGENCY_WITHDRAW_SEED,
+            vault.as_ref(),
+            token.copied().unwrap_or_default().as_ref(),
+            &announced_at.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Returns the address and bump of the event authority of the relay depository
+pub fn find_event_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id)
+}
+
+/// Returns the associated token account of a wallet
+pub fn find_associated_token_address(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The seeds as written in the relay depository program
+    #[test]
+    fn test_depository_derivations() {
+        let program_id = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let relay_depository = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let hash = [7; 32];
+
+        assert_eq!(
+            find_relay_depository_address(&program_id),
+            Pubkey::find_program_address(&[b"relay_depository"], &program_id)
+        );
+        assert_eq!(
+            find_relay_depository_instance_address(&program_id, &creator, 3),
+            Pubkey::find_program_address(
+                &[b"relay_depository", creator.as_ref(), &3u64.to_le_bytes()],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_vault_address(&program_id),
+            Pubkey::find_program_address(&[b"vault"], &program_id)
+        );
+        assert_eq!(
+            find_instance_vault_address(&program_id, &relay_depository),
+            Pubkey::find_program_address(&[b"vault", relay_depository.as_ref()], &program_id)
+        );
+        assert_eq!(
+            find_indexed_vault_address(&program_id, &relay_depository, 2),
+            Pubkey::find_program_address(
+                &[b"vault", relay_depository.as_ref(), &2u16.to_le_bytes()],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_vault_config_address(&program_id, &relay_depository, 2),
+            Pubkey::find_program_address(
+                &[
+                    b"vault_config",
+                    relay_depository.as_ref(),
+                    &2u16.to_le_bytes()
+                ],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_vault_ledger_address(&program_id, &vault, Some(&mint)),
+            Pubkey::find_program_address(
+                &[b"vault_ledger", vault.as_ref(), mint.as_ref()],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_vault_ledger_address(&program_id, &vault, None),
+            Pubkey::find_program_address(
+                &[b"vault_ledger", vault.as_ref(), Pubkey::default().as_ref()],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_used_request_address(&program_id, &hash),
+            Pubkey::find_program_address(&[b"used_request", &hash], &program_id)
+        );
+        assert_eq!(
+            find_used_quote_address(&program_id, &hash),
+            Pubkey::find_program_address(&[b"used_quote", &hash], &program_id)
+        );
+        assert_eq!(
+            find_used_direct_deposit_address(&program_id, &vault, &hash),
+            Pubkey::find_program_address(
+                &[b"used_direct_deposit", vault.as_ref(), &hash],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_treasury_config_address(&program_id, &relay_depository),
+            Pubkey::find_program_address(&[b"treasury", relay_depository.as_ref()], &program_id)
+        );
+        assert_eq!(
+            find_pending_sweep_address(&program_id, &vault),
+            Pubkey::find_program_address(&[b"pending_sweep", vault.as_ref()], &program_id)
+        );
+        assert_eq!(
+            find_pending_emergency_withdraw_address(&program_id, &vault),
+            Pubkey::find_program_address(
+                &[b"pending_emergency_withdraw", vault.as_ref()],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_executed_emergency_withdraw_address(&program_id, &vault, Some(&mint), 5),
+            Pubkey::find_program_address(
+                &[
+                    b"executed_emergency_withdraw",
+                    vault.as_ref(),
+                    mint.as_ref(),
+                    &5i64.to_le_bytes()
+                ],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_event_authority_address(&program_id),
+            Pubkey::find_program_address(&[b"__event_authority"], &program_id)
+        );
+    }
+
+    #[test]
+    fn test_relay_vault_addresses() {
+        let program_id = Pubkey::new_unique();
+        let default_relay_depository = find_relay_depository_address(&program_id).0;
+        let instance = Pubkey::new_unique();
+
+        // The default relay depository keeps the vault of the first deployment
+        assert_eq!(
+            find_relay_vault_addresses(&program_id, &default_relay_depository, None),
+            (find_vault_address(&program_id).0, None)
+        );
+        assert_eq!(
+            find_relay_vault_addresses(&program_id, &instance, None),
+            (find_instance_vault_address(&program_id, &instance).0, None)
+        );
+        assert_eq!(
+            find_relay_vault_addresses(&program_id, &instance, Some(1)),
+            (
+                find_indexed_vault_address(&program_id, &instance, 1).0,
+                Some(find_vault_config_address(&program_id, &instance, 1).0)
+            )
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Seeds and address derivations of the relay forwarder accounts
+
+use solana_program::{pubkey, pubkey::Pubkey};
+
+use crate::depository::{find_associated_token_address, EVENT_AUTHORITY_SEED};
+
+/// Program id of the relay forwarder, as declared by the program
+///
+/// The derivations take the program id of the relay forwarder deployment they
+/// derive for, which is this one for the main deployment.
+pub const RELAY_FORWARDER_PROGRAM_ID: Pubkey =
+    pubkey!("DPArtTLbEqa6EuXHfL5UFLBZhFjiEXWRudhvXDrjwXUr");
+
+pub const RELAY_FORWARDER_SEED: &[u8] = b"relay_forwarder";
+
+pub const FORWARDER_CONFIG_SEED: &[u8] = b"forwarder_config";
+
+pub const FORWARD_MINIMUM_SEED: &[u8] = b"forward_minimum";
+
+pub const PENDING_RECOVER_SEED: &[u8] = b"pending_recover";
+
+/// Returns the address and bump of the forwarder of a relay depository
+pub fn find_forwarder_address(
+    program_id: &Pubkey,
+    relay_depository_program: &Pubkey,
+    relay_depository: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            RELAY_FORWARDER_SEED,
+            relay_depository_program.as_ref(),
+            relay_depository.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Returns the address and bump of the legacy forwarder, which forwards to the
+/// default relay depository
+pub fn find_legacy_forwarder_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RELAY_FORWARDER_SEED], program_id)
+}
+
+/// Returns the associated token account of a forwarder
+pub fn find_forwarder_token_address(
+    forwarder: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    find_associated_token_address(forwarder, mint, token_program)
+}
+
+/// Returns the address and bump of the configuration of a forwarder
+pub fn find_forwarder_config_address(program_id: &Pubkey, forwarder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FORWARDER_CONFIG_SEED, forwarder.as_ref()], program_id)
+}
+
+/// Returns the address and bump of the forward minimum of a mint (None for native) for a
+/// relay depository
+pub fn find_forward_minimum_address(
+    program_id: &Pubkey,
+    relay_depository: &Pubkey,
+    mint: Option<&Pubkey>,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            FORWARD_MINIMUM_SEED,
+            relay_depository.as_ref(),
+            mint.copied().unwrap_or_default().as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Returns the address and bump of the pending recovery of a forwarder token account
+pub fn find_pending_recover_address(
+    program_id: &Pubkey,
+    forwarder_token_account: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PENDING_RECOVER_SEED, forwarder_token_account.as_ref()],
+        program_id,
+    )
+}
+
+/// Returns the address and bump of the event authority of the relay forwarder
+pub fn find_forwarder_event_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The seeds as written in the relay forwarder program
+    #[test]
+    fn test_forwarder_derivations() {
+        let program_id = Pubkey::new_unique();
+        let relay_depository_program = Pubkey::new_unique();
+        let relay_depository = Pubkey::new_unique();
+        let forwarder = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        assert_eq!(
+            find_forwarder_address(&program_id, &relay_depository_program, &relay_depository),
+            Pubkey::find_program_address(
+                &[
+                    b"relay_forwarder",
+                    relay_depository_program.as_ref(),
+                    relay_depository.as_ref()
+                ],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_legacy_forwarder_address(&program_id),
+            Pubkey::find_program_address(&[b"relay_forwarder"], &program_id)
+        );
+        assert_eq!(
+            find_forwarder_config_address(&program_id, &forwarder),
+            Pubkey::find_program_address(&[b"forwarder_config", forwarder.as_ref()], &program_id)
+        );
+        assert_eq!(
+            find_forward_minimum_address(&program_id, &relay_depository, Some(&mint)),
+            Pubkey::find_program_address(
+                &[b"forward_minimum", relay_depository.as_ref(), mint.as_ref()],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_forward_minimum_address(&program_id, &relay_depository, None),
+            Pubkey::find_program_address(
+                &[
+                    b"forward_minimum",
+                    relay_depository.as_ref(),
+                    Pubkey::default().as_ref()
+                ],
+                &program_id
+            )
+        );
+        assert_eq!(
+            find_pending_recover_address(&program_id, &forwarder),
+            Pubkey::find_program_address(&[b"pending_recover", forwarder.as_ref()], &program_id)
+        );
+        assert_eq!(
+            find_forwarder_event_authority_address(&program_id),
+            Pubkey::find_program_address(&[b"__event_authority"], &program_id)
+        );
+    }
+
+    #[test]
+    fn test_forwarder_derivations_are_per_deployment() {
+        let relay_depository_program = Pubkey::new_unique();
+        let relay_depository = Pubkey::new_unique();
+
+        assert_ne!(
+            find_forwarder_address(
+                &RELAY_FORWARDER_PROGRAM_ID,
+                &relay_depository_program,
+                &relay_depository
+            ),
+            find_forwarder_address(
+                &Pubkey::new_unique(),
+                &relay_depository_program,
+                &relay_depository
+            )
+        );
+        // Forwarders of distinct relay depositories never share an address
+        assert_ne!(
+            find_forwarder_address(
+                &RELAY_FORWARDER_PROGRAM_ID,
+                &relay_depository_program,
+                &relay_depository
+            ),
+            find_forwarder_address(
+                &RELAY_FORWARDER_PROGRAM_ID,
+                &relay_depository_program,
+                &Pubkey::new_unique()
+            )
+        );
+    }
+}
@@ -0,0 +1,11 @@
+//! Seeds and address derivations of the relay depository and relay forwarder accounts
+//!
+//! Used by the programs themselves and by off-chain code, so that derivations can't
+//! drift. Only depends on `solana-program`, so that services deriving deposit
+//! addresses don't pull in Anchor or the programs. The helpers only depend on
+//! `Pubkey` and don't allocate.
+
+#![cfg_attr(not(test), no_std)]
+
+pub mod depository;
+pub mod forwarder;
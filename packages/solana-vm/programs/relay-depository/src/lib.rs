@@ -8,16 +8,21 @@ use anchor_lang::{
         system_instruction, sysvar,
     },
 };
+use anchor_spl::memo::{build_memo, BuildMemo, Memo};
 use anchor_spl::token::Token;
 use anchor_spl::token_2022::spl_token_2022::{
     self,
     extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
 };
 use anchor_spl::{
-    associated_token::{get_associated_token_address_with_program_id, AssociatedToken, Create},
+    associated_token::{AssociatedToken, Create},
     token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
+pub mod pda;
+
+use pda::*;
+
 /// 
 /// A Solana relay depository smart contract built with the Anchor framework. 
 /// This contract allows users to deposit SOL or SPL tokens and execute transfers with verified signatures.
@@ -28,17 +33,16 @@ use anchor_spl::{
 // Constants
 //----------------------------------------
 
-const AUTHORIZED_PUBKEY: Pubkey = pubkey!("7LZXYdDQcRTsXnL9EU2zGkninV3yJsqX43m4RMPbs68u");
-
-const RELAY_DEPOSITORY_SEED: &[u8] = b"relay_depository";
-
-const USED_REQUEST_SEED: &[u8] = b"used_request";
+pub const DOMAIN_NAME: &[u8] = b"RelayDepository";
 
-const VAULT_SEED: &[u8] = b"vault";
+pub const DOMAIN_VERSION: &[u8] = b"1";
 
-const DOMAIN_NAME: &[u8] = b"RelayDepository";
+/// Delay (in seconds) between requesting and executing a vault sweep or a
+/// treasury change
+const SWEEP_TIMELOCK: i64 = 14 * 24 * 60 * 60;
 
-const DOMAIN_VERSION: &[u8] = b"1";
+/// Delay (in seconds) between announcing and executing an emergency withdraw
+const EMERGENCY_WITHDRAW_TIMELOCK: i64 = 14 * 24 * 60 * 60;
 
 //----------------------------------------
 // Program ID
@@ -58,6 +62,7 @@ pub mod relay_depository {
     ///
     /// Creates and initializes the relay depository account with the specified
     /// owner, allocator, and calculates the domain separator for cross-chain security.
+    /// Only the upgrade authority of the program can initialize it.
     ///
     /// # Parameters
     /// * `ctx` - The context containing the accounts
@@ -65,6 +70,7 @@ pub mod relay_depository {
     ///
     /// # Returns
     /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized
     pub fn initialize(ctx: Context<Initialize>, chain_id: String) -> Result<()> {
         let relay_depository = &mut ctx.accounts.relay_depository;
         relay_depository.owner = ctx.accounts.owner.key();
@@ -85,6 +91,9 @@ pub mod relay_depository {
     /// Update the allocator public key
     ///
     /// Allows the owner to change the authorized allocator that can sign transfer requests.
+    /// The change takes effect immediately, without the timelock of sweeps and
+    /// emergency withdraws, so that a compromised allocator can be replaced at once,
+    /// and emits an `AllocatorUpdatedEvent` so that it can be watched.
     ///
     /// # Parameters
     /// * `ctx` - The context containing the accounts
@@ -100,13 +109,23 @@ pub mod relay_depository {
             relay_depository.owner,
             CustomError::Unauthorized
         );
+        let previous_allocator = relay_depository.allocator;
         relay_depository.allocator = new_allocator;
+
+        emit_cpi!(AllocatorUpdatedEvent {
+            relay_depository: relay_depository.key(),
+            vault_config: None,
+            previous_allocator,
+            new_allocator,
+        });
+
         Ok(())
     }
 
     /// Update the owner public key
     ///
-    /// Allows the current owner to transfer ownership to a new address.
+    /// Allows the current owner to transfer ownership to a new address. The change
+    /// takes effect immediately and emits an `OwnerUpdatedEvent`.
     ///
     /// # Parameters
     /// * `ctx` - The context containing the accounts
@@ -122,7 +141,125 @@ pub mod relay_depository {
             relay_depository.owner,
             CustomError::Unauthorized
         );
+        let previous_owner = relay_depository.owner;
         relay_depository.owner = new_owner;
+
+        emit_cpi!(OwnerUpdatedEvent {
+            relay_depository: relay_depository.key(),
+            previous_owner,
+            new_owner,
+        });
+
+        Ok(())
+    }
+
+    /// Create an independent relay depository instance
+    ///
+    /// Creates a relay depository PDA derived from the creator and the given
+    /// instance id, with its own owner, allocator, vault and domain separator.
+    /// Anyone can create an instance; it is isolated from all other instances.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `instance_id` - The identifier of the instance, unique per creator
+    /// * `owner` - The public key of the owner of the instance
+    /// * `allocator` - The public key of the allocator of the instance
+    /// * `chain_id` - The chain identifier (e.g., "solana-mainnet")
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    pub fn create_instance(
+        ctx: Context<CreateInstance>,
+        instance_id: u64,
+        owner: Pubkey,
+        allocator: Pubkey,
+        chain_id: String,
+    ) -> Result<()> {
+        let relay_depository_key = ctx.accounts.relay_depository.key();
+        let relay_depository = &mut ctx.accounts.relay_depository;
+        relay_depository.owner = owner;
+        relay_depository.allocator = allocator;
+        relay_depository.vault_bump = ctx.bumps.vault;
+
+        // The instance is the verifying contract of its domain separator, so
+        // requests signed for one instance cannot be replayed on another
+        relay_depository.domain_separator = Some(create_domain_separator(
+            DOMAIN_NAME,
+            DOMAIN_VERSION,
+            chain_id.as_bytes(),
+            &relay_depository_key,
+        ));
+
+        emit_cpi!(InstanceCreatedEvent {
+            relay_depository: relay_depository_key,
+            instance_id,
+            creator: ctx.accounts.creator.key(),
+            owner,
+            allocator,
+            vault: ctx.accounts.vault.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Create an additional isolated vault
+    ///
+    /// Creates the configuration of a vault PDA derived from the relay depository
+    /// and the given index. The vault is controlled by its own allocator.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `index` - The index of the vault
+    /// * `allocator` - The public key of the allocator of the vault
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized
+    pub fn create_vault(ctx: Context<CreateVault>, index: u16, allocator: Pubkey) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        let vault_config = &mut ctx.accounts.vault_config;
+        vault_config.relay_depository = ctx.accounts.relay_depository.key();
+        vault_config.index = index;
+        vault_config.allocator = allocator;
+        vault_config.vault_bump = ctx.bumps.vault;
+
+        Ok(())
+    }
+
+    /// Update the allocator of an additional vault
+    ///
+    /// Allows the owner to change the allocator that can sign transfer requests
+    /// for the vault. Like `set_allocator`, the change takes effect immediately and
+    /// emits an `AllocatorUpdatedEvent`.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `new_allocator` - The public key of the new allocator
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized
+    pub fn set_vault_allocator(ctx: Context<SetVaultAllocator>, new_allocator: Pubkey) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+        let previous_allocator = ctx.accounts.vault_config.allocator;
+        ctx.accounts.vault_config.allocator = new_allocator;
+
+        emit_cpi!(AllocatorUpdatedEvent {
+            relay_depository: ctx.accounts.relay_depository.key(),
+            vault_config: Some(ctx.accounts.vault_config.key()),
+            previous_allocator,
+            new_allocator,
+        });
+
         Ok(())
     }
 
@@ -143,10 +280,7 @@ pub mod relay_depository {
         let relay_info = &ctx.accounts.relay_depository;
         
         // Validate PDA
-        let (expected_pda, _bump) = Pubkey::find_program_address(
-            &[RELAY_DEPOSITORY_SEED],
-            ctx.program_id
-        );
+        let (expected_pda, _bump) = find_relay_depository_address(ctx.program_id);
 
         require_keys_eq!(
             expected_pda,
@@ -269,27 +403,7 @@ pub mod relay_depository {
     /// # Returns
     /// * `Ok(())` on success
     pub fn deposit_native(ctx: Context<DepositNative>, amount: u64, id: [u8; 32]) -> Result<()> {
-        // Transfer to vault
-        invoke(
-            &system_instruction::transfer(
-                ctx.accounts.sender.key,
-                &ctx.accounts.vault.key(),
-                amount,
-            ),
-            &[
-                ctx.accounts.sender.to_account_info(),
-                ctx.accounts.vault.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
-
-        emit!(DepositEvent {
-            depositor: ctx.accounts.depositor.key(),
-            token: None,
-            amount,
-            id,
-        });
-
+        ctx.accounts.deposit(amount, id, ctx.bumps.event_authority)?;
         Ok(())
     }
 
@@ -306,73 +420,89 @@ pub mod relay_depository {
     /// # Returns
     /// * `Ok(())` on success
     pub fn deposit_token(ctx: Context<DepositToken>, amount: u64, id: [u8; 32]) -> Result<()> {
-        // Ensure token program is either SPL Token or SPL Token 2022
-        require!(
-            ctx.accounts.token_program.key() == anchor_spl::token::ID
-            || ctx.accounts.token_program.key() == anchor_spl::token_2022::ID,
-            CustomError::InvalidTokenProgram
-        );
-
-        // Ensure mint is owned by the token program
-        require_keys_eq!(
-            *ctx.accounts.mint.to_account_info().owner,
-            ctx.accounts.token_program.key(),
-            CustomError::InvalidMint
-        );
+        ctx.accounts.deposit(amount, id, ctx.bumps.event_authority)?;
+        Ok(())
+    }
 
-        // Create associated token account for the vault if needed
-        if ctx.accounts.vault_token_account.data_is_empty() {
-            anchor_spl::associated_token::create(CpiContext::new(
-                ctx.accounts.associated_token_program.to_account_info(),
-                Create {
-                    payer: ctx.accounts.sender.to_account_info(),
-                    associated_token: ctx.accounts.vault_token_account.to_account_info(),
-                    authority: ctx.accounts.vault.to_account_info(),
-                    mint: ctx.accounts.mint.to_account_info(),
-                    system_program: ctx.accounts.system_program.to_account_info(),
-                    token_program: ctx.accounts.token_program.to_account_info(),
-                },
-            ))?;
-        }
+    /// Deposit native SOL into the vault against an allocator-signed quote
+    ///
+    /// Verifies the allocator's signature of the quote, then deposits like
+    /// `deposit_native` using the quote id. The deposit is rejected if the quote
+    /// is expired, already used, or doesn't match the deposit.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `quote` - The quote accepted by the depositor
+    /// * `amount` - The amount of SOL to deposit
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if the quote is invalid or doesn't match the deposit
+    pub fn deposit_native_with_quote(
+        ctx: Context<DepositNativeWithQuote>,
+        quote: DepositQuote,
+        amount: u64,
+    ) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+        validate_deposit_quote(
+            &quote,
+            &deposit.relay_depository,
+            &deposit.vault_config,
+            &deposit.vault.key(),
+            &deposit.depositor.key(),
+            None,
+            &ctx.accounts.ix_sysvar,
+        )?;
 
-        let expected_vault_ata = get_associated_token_address_with_program_id(
-            &ctx.accounts.vault.key(),
-            &ctx.accounts.mint.key(),
-            &ctx.accounts.token_program.key(),
+        let credited_amount = deposit.deposit(amount, quote.id, ctx.bumps.deposit.event_authority)?;
+        require!(
+            credited_amount >= quote.min_amount,
+            CustomError::InsufficientDepositAmount
         );
 
-        // Check if the vault token account is the expected associated token account
-        require_keys_eq!(
-            ctx.accounts.vault_token_account.key(),
-            expected_vault_ata,
-            CustomError::InvalidVaultTokenAccount
-        );
+        ctx.accounts.used_quote.is_used = true;
 
-        // Calculate transfer fee
-        let mint = &ctx.accounts.mint;
-        let transfer_fee = get_transfer_fee(mint, amount)?;
+        Ok(())
+    }
 
-        // Transfer to vault
-        transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    mint: ctx.accounts.mint.to_account_info(),
-                    from: ctx.accounts.sender_token_account.to_account_info(),
-                    to: ctx.accounts.vault_token_account.to_account_info(),
-                    authority: ctx.accounts.sender.to_account_info(),
-                },
-            ),
-            amount,
-            mint.decimals,
+    /// Deposit SPL tokens into the vault against an allocator-signed quote
+    ///
+    /// Verifies the allocator's signature of the quote, then deposits like
+    /// `deposit_token` using the quote id. The deposit is rejected if the quote
+    /// is expired, already used, or doesn't match the deposit. The minimum amount applies to the
+    /// amount credited after transfer fees.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `quote` - The quote accepted by the depositor
+    /// * `amount` - The amount of tokens to deposit
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if the quote is invalid or doesn't match the deposit
+    pub fn deposit_token_with_quote(
+        ctx: Context<DepositTokenWithQuote>,
+        quote: DepositQuote,
+        amount: u64,
+    ) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+        validate_deposit_quote(
+            &quote,
+            &deposit.relay_depository,
+            &deposit.vault_config,
+            &deposit.vault.key(),
+            &deposit.depositor.key(),
+            Some(deposit.mint.key()),
+            &ctx.accounts.ix_sysvar,
         )?;
 
-        emit!(DepositEvent {
-            depositor: ctx.accounts.depositor.key(),
-            token: Some(ctx.accounts.mint.key()),
-            amount: amount - transfer_fee,
-            id,
-        });
+        let credited_amount = deposit.deposit(amount, quote.id, ctx.bumps.deposit.event_authority)?;
+        require!(
+            credited_amount >= quote.min_amount,
+            CustomError::InsufficientDepositAmount
+        );
+
+        ctx.accounts.used_quote.is_used = true;
 
         Ok(())
     }
@@ -380,7 +510,8 @@ pub mod relay_depository {
     /// Execute a transfer with allocator signature
     ///
     /// Verifies the allocator's signature, transfers tokens to the recipient,
-    /// and marks the request as used.
+    /// and marks the request as used. If the memo program is provided, the
+    /// request reference is also written as an SPL memo.
     ///
     /// # Parameters
     /// * `ctx` - The context containing the accounts
@@ -391,8 +522,9 @@ pub mod relay_depository {
     /// * `Err(error)` if signature is invalid or request can't be processed
     pub fn execute_transfer(ctx: Context<ExecuteTransfer>, request: TransferRequest) -> Result<()> {
         let relay_depository = &ctx.accounts.relay_depository;
+        let vault_config = &ctx.accounts.vault_config;
         let used_request = &mut ctx.accounts.used_request;
-        let vault_bump = relay_depository.vault_bump;
+        let vault_ledger = &mut ctx.accounts.vault_ledger;
 
         require!(
             !used_request.is_used,
@@ -423,10 +555,15 @@ pub mod relay_depository {
             &ctx.accounts.ix_sysvar,
         )?;
 
+        // Each vault is controlled by its own allocator
+        let allocator = vault_config
+            .as_ref()
+            .map_or(relay_depository.allocator, |vault_config| vault_config.allocator);
+
         validate_ed25519_signature_instruction(
             &signature_ix,
-            &relay_depository.allocator,
-            &request,
+            &allocator,
+            &request.get_hash(),
         )?;
 
         // Validate domain separator (if set)
@@ -439,7 +576,8 @@ pub mod relay_depository {
 
         used_request.is_used = true;
 
-        let seeds: &[&[u8]] = &[VAULT_SEED, &[vault_bump]];
+        let vault_seeds = VaultSeeds::new(relay_depository, vault_config);
+        let seeds: &[&[u8]] = &vault_seeds.seeds();
 
         // Execute the transfer based on the token type
         match request.token {
@@ -451,27 +589,14 @@ pub mod relay_depository {
                     CustomError::InvalidRecipient
                 );
 
-                // Ensure vault maintains rent-exempt status after transfer
-                let min_rent = Rent::get()?.minimum_balance(0);
-                let vault_balance = ctx.accounts.vault.lamports();
-                let max_transferable = vault_balance.saturating_sub(min_rent);
-                require!(
-                    request.amount <= max_transferable,
-                    CustomError::InsufficientVaultBalance
-                );
+                vault_ledger.open(ctx.accounts.vault.key(), None, ctx.accounts.vault.lamports());
 
-                invoke_signed(
-                    &system_instruction::transfer(
-                        &ctx.accounts.vault.key(),
-                        &ctx.accounts.recipient.key(),
-                        request.amount,
-                    ),
-                    &[
-                        ctx.accounts.vault.to_account_info(),
-                        ctx.accounts.recipient.to_account_info(),
-                        ctx.accounts.system_program.to_account_info(),
-                    ],
-                    &[seeds],
+                transfer_native_from_vault(
+                    &ctx.accounts.vault,
+                    &ctx.accounts.recipient,
+                    &ctx.accounts.system_program,
+                    request.amount,
+                    seeds,
                 )?;
             }
             // Transfer token
@@ -497,287 +622,2016 @@ pub mod relay_depository {
                     CustomError::InvalidRecipient
                 );
 
-                // Ensure token program is either SPL Token or SPL Token 2022
-                require!(
-                    ctx.accounts.token_program.key() == anchor_spl::token::ID
-                    || ctx.accounts.token_program.key() == anchor_spl::token_2022::ID,
-                    CustomError::InvalidTokenProgram
-                );
-                
-                // Ensure mint is owned by the token program
-                require_keys_eq!(
-                    *ctx.accounts.mint.as_ref().unwrap().to_account_info().owner,
-                    ctx.accounts.token_program.key(),
-                    CustomError::InvalidMint
-                );
+                vault_ledger.open(ctx.accounts.vault.key(), Some(mint.key()), vault_token_account.amount);
+                vault_ledger.total_fees += get_transfer_fee(mint, request.amount)? as u128;
 
-                transfer_checked(
-                    CpiContext::new_with_signer(
-                        ctx.accounts.token_program.to_account_info(),
-                        TransferChecked {
-                            mint: mint.to_account_info(),
-                            from: vault_token_account.to_account_info(),
-                            to: recipient_token_account.to_account_info(),
-                            authority: ctx.accounts.vault.to_account_info(),
-                        },
-                        &[seeds],
-                    ),
+                transfer_token_from_vault(
+                    &ctx.accounts.token_program,
+                    mint,
+                    vault_token_account,
+                    recipient_token_account,
+                    &ctx.accounts.vault,
                     request.amount,
-                    mint.decimals,
+                    seeds,
                 )?;
             }
         }
 
-        emit!(TransferExecutedEvent {
+        vault_ledger.total_withdrawn += request.amount as u128;
+
+        // Attach the request reference as a memo (if requested)
+        if let Some(memo_program) = &ctx.accounts.memo_program {
+            build_memo(
+                CpiContext::new(memo_program.to_account_info(), BuildMemo {}),
+                encode_hex(&request.reference).as_bytes(),
+            )?;
+        }
+
+        emit_cpi!(TransferExecutedEvent {
             id: used_request.key(),
-            request: request.clone(),
+            request,
             executor: ctx.accounts.executor.key(),
         });
 
         Ok(())
     }
-}
 
-//----------------------------------------
-// Account Structures
-//----------------------------------------
+    /// Atomically deposit and execute an allocator-signed swap
+    ///
+    /// Deposits the input of the swap from the depositor into the vault and pays
+    /// out the output of the swap from the vault to the recipient, in a single
+    /// instruction.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `request` - The swap request signed by the allocator
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if signature is invalid or the swap can't be processed
+    pub fn swap(ctx: Context<Swap>, request: SwapRequest) -> Result<()> {
+        let relay_depository = &ctx.accounts.relay_depository;
+        let vault_config = &ctx.accounts.vault_config;
 
-/// Relay depository account that stores configuration and state
-/// 
-/// This account is a PDA derived from the `RELAY_DEPOSITORY_SEED` and
-/// contains the ownership and allocation information.
-#[account]
-#[derive(InitSpace)]
-pub struct RelayDepository {
-    /// The owner of the relay depository who can update settings
-    pub owner: Pubkey,
-    /// The authorized allocator that can sign transfer requests
-    pub allocator: Pubkey,
-    /// The bump seed for the vault PDA, used for deriving the vault address
-    pub vault_bump: u8,
-    /// Expected domain separator hash for this deployment (Optional for upgrade compatibility)
-    pub domain_separator: Option<[u8; 32]>,
-}
+        require!(
+            Clock::get()?.unix_timestamp < request.expiration,
+            CustomError::SignatureExpired
+        );
 
-/// Account that tracks whether a transfer request has been used
-/// 
-/// This account is created for each transfer request to prevent replay attacks.
-#[account]
-#[derive(InitSpace)]
-pub struct UsedRequest {
-    /// Flag indicating whether the request has been processed
-    pub is_used: bool,
-}
+        require_keys_eq!(
+            ctx.accounts.vault.key(),
+            request.vault_address,
+            CustomError::InvalidVaultAddress
+        );
+        require_keys_eq!(
+            ctx.accounts.depositor.key(),
+            request.depositor,
+            CustomError::InvalidDepositor
+        );
+        require_keys_eq!(
+            ctx.accounts.recipient.key(),
+            request.recipient,
+            CustomError::InvalidRecipient
+        );
 
-//----------------------------------------
-// Instruction Contexts
-//----------------------------------------
+        // Validate domain separator (if set)
+        if let Some(expected_domain) = relay_depository.domain_separator {
+            require!(
+                request.domain == expected_domain,
+                CustomError::InvalidDomainSeparator
+            );
+        }
 
-/// Accounts required for initializing the relay depository
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    /// The relay depository account to be initialized
-    /// This is a PDA derived from the RELAY_DEPOSITORY_SEED
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + RelayDepository::INIT_SPACE,
-        seeds = [RELAY_DEPOSITORY_SEED],
-        constraint = owner.key() == AUTHORIZED_PUBKEY @ CustomError::Unauthorized,
-        bump
-    )]
-    pub relay_depository: Account<'info, RelayDepository>,
+        // Validate allocator signature
+        let cur_index: usize =
+            sysvar::instructions::load_current_index_checked(&ctx.accounts.ix_sysvar)?.into();
+        require!(cur_index > 0, CustomError::MalformedEd25519Data);
 
-    /// PDA that will hold SOL deposits
-    /// CHECK: This is a PDA derived from the VAULT_SEED
-    #[account(
-        mut,
-        seeds = [VAULT_SEED],
-        bump
-    )]
-    pub vault: UncheckedAccount<'info>,
+        let signature_ix = sysvar::instructions::load_instruction_at_checked(
+            cur_index - 1,
+            &ctx.accounts.ix_sysvar,
+        )?;
 
-    /// The owner account that pays for initialization
-    /// Must match the AUTHORIZED_PUBKEY
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        let allocator = vault_config
+            .as_ref()
+            .map_or(relay_depository.allocator, |vault_config| vault_config.allocator);
 
-    /// The allocator account that will be authorized to sign transfer requests
+        validate_ed25519_signature_instruction(&signature_ix, &allocator, &request.get_hash())?;
+
+        ctx.accounts.used_request.is_used = true;
+
+        // Deposit the input into the vault
+        let input_vault_ledger = &mut ctx.accounts.input_vault_ledger;
+        let (deposited_amount, input_fee) = match request.input_token {
+            None => {
+                input_vault_ledger.open(ctx.accounts.vault.key(), None, ctx.accounts.vault.lamports());
+
+                invoke(
+                    &system_instruction::transfer(
+                        ctx.accounts.depositor.key,
+                        &ctx.accounts.vault.key(),
+                        request.input_amount,
+                    ),
+                    &[
+                        ctx.accounts.depositor.to_account_info(),
+                        ctx.accounts.vault.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                )?;
+
+                (request.input_amount, 0)
+            }
+            Some(token_mint) => {
+                let mint = ctx.accounts.input_mint.as_ref().ok_or(CustomError::InvalidMint)?;
+
+                require_keys_eq!(token_mint, mint.key(), CustomError::InvalidMint);
+
+                let depositor_token_account = ctx
+                    .accounts
+                    .depositor_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_input_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+
+                input_vault_ledger.open(ctx.accounts.vault.key(), Some(mint.key()), vault_token_account.amount);
+
+                let transfer_fee = get_transfer_fee(mint, request.input_amount)?;
+
+                transfer_checked(
+                    CpiContext::new(
+                        ctx.accounts.input_token_program.to_account_info(),
+                        TransferChecked {
+                            mint: mint.to_account_info(),
+                            from: depositor_token_account.to_account_info(),
+                            to: vault_token_account.to_account_info(),
+                            authority: ctx.accounts.depositor.to_account_info(),
+                        },
+                    ),
+                    request.input_amount,
+                    mint.decimals,
+                )?;
+
+                (request.input_amount - transfer_fee, transfer_fee)
+            }
+        };
+
+        input_vault_ledger.total_deposited += deposited_amount as u128;
+        input_vault_ledger.total_fees += input_fee as u128;
+
+        // Pay out the output from the vault
+        let vault_seeds = VaultSeeds::new(relay_depository, vault_config);
+        let seeds: &[&[u8]] = &vault_seeds.seeds();
+        let output_vault_ledger = if request.output_token == request.input_token {
+            require!(
+                ctx.accounts.output_vault_ledger.is_none(),
+                CustomError::InvalidVaultLedger
+            );
+            input_vault_ledger
+        } else {
+            ctx.accounts
+                .output_vault_ledger
+                .as_mut()
+                .ok_or(CustomError::InvalidVaultLedger)?
+        };
+
+        match request.output_token {
+            None => {
+                output_vault_ledger.open(ctx.accounts.vault.key(), None, ctx.accounts.vault.lamports());
+
+                transfer_native_from_vault(
+                    &ctx.accounts.vault,
+                    &ctx.accounts.recipient,
+                    &ctx.accounts.system_program,
+                    request.output_amount,
+                    seeds,
+                )?;
+            }
+            Some(token_mint) => {
+                let mint = ctx.accounts.output_mint.as_ref().ok_or(CustomError::InvalidMint)?;
+
+                require_keys_eq!(token_mint, mint.key(), CustomError::InvalidMint);
+
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_output_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+                let recipient_token_account = ctx
+                    .accounts
+                    .recipient_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+
+                output_vault_ledger.open(ctx.accounts.vault.key(), Some(mint.key()), vault_token_account.amount);
+                output_vault_ledger.total_fees += get_transfer_fee(mint, request.output_amount)? as u128;
+
+                transfer_token_from_vault(
+                    &ctx.accounts.output_token_program,
+                    mint,
+                    vault_token_account,
+                    recipient_token_account,
+                    &ctx.accounts.vault,
+                    request.output_amount,
+                    seeds,
+                )?;
+            }
+        }
+
+        output_vault_ledger.total_withdrawn += request.output_amount as u128;
+
+        // The deposit is filled in the same instruction, so no `DepositEvent` is emitted
+        // for it: indexers would otherwise see an order waiting to be filled
+        emit_cpi!(SwapExecutedEvent {
+            request,
+            deposited_amount,
+            id: ctx.accounts.used_request.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Register funds sent directly to the vault as a deposit
+    ///
+    /// Compares the actual vault balance with the balance expected from the vault
+    /// ledger, and emits a deposit event crediting the difference to the given
+    /// depositor. The difference is added to the ledger, so the same funds can't be
+    /// registered twice, and the id is marked as used. Funds already in the vault
+    /// when the ledger was opened are part of its opening balance and can't be
+    /// registered. Only the owner or the allocator of the vault can attribute
+    /// direct deposits.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `depositor` - The account credited for the deposit
+    /// * `id` - A unique identifier for the deposit
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized or there is no unregistered balance
+    pub fn register_direct_deposit(
+        ctx: Context<RegisterDirectDeposit>,
+        depositor: Pubkey,
+        id: [u8; 32],
+    ) -> Result<()> {
+        let relay_depository = &ctx.accounts.relay_depository;
+        let allocator = ctx
+            .accounts
+            .vault_config
+            .as_ref()
+            .map_or(relay_depository.allocator, |vault_config| vault_config.allocator);
+
+        require!(
+            ctx.accounts.authority.key() == relay_depository.owner
+                || ctx.accounts.authority.key() == allocator,
+            CustomError::Unauthorized
+        );
+
+        let (token, balance) = match &ctx.accounts.mint {
+            None => (None, ctx.accounts.vault.lamports()),
+            Some(mint) => {
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+
+                (Some(mint.key()), vault_token_account.amount)
+            }
+        };
+
+        let vault_ledger = &mut ctx.accounts.vault_ledger;
+        let expected_balance = (vault_ledger.opening_balance as u128 + vault_ledger.total_deposited)
+            .saturating_sub(vault_ledger.total_withdrawn);
+        require!(
+            balance as u128 > expected_balance,
+            CustomError::NoDirectDeposit
+        );
+
+        // The difference is below the actual balance, so it fits in a u64
+        let amount = (balance as u128 - expected_balance) as u64;
+        vault_ledger.total_deposited += amount as u128;
+        ctx.accounts.used_direct_deposit.is_used = true;
+
+        emit_cpi!(DepositEvent {
+            depositor,
+            token,
+            amount,
+            id,
+            relay_depository: ctx.accounts.relay_depository.key(),
+            vault: ctx.accounts.vault.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Open the ledger of a vault for a token at the owner's expense
+    ///
+    /// Lets the owner fund the ledgers of the tokens the vault supports ahead of
+    /// deposits, so that depositors don't pay for them. Without it, the ledger is
+    /// created by the first instruction moving the token, at its signer's expense.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized or the ledger is already open
+    pub fn open_ledger(ctx: Context<OpenLedger>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        let (token, balance) = match &ctx.accounts.mint {
+            None => (None, ctx.accounts.vault.lamports()),
+            Some(mint) => {
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+
+                require_keys_eq!(
+                    vault_token_account.key(),
+                    find_vault_token_address(
+                        &ctx.accounts.vault.key(),
+                        &mint.key(),
+                        mint.to_account_info().owner,
+                    ),
+                    CustomError::InvalidVaultTokenAccount
+                );
+
+                (Some(mint.key()), get_token_account_balance(vault_token_account)?)
+            }
+        };
+
+        ctx.accounts.vault_ledger.open(ctx.accounts.vault.key(), token, balance);
+
+        Ok(())
+    }
+
+    /// Set the treasury address that vault sweeps are sent to
+    ///
+    /// Allows the owner to configure (or update) the cold storage address
+    /// receiving swept vault funds. The change is recorded as pending and only
+    /// takes effect through `execute_treasury_update` once `SWEEP_TIMELOCK` has
+    /// elapsed, so redirecting sweeps takes as long as a sweep itself.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `treasury` - The public key of the treasury
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized
+    pub fn set_treasury(ctx: Context<SetTreasury>, treasury: Pubkey) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        let executable_at = Clock::get()?.unix_timestamp + SWEEP_TIMELOCK;
+
+        let treasury_config = &mut ctx.accounts.treasury_config;
+        treasury_config.pending_treasury = Some(treasury);
+        treasury_config.executable_at = executable_at;
+
+        emit_cpi!(TreasuryUpdateRequestedEvent {
+            treasury,
+            executable_at,
+        });
+
+        Ok(())
+    }
+
+    /// Apply a pending treasury update once its timelock has elapsed
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized, no update is pending or the timelock has not elapsed
+    pub fn execute_treasury_update(ctx: Context<ExecuteTreasuryUpdate>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        let treasury_config = &mut ctx.accounts.treasury_config;
+        let treasury = treasury_config
+            .pending_treasury
+            .ok_or(CustomError::InvalidTreasury)?;
+
+        require!(
+            Clock::get()?.unix_timestamp >= treasury_config.executable_at,
+            CustomError::TimelockNotExpired
+        );
+
+        treasury_config.treasury = treasury;
+        treasury_config.pending_treasury = None;
+        treasury_config.executable_at = 0;
+
+        emit_cpi!(TreasuryUpdatedEvent { treasury });
+
+        Ok(())
+    }
+
+    /// Cancel a pending treasury update
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized or no update is pending
+    pub fn cancel_treasury_update(ctx: Context<CancelTreasuryUpdate>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        let treasury_config = &mut ctx.accounts.treasury_config;
+        let treasury = treasury_config
+            .pending_treasury
+            .take()
+            .ok_or(CustomError::InvalidTreasury)?;
+        treasury_config.executable_at = 0;
+
+        emit_cpi!(TreasuryUpdateCancelledEvent { treasury });
+
+        Ok(())
+    }
+
+    /// Request a sweep of vault funds to the treasury
+    ///
+    /// Records a pending sweep that can only be executed once `SWEEP_TIMELOCK`
+    /// has elapsed. The treasury is fixed at request time, and must have been
+    /// set through a (timelocked) treasury update.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `token` - The token mint to sweep (None for native SOL)
+    /// * `amount` - The amount to sweep
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized or no treasury is set
+    pub fn request_sweep(ctx: Context<RequestSweep>, token: Option<Pubkey>, amount: u64) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        require_keys_neq!(
+            ctx.accounts.treasury_config.treasury,
+            Pubkey::default(),
+            CustomError::InvalidTreasury
+        );
+
+        let executable_at = Clock::get()?.unix_timestamp + SWEEP_TIMELOCK;
+
+        let pending_sweep = &mut ctx.accounts.pending_sweep;
+        pending_sweep.token = token;
+        pending_sweep.amount = amount;
+        pending_sweep.treasury = ctx.accounts.treasury_config.treasury;
+        pending_sweep.executable_at = executable_at;
+
+        emit_cpi!(SweepRequestedEvent {
+            vault: ctx.accounts.vault.key(),
+            token,
+            amount,
+            treasury: pending_sweep.treasury,
+            executable_at,
+        });
+
+        Ok(())
+    }
+
+    /// Execute a pending sweep once its timelock has elapsed
+    ///
+    /// Transfers the requested amount from the vault to the treasury recorded
+    /// in the pending sweep, then closes the pending sweep account.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized or the timelock has not elapsed
+    pub fn execute_sweep(ctx: Context<ExecuteSweep>) -> Result<()> {
+        let relay_depository = &ctx.accounts.relay_depository;
+        let pending_sweep = &ctx.accounts.pending_sweep;
+
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        require!(
+            Clock::get()?.unix_timestamp >= pending_sweep.executable_at,
+            CustomError::TimelockNotExpired
+        );
+
+        let vault_seeds = VaultSeeds::new(relay_depository, &ctx.accounts.vault_config);
+        let seeds: &[&[u8]] = &vault_seeds.seeds();
+        let vault_ledger = &mut ctx.accounts.vault_ledger;
+
+        match pending_sweep.token {
+            None => {
+                vault_ledger.open(ctx.accounts.vault.key(), None, ctx.accounts.vault.lamports());
+
+                transfer_native_from_vault(
+                    &ctx.accounts.vault,
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.system_program,
+                    pending_sweep.amount,
+                    seeds,
+                )?;
+            }
+            Some(token_mint) => {
+                let mint = ctx.accounts.mint.as_ref().ok_or(CustomError::InvalidMint)?;
+
+                require_keys_eq!(token_mint, mint.key(), CustomError::InvalidMint);
+
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+                let treasury_token_account = ctx
+                    .accounts
+                    .treasury_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+
+                vault_ledger.open(ctx.accounts.vault.key(), Some(mint.key()), vault_token_account.amount);
+                vault_ledger.total_fees += get_transfer_fee(mint, pending_sweep.amount)? as u128;
+
+                transfer_token_from_vault(
+                    &ctx.accounts.token_program,
+                    mint,
+                    vault_token_account,
+                    treasury_token_account,
+                    &ctx.accounts.vault,
+                    pending_sweep.amount,
+                    seeds,
+                )?;
+            }
+        }
+
+        vault_ledger.total_withdrawn += pending_sweep.amount as u128;
+
+        emit_cpi!(SweepExecutedEvent {
+            vault: ctx.accounts.vault.key(),
+            token: pending_sweep.token,
+            amount: pending_sweep.amount,
+            treasury: pending_sweep.treasury,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a pending sweep
+    ///
+    /// Closes the pending sweep account without moving any funds.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized
+    pub fn cancel_sweep(ctx: Context<CancelSweep>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        emit_cpi!(SweepCancelledEvent {
+            vault: ctx.accounts.vault.key(),
+            token: ctx.accounts.pending_sweep.token,
+            amount: ctx.accounts.pending_sweep.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Announce an emergency withdraw of all vault funds
+    ///
+    /// Starts the `EMERGENCY_WITHDRAW_TIMELOCK` delay after which the owner can
+    /// drain the vault to the announced recipient, without any allocator signature.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    /// * `recipient` - The account receiving the vault funds
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized
+    pub fn announce_emergency_withdraw(ctx: Context<AnnounceEmergencyWithdraw>, recipient: Pubkey) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        let announced_at = Clock::get()?.unix_timestamp;
+        let executable_at = announced_at + EMERGENCY_WITHDRAW_TIMELOCK;
+
+        let pending_emergency_withdraw = &mut ctx.accounts.pending_emergency_withdraw;
+        pending_emergency_withdraw.recipient = recipient;
+        pending_emergency_withdraw.announced_at = announced_at;
+        pending_emergency_withdraw.executable_at = executable_at;
+
+        emit_cpi!(EmergencyWithdrawAnnouncedEvent {
+            vault: ctx.accounts.vault.key(),
+            recipient,
+            executable_at,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw the full vault balance of one asset to the announced recipient
+    ///
+    /// Can be called once per asset (native SOL or token mint) and announcement
+    /// after the emergency withdraw timelock has elapsed: each execution creates
+    /// an `ExecutedEmergencyWithdraw` marker for the asset, so a second call for
+    /// the same asset fails. The announcement stays active until it is cancelled.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized, the timelock has not elapsed or the asset was already withdrawn
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        let relay_depository = &ctx.accounts.relay_depository;
+        let pending_emergency_withdraw = &ctx.accounts.pending_emergency_withdraw;
+
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        require!(
+            Clock::get()?.unix_timestamp >= pending_emergency_withdraw.executable_at,
+            CustomError::TimelockNotExpired
+        );
+
+        let vault_seeds = VaultSeeds::new(relay_depository, &ctx.accounts.vault_config);
+        let seeds: &[&[u8]] = &vault_seeds.seeds();
+
+        // Withdraw the full balance of the asset
+        let vault_ledger = &mut ctx.accounts.vault_ledger;
+
+        let (token, amount) = match &ctx.accounts.mint {
+            None => {
+                vault_ledger.open(ctx.accounts.vault.key(), None, ctx.accounts.vault.lamports());
+
+                let min_rent = Rent::get()?.minimum_balance(0);
+                let amount = ctx.accounts.vault.lamports().saturating_sub(min_rent);
+
+                transfer_native_from_vault(
+                    &ctx.accounts.vault,
+                    &ctx.accounts.recipient,
+                    &ctx.accounts.system_program,
+                    amount,
+                    seeds,
+                )?;
+
+                (None, amount)
+            }
+            Some(mint) => {
+                let vault_token_account = ctx
+                    .accounts
+                    .vault_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+                let recipient_token_account = ctx
+                    .accounts
+                    .recipient_token_account
+                    .as_ref()
+                    .ok_or(CustomError::InvalidMint)?;
+                let amount = vault_token_account.amount;
+
+                vault_ledger.open(ctx.accounts.vault.key(), Some(mint.key()), amount);
+                vault_ledger.total_fees += get_transfer_fee(mint, amount)? as u128;
+
+                transfer_token_from_vault(
+                    &ctx.accounts.token_program,
+                    mint,
+                    vault_token_account,
+                    recipient_token_account,
+                    &ctx.accounts.vault,
+                    amount,
+                    seeds,
+                )?;
+
+                (Some(mint.key()), amount)
+            }
+        };
+
+        vault_ledger.total_withdrawn += amount as u128;
+
+        let executed_emergency_withdraw = &mut ctx.accounts.executed_emergency_withdraw;
+        executed_emergency_withdraw.token = token;
+        executed_emergency_withdraw.amount = amount;
+
+        emit_cpi!(EmergencyWithdrawExecutedEvent {
+            vault: ctx.accounts.vault.key(),
+            token,
+            amount,
+            recipient: pending_emergency_withdraw.recipient,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel an announced emergency withdraw
+    ///
+    /// Closes the pending emergency withdraw account, ending the emergency mode.
+    ///
+    /// # Parameters
+    /// * `ctx` - The context containing the accounts
+    ///
+    /// # Returns
+    /// * `Ok(())` on success
+    /// * `Err(error)` if not authorized
+    pub fn cancel_emergency_withdraw(ctx: Context<CancelEmergencyWithdraw>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            ctx.accounts.relay_depository.owner,
+            CustomError::Unauthorized
+        );
+
+        emit_cpi!(EmergencyWithdrawCancelledEvent {
+            vault: ctx.accounts.vault.key(),
+            recipient: ctx.accounts.pending_emergency_withdraw.recipient,
+        });
+
+        Ok(())
+    }
+}
+
+//----------------------------------------
+// Account Structures
+//----------------------------------------
+
+/// Relay depository account that stores configuration and state
+/// 
+/// The default relay depository is a PDA derived from the `RELAY_DEPOSITORY_SEED`,
+/// while additional instances are derived from the `RELAY_DEPOSITORY_SEED`, their
+/// creator and instance id. It contains the ownership and allocation information.
+#[account]
+#[derive(InitSpace)]
+pub struct RelayDepository {
+    /// The owner of the relay depository who can update settings
+    pub owner: Pubkey,
+    /// The authorized allocator that can sign transfer requests
+    pub allocator: Pubkey,
+    /// The bump seed for the vault PDA, used for deriving the vault address
+    pub vault_bump: u8,
+    /// Expected domain separator hash for this deployment (Optional for upgrade compatibility)
+    pub domain_separator: Option<[u8; 32]>,
+}
+
+/// Configuration of an additional vault
+///
+/// This account is a PDA derived from the `VAULT_CONFIG_SEED`, the relay depository
+/// and the vault index. The vault itself is a PDA derived from the `VAULT_SEED`,
+/// the relay depository and the vault index.
+#[account]
+#[derive(InitSpace)]
+pub struct VaultConfig {
+    /// The relay depository the vault belongs to
+    pub relay_depository: Pubkey,
+    /// The index of the vault
+    pub index: u16,
+    /// The authorized allocator that can sign transfer requests for the vault
+    pub allocator: Pubkey,
+    /// The bump seed for the vault PDA
+    pub vault_bump: u8,
+}
+
+/// Accounting of the funds of a vault for a single token
+///
+/// This account is a PDA derived from the `VAULT_LEDGER_SEED`, the vault and the
+/// token mint (the default public key for native SOL). It is opened by the owner
+/// through `open_ledger`, or otherwise created on first use by whichever instruction
+/// moves the token in or out of the vault, at the expense of the signer of that
+/// instruction (the sender for deposits), and is never closed. It is updated by
+/// every deposit, transfer, swap, sweep and emergency withdraw. The balance
+/// expected from the ledger (`opening_balance + total_deposited - total_withdrawn`)
+/// can be reconciled against the actual vault balance.
+#[account]
+#[derive(InitSpace)]
+pub struct VaultLedger {
+    /// The vault the ledger belongs to
+    pub vault: Pubkey,
+    /// The token mint (None for native SOL)
+    pub token: Option<Pubkey>,
+    /// The vault balance when the ledger was created
+    pub opening_balance: u64,
+    /// The total amount credited to the vault by deposits (net of transfer fees)
+    pub total_deposited: u128,
+    /// The total amount debited from the vault by transfers, swaps, sweeps and emergency withdraws
+    pub total_withdrawn: u128,
+    /// The total transfer fees withheld on deposits and withdrawals
+    pub total_fees: u128,
+}
+
+impl VaultLedger {
+    /// Records the vault, token and current vault balance when the ledger is first used
+    pub fn open(&mut self, vault: Pubkey, token: Option<Pubkey>, balance: u64) {
+        if self.vault == Pubkey::default() {
+            self.vault = vault;
+            self.token = token;
+            self.opening_balance = balance;
+        }
+    }
+}
+
+/// Account that tracks whether a transfer request has been used
+/// 
+/// This account is created for each transfer request to prevent replay attacks.
+#[account]
+#[derive(InitSpace)]
+pub struct UsedRequest {
+    /// Flag indicating whether the request has been processed
+    pub is_used: bool,
+}
+
+/// Treasury configuration of a relay depository
+///
+/// This account is a PDA derived from the `TREASURY_SEED` and the relay
+/// depository, and holds the destination of vault sweeps along with any
+/// pending (timelocked) change of it.
+#[account]
+#[derive(InitSpace)]
+pub struct TreasuryConfig {
+    /// The owner-controlled address receiving swept vault funds
+    pub treasury: Pubkey,
+    /// The treasury set by a pending update, if any
+    pub pending_treasury: Option<Pubkey>,
+    /// The timestamp after which the pending update can be executed
+    pub executable_at: i64,
+}
+
+/// Sweep of vault funds awaiting its timelock
+///
+/// This account is a PDA derived from the `PENDING_SWEEP_SEED` and the vault,
+/// so only one sweep can be pending per vault at a time.
+#[account]
+#[derive(InitSpace)]
+pub struct PendingSweep {
+    /// The token mint to sweep (None for native SOL)
+    pub token: Option<Pubkey>,
+    /// The amount to sweep
+    pub amount: u64,
+    /// The treasury receiving the funds, fixed when the sweep is requested
+    pub treasury: Pubkey,
+    /// The timestamp after which the sweep can be executed
+    pub executable_at: i64,
+}
+
+/// Announced emergency withdraw of all vault funds
+///
+/// This account is a PDA derived from the `PENDING_EMERGENCY_WITHDRAW_SEED` and
+/// the vault. While it exists, the owner can drain the vault once the timelock
+/// has elapsed.
+#[account]
+#[derive(InitSpace)]
+pub struct PendingEmergencyWithdraw {
+    /// The account receiving the vault funds
+    pub recipient: Pubkey,
+    /// The timestamp at which the emergency withdraw was announced
+    pub announced_at: i64,
+    /// The timestamp after which the vault can be drained
+    pub executable_at: i64,
+}
+
+/// Asset drained by an emergency withdraw
+///
+/// This account is a PDA derived from the `EXECUTED_EMERGENCY_WITHDRAW_SEED`, the
+/// vault, the token mint (default pubkey for native SOL) and the announcement
+/// timestamp, so each asset can only be withdrawn once per announcement.
+#[account]
+#[derive(InitSpace)]
+pub struct ExecutedEmergencyWithdraw {
+    /// The token mint (None for native SOL)
+    pub token: Option<Pubkey>,
+    /// The amount withdrawn
+    pub amount: u64,
+}
+
+//----------------------------------------
+// Instruction Contexts
+//----------------------------------------
+
+/// Accounts required for initializing the relay depository
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    /// The relay depository account to be initialized
+    /// This is a PDA derived from the RELAY_DEPOSITORY_SEED
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + RelayDepository::INIT_SPACE,
+        seeds = [RELAY_DEPOSITORY_SEED],
+        bump
+    )]
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// PDA that will hold SOL deposits
+    /// CHECK: This is a PDA derived from the VAULT_SEED
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The owner account that pays for initialization
+    /// Must match the upgrade authority of the program
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The allocator account that will be authorized to sign transfer requests
     /// CHECK: Used as public key only
     pub allocator: UncheckedAccount<'info>,
 
-    // System program
-    pub system_program: Program<'info, System>,
-}
+    /// The relay depository program
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ CustomError::InvalidProgramData
+    )]
+    pub program: Program<'info, crate::program::RelayDepository>,
+
+    /// The program data account of the relay depository program
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(owner.key()) @ CustomError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    // System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for creating a relay depository instance
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(instance_id: u64)]
+pub struct CreateInstance<'info> {
+    /// The relay depository instance to be initialized
+    /// This is a PDA derived from the RELAY_DEPOSITORY_SEED, creator and instance id
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + RelayDepository::INIT_SPACE,
+        seeds = [RELAY_DEPOSITORY_SEED, creator.key().as_ref(), &instance_id.to_le_bytes()],
+        bump
+    )]
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// PDA that will hold the instance funds
+    /// CHECK: This is a PDA derived from the VAULT_SEED and relay depository
+    #[account(
+        seeds = [VAULT_SEED, relay_depository.key().as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The creator of the instance (pays for initialization)
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The system program
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for updating the allocator
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetAllocator<'info> {
+    /// The relay depository account to update
+    #[account(mut)]
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The owner of the relay depository
+    pub owner: Signer<'info>,
+}
+
+/// Accounts required for updating the owner
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetOwner<'info> {
+    /// The relay depository account to update
+    #[account(mut)]
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The current owner of the relay depository
+    pub owner: Signer<'info>,
+}
+
+/// Accounts required for creating an additional vault
+#[derive(Accounts)]
+#[instruction(index: u16)]
+pub struct CreateVault<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The vault configuration to be initialized
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + VaultConfig::INIT_SPACE,
+        seeds = [VAULT_CONFIG_SEED, relay_depository.key().as_ref(), &index.to_le_bytes()],
+        bump
+    )]
+    pub vault_config: Account<'info, VaultConfig>,
+
+    /// PDA that will hold the vault funds
+    /// CHECK: This is a PDA derived from the VAULT_SEED, relay depository and index
+    #[account(
+        seeds = [VAULT_SEED, relay_depository.key().as_ref(), &index.to_le_bytes()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The owner of the relay depository (also pays for the vault configuration)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The system program
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for updating the allocator of an additional vault
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetVaultAllocator<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The vault configuration to update
+    #[account(
+        mut,
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Account<'info, VaultConfig>,
+
+    /// The owner of the relay depository
+    pub owner: Signer<'info>,
+}
+
+/// Accounts required for migrating domain separator
+#[derive(Accounts)]
+pub struct MigrateDomainSeparator<'info> {
+    /// The relay depository account to migrate in-place
+    /// CHECK: This is the existing relay depository account to be migrated
+    #[account(
+        mut,
+        seeds = [RELAY_DEPOSITORY_SEED],
+        bump
+    )]
+    pub relay_depository: UncheckedAccount<'info>,
+
+    /// The owner of the relay depository (also pays for reallocation)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// System program for reallocation
+    pub system_program: Program<'info, System>,
+
+    /// The instruction sysvar for reentrancy protection
+    /// CHECK: The instruction sysvar for reentrancy protection
+    pub ix_sysvar: AccountInfo<'info>,
+}
+
+/// Accounts required for depositing native currency
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositNative<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The sender of the deposit
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The account credited for the deposit
+    /// CHECK: The account credited for the deposit
+    pub depositor: UncheckedAccount<'info>,
+
+    /// The vault PDA that will receive the SOL
+    /// CHECK: The vault PDA that will receive the SOL
+    #[account(
+        mut,
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The ledger of the vault for native SOL, created at the sender's expense if the
+    /// owner hasn't opened it
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + VaultLedger::INIT_SPACE,
+        seeds = [VAULT_LEDGER_SEED, vault.key().as_ref(), Pubkey::default().as_ref()],
+        bump
+    )]
+    pub vault_ledger: Account<'info, VaultLedger>,
+
+    /// The system program
+    pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
+}
+
+impl<'info> DepositNative<'info> {
+    /// Transfers SOL from the sender to the vault and emits a deposit event
+    ///
+    /// Returns the amount credited to the depositor.
+    fn deposit(&mut self, amount: u64, id: [u8; 32], event_authority_bump: u8) -> Result<u64> {
+        let vault_ledger = &mut self.vault_ledger;
+        vault_ledger.open(self.vault.key(), None, self.vault.lamports());
+
+        // Transfer to vault
+        invoke(
+            &system_instruction::transfer(
+                self.sender.key,
+                &self.vault.key(),
+                amount,
+            ),
+            &[
+                self.sender.to_account_info(),
+                self.vault.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+        )?;
+
+        vault_ledger.total_deposited += amount as u128;
+
+        emit_cpi_event(
+            &self.event_authority,
+            event_authority_bump,
+            &DepositEvent {
+                depositor: self.depositor.key(),
+                token: None,
+                amount,
+                id,
+                relay_depository: self.relay_depository.key(),
+                vault: self.vault.key(),
+            },
+        )?;
+
+        Ok(amount)
+    }
+}
+
+/// Accounts required for depositing tokens
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositToken<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The sender of the deposit
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The account credited for the deposit
+    /// CHECK: The account credited for the deposit
+    pub depositor: UncheckedAccount<'info>,
+
+    /// The vault PDA that will receive the tokens
+    /// CHECK: The vault PDA that will receive the tokens
+    #[account(
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The mint of the token being deposited
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The sender's token account
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sender,
+        token::token_program = token_program
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: The vault's token account
+    #[account(mut)]
+    pub vault_token_account: UncheckedAccount<'info>,
+
+    /// The ledger of the vault for the token, created at the sender's expense if the
+    /// owner hasn't opened it
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + VaultLedger::INIT_SPACE,
+        seeds = [VAULT_LEDGER_SEED, vault.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub vault_ledger: Account<'info, VaultLedger>,
+
+    /// The token program
+    pub token_program: Interface<'info, TokenInterface>,
+    /// The associated token program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// The system program
+    pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
+}
+
+impl<'info> DepositToken<'info> {
+    /// Transfers tokens from the sender to the vault and emits a deposit event
+    ///
+    /// Returns the amount credited to the depositor (net of transfer fees).
+    fn deposit(&mut self, amount: u64, id: [u8; 32], event_authority_bump: u8) -> Result<u64> {
+        // Ensure token program is either SPL Token or SPL Token 2022
+        require!(
+            self.token_program.key() == anchor_spl::token::ID
+            || self.token_program.key() == anchor_spl::token_2022::ID,
+            CustomError::InvalidTokenProgram
+        );
+
+        // Ensure mint is owned by the token program
+        require_keys_eq!(
+            *self.mint.to_account_info().owner,
+            self.token_program.key(),
+            CustomError::InvalidMint
+        );
+
+        // Create associated token account for the vault if needed
+        if self.vault_token_account.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.sender.to_account_info(),
+                    associated_token: self.vault_token_account.to_account_info(),
+                    authority: self.vault.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                },
+            ))?;
+        }
+
+        let expected_vault_ata = find_vault_token_address(
+            &self.vault.key(),
+            &self.mint.key(),
+            &self.token_program.key(),
+        );
+
+        // Check if the vault token account is the expected associated token account
+        require_keys_eq!(
+            self.vault_token_account.key(),
+            expected_vault_ata,
+            CustomError::InvalidVaultTokenAccount
+        );
+
+        let vault_ledger = &mut self.vault_ledger;
+        vault_ledger.open(
+            self.vault.key(),
+            Some(self.mint.key()),
+            get_token_account_balance(&self.vault_token_account)?,
+        );
+
+        // Calculate transfer fee
+        let mint = &self.mint;
+        let transfer_fee = get_transfer_fee(mint, amount)?;
+
+        // Transfer to vault
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    mint: self.mint.to_account_info(),
+                    from: self.sender_token_account.to_account_info(),
+                    to: self.vault_token_account.to_account_info(),
+                    authority: self.sender.to_account_info(),
+                },
+            ),
+            amount,
+            mint.decimals,
+        )?;
+
+        vault_ledger.total_deposited += (amount - transfer_fee) as u128;
+        vault_ledger.total_fees += transfer_fee as u128;
+
+        emit_cpi_event(
+            &self.event_authority,
+            event_authority_bump,
+            &DepositEvent {
+                depositor: self.depositor.key(),
+                token: Some(self.mint.key()),
+                amount: amount - transfer_fee,
+                id,
+                relay_depository: self.relay_depository.key(),
+                vault: self.vault.key(),
+            },
+        )?;
+
+        Ok(amount - transfer_fee)
+    }
+}
+
+/// Accounts required for depositing native currency with a signed quote
+#[derive(Accounts)]
+#[instruction(quote: DepositQuote)]
+pub struct DepositNativeWithQuote<'info> {
+    /// The accounts of the native deposit
+    pub deposit: DepositNative<'info>,
+
+    /// The account marking the quote as used, paid by the sender, so that it can't be replayed
+    #[account(
+        init,
+        payer = deposit.sender,
+        space = 8 + UsedRequest::INIT_SPACE,
+        seeds = [
+            USED_QUOTE_SEED,
+            &quote.get_hash().to_bytes()[..],
+        ],
+        bump
+    )]
+    pub used_quote: Account<'info, UsedRequest>,
+
+    /// The instruction sysvar for ed25519 verification
+    /// CHECK: The instruction sysvar for ed25519 verification
+    #[account(address = sysvar::instructions::ID)]
+    pub ix_sysvar: AccountInfo<'info>,
+
+    /// The system program
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for depositing tokens with a signed quote
+#[derive(Accounts)]
+#[instruction(quote: DepositQuote)]
+pub struct DepositTokenWithQuote<'info> {
+    /// The accounts of the token deposit
+    pub deposit: DepositToken<'info>,
+
+    /// The account marking the quote as used, paid by the sender, so that it can't be replayed
+    #[account(
+        init,
+        payer = deposit.sender,
+        space = 8 + UsedRequest::INIT_SPACE,
+        seeds = [
+            USED_QUOTE_SEED,
+            &quote.get_hash().to_bytes()[..],
+        ],
+        bump
+    )]
+    pub used_quote: Account<'info, UsedRequest>,
+
+    /// The instruction sysvar for ed25519 verification
+    /// CHECK: The instruction sysvar for ed25519 verification
+    #[account(address = sysvar::instructions::ID)]
+    pub ix_sysvar: AccountInfo<'info>,
+
+    /// The system program
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for executing a transfer
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(request: TransferRequest)]
+pub struct ExecuteTransfer<'info> {
+
+    /// The relay depository account
+    /// CHECK: The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The executor of the transfer
+    /// CHECK: The executor of the transfer
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    /// The recipient of the transfer
+    /// CHECK: The recipient of the transfer
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// The vault PDA that will receive the tokens
+    /// CHECK: The vault PDA that will receive the tokens
+    #[account(
+        mut,
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The mint of the token being transferred
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The recipient's token account
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault's token account
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The account that tracks whether a transfer request has been used
+    /// 
+    /// This account is created for each transfer request to prevent replay attacks.
+    #[account(
+        init,
+        payer = executor,
+        space = 8 + UsedRequest::INIT_SPACE,
+        seeds = [
+            USED_REQUEST_SEED,
+            &request.get_hash().to_bytes()[..],
+        ],
+        bump
+    )]
+    pub used_request: Account<'info, UsedRequest>,
+
+    /// The ledger of the vault for the transferred token, created on first use
+    #[account(
+        init_if_needed,
+        payer = executor,
+        space = 8 + VaultLedger::INIT_SPACE,
+        seeds = [VAULT_LEDGER_SEED, vault.key().as_ref(), request.token.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub vault_ledger: Account<'info, VaultLedger>,
+
+    /// The instruction sysvar for ed25519 verification
+    /// CHECK: The instruction sysvar for ed25519 verification
+    pub ix_sysvar: AccountInfo<'info>,
+
+    /// The token program
+    pub token_program: Interface<'info, TokenInterface>,
+    /// The associated token program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// The system program
+    pub system_program: Program<'info, System>,
+
+    /// The SPL memo program, used to attach the request reference as a memo
+    pub memo_program: Option<Program<'info, Memo>>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
+}
+
+/// Accounts required for executing a swap
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(request: SwapRequest)]
+pub struct Swap<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The depositor providing the input of the swap
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// The recipient of the output of the swap
+    /// CHECK: The recipient of the output of the swap
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// The vault PDA receiving the input and paying out the output
+    /// CHECK: The vault PDA receiving the input and paying out the output
+    #[account(
+        mut,
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The account that tracks whether the swap request has been used
+    #[account(
+        init,
+        payer = depositor,
+        space = 8 + UsedRequest::INIT_SPACE,
+        seeds = [
+            USED_REQUEST_SEED,
+            &request.get_hash().to_bytes()[..],
+        ],
+        bump
+    )]
+    pub used_request: Box<Account<'info, UsedRequest>>,
+
+    /// The mint of the input token (None for native SOL)
+    pub input_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// The depositor's input token account
+    #[account(
+        mut,
+        associated_token::mint = input_mint,
+        associated_token::authority = depositor,
+        associated_token::token_program = input_token_program
+    )]
+    pub depositor_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The vault's input token account
+    #[account(
+        mut,
+        associated_token::mint = input_mint,
+        associated_token::authority = vault,
+        associated_token::token_program = input_token_program
+    )]
+    pub vault_input_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The ledger of the vault for the input token, created on first use
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + VaultLedger::INIT_SPACE,
+        seeds = [VAULT_LEDGER_SEED, vault.key().as_ref(), request.input_token.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub input_vault_ledger: Box<Account<'info, VaultLedger>>,
 
-/// Accounts required for updating the allocator
-#[derive(Accounts)]
-pub struct SetAllocator<'info> {
-    /// The relay depository account to update
+    /// The mint of the output token (None for native SOL)
+    pub output_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// The recipient's output token account
     #[account(
         mut,
-        seeds = [RELAY_DEPOSITORY_SEED],
+        associated_token::mint = output_mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = output_token_program
+    )]
+    pub recipient_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The vault's output token account
+    #[account(
+        mut,
+        associated_token::mint = output_mint,
+        associated_token::authority = vault,
+        associated_token::token_program = output_token_program
+    )]
+    pub vault_output_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The ledger of the vault for the output token, created on first use
+    ///
+    /// None when the output token is the input token, whose ledger records both legs.
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + VaultLedger::INIT_SPACE,
+        seeds = [VAULT_LEDGER_SEED, vault.key().as_ref(), request.output_token.unwrap_or_default().as_ref()],
         bump
     )]
-    pub relay_depository: Account<'info, RelayDepository>,
+    pub output_vault_ledger: Option<Box<Account<'info, VaultLedger>>>,
 
-    /// The owner of the relay depository
-    pub owner: Signer<'info>,
+    /// The instruction sysvar for ed25519 verification
+    /// CHECK: The instruction sysvar for ed25519 verification
+    #[account(address = sysvar::instructions::ID)]
+    pub ix_sysvar: AccountInfo<'info>,
+
+    /// The token program of the input token
+    pub input_token_program: Interface<'info, TokenInterface>,
+    /// The token program of the output token
+    pub output_token_program: Interface<'info, TokenInterface>,
+    /// The system program
+    pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
 }
 
-/// Accounts required for updating the owner
+/// Accounts required for registering a direct deposit
+#[event_cpi]
 #[derive(Accounts)]
-pub struct SetOwner<'info> {
-    /// The relay depository account to update
+#[instruction(depositor: Pubkey, id: [u8; 32])]
+pub struct RegisterDirectDeposit<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The owner or allocator attributing the deposit, paying for the used id marker
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The vault PDA that received the funds
+    /// CHECK: The vault PDA that received the funds
+    #[account(
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The mint of the token received (None for native SOL)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The vault's token account
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The ledger of the vault for the received token
     #[account(
         mut,
-        seeds = [RELAY_DEPOSITORY_SEED],
+        seeds = [
+            VAULT_LEDGER_SEED,
+            vault.key().as_ref(),
+            mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref(),
+        ],
+        bump
+    )]
+    pub vault_ledger: Account<'info, VaultLedger>,
+
+    /// The account marking the deposit id as used by the vault, so that it can't be registered twice
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + UsedRequest::INIT_SPACE,
+        seeds = [USED_DIRECT_DEPOSIT_SEED, vault.key().as_ref(), &id[..]],
         bump
     )]
+    pub used_direct_deposit: Account<'info, UsedRequest>,
+
+    /// The token program
+    pub token_program: Interface<'info, TokenInterface>,
+    /// The system program
+    pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
+}
+
+/// Accounts required for opening the ledger of a vault
+#[derive(Accounts)]
+pub struct OpenLedger<'info> {
+    /// The relay depository account
     pub relay_depository: Account<'info, RelayDepository>,
 
-    /// The current owner of the relay depository
+    /// The owner of the relay depository (also pays for the ledger)
+    #[account(mut)]
     pub owner: Signer<'info>,
+
+    /// The vault PDA the ledger belongs to
+    /// CHECK: The vault PDA the ledger belongs to
+    #[account(
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The mint of the token (None for native SOL)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: The vault's token account, which may not exist yet
+    pub vault_token_account: Option<UncheckedAccount<'info>>,
+
+    /// The ledger of the vault for the token
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + VaultLedger::INIT_SPACE,
+        seeds = [
+            VAULT_LEDGER_SEED,
+            vault.key().as_ref(),
+            mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref(),
+        ],
+        bump
+    )]
+    pub vault_ledger: Account<'info, VaultLedger>,
+
+    /// The system program
+    pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
 }
 
-/// Accounts required for migrating domain separator
+/// Accounts required for setting the treasury
+#[event_cpi]
 #[derive(Accounts)]
-pub struct MigrateDomainSeparator<'info> {
-    /// The relay depository account to migrate in-place
-    /// CHECK: This is the existing relay depository account to be migrated
+pub struct SetTreasury<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The treasury configuration, created on first use
     #[account(
-        mut,
-        seeds = [RELAY_DEPOSITORY_SEED],
+        init_if_needed,
+        payer = owner,
+        space = 8 + TreasuryConfig::INIT_SPACE,
+        seeds = [TREASURY_SEED, relay_depository.key().as_ref()],
         bump
     )]
-    pub relay_depository: UncheckedAccount<'info>,
+    pub treasury_config: Account<'info, TreasuryConfig>,
 
-    /// The owner of the relay depository (also pays for reallocation)
+    /// The owner of the relay depository
     #[account(mut)]
     pub owner: Signer<'info>,
 
-    /// System program for reallocation
+    /// The system program
     pub system_program: Program<'info, System>,
-
-    /// The instruction sysvar for reentrancy protection
-    /// CHECK: The instruction sysvar for reentrancy protection
-    pub ix_sysvar: AccountInfo<'info>,
 }
 
-/// Accounts required for depositing native currency
+/// Accounts required for executing a treasury update
+#[event_cpi]
 #[derive(Accounts)]
-pub struct DepositNative<'info> {
+pub struct ExecuteTreasuryUpdate<'info> {
     /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The treasury configuration
     #[account(
-        seeds = [RELAY_DEPOSITORY_SEED],
+        mut,
+        seeds = [TREASURY_SEED, relay_depository.key().as_ref()],
         bump
     )]
-    pub relay_depository: Account<'info, RelayDepository>,
+    pub treasury_config: Account<'info, TreasuryConfig>,
 
-    /// The sender of the deposit
-    #[account(mut)]
-    pub sender: Signer<'info>,
+    /// The owner of the relay depository
+    pub owner: Signer<'info>,
+}
 
-    /// The account credited for the deposit
-    /// CHECK: The account credited for the deposit
-    pub depositor: UncheckedAccount<'info>,
+/// Accounts required for cancelling a treasury update
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelTreasuryUpdate<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
 
-    /// The vault PDA that will receive the SOL
-    /// CHECK: The vault PDA that will receive the SOL
+    /// The treasury configuration
     #[account(
         mut,
-        seeds = [VAULT_SEED],
-        bump = relay_depository.vault_bump
+        seeds = [TREASURY_SEED, relay_depository.key().as_ref()],
+        bump
     )]
-    pub vault: UncheckedAccount<'info>,
+    pub treasury_config: Account<'info, TreasuryConfig>,
 
-    /// The system program
-    pub system_program: Program<'info, System>,
+    /// The owner of the relay depository
+    pub owner: Signer<'info>,
 }
 
-/// Accounts required for depositing tokens
+/// Accounts required for requesting a sweep
+#[event_cpi]
 #[derive(Accounts)]
-pub struct DepositToken<'info> {
+pub struct RequestSweep<'info> {
     /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The treasury configuration
     #[account(
-        seeds = [RELAY_DEPOSITORY_SEED],
+        seeds = [TREASURY_SEED, relay_depository.key().as_ref()],
         bump
     )]
-    pub relay_depository: Account<'info, RelayDepository>,
+    pub treasury_config: Account<'info, TreasuryConfig>,
 
-    /// The sender of the deposit
+    /// The vault PDA to sweep from
+    /// CHECK: The vault PDA to sweep from
+    #[account(
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The pending sweep account to be created
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PendingSweep::INIT_SPACE,
+        seeds = [PENDING_SWEEP_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub pending_sweep: Account<'info, PendingSweep>,
+
+    /// The owner of the relay depository (also pays for the pending sweep)
     #[account(mut)]
-    pub sender: Signer<'info>,
+    pub owner: Signer<'info>,
 
-    /// The account credited for the deposit
-    /// CHECK: The account credited for the deposit
-    pub depositor: UncheckedAccount<'info>,
+    /// The system program
+    pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
+}
 
-    /// The vault PDA that will receive the tokens
-    /// CHECK: The vault PDA that will receive the tokens
+/// Accounts required for executing a sweep
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteSweep<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The owner of the relay depository (receives the pending sweep rent)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The vault PDA to sweep from
+    /// CHECK: The vault PDA to sweep from
     #[account(
-        seeds = [VAULT_SEED],
-        bump = relay_depository.vault_bump
+        mut,
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
     )]
     pub vault: UncheckedAccount<'info>,
 
-    /// The mint of the token being deposited
-    pub mint: InterfaceAccount<'info, Mint>,
+    /// The pending sweep to execute
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PENDING_SWEEP_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub pending_sweep: Account<'info, PendingSweep>,
 
-    /// The sender's token account
+    /// The treasury recorded in the pending sweep
+    /// CHECK: Must match the treasury of the pending sweep
+    #[account(
+        mut,
+        address = pending_sweep.treasury @ CustomError::InvalidTreasury
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// The mint of the token being swept
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The treasury's token account
     #[account(
         mut,
         associated_token::mint = mint,
-        associated_token::authority = sender,
+        associated_token::authority = treasury,
         associated_token::token_program = token_program
     )]
-    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
-    /// CHECK: The vault's token account
-    #[account(mut)]
-    pub vault_token_account: UncheckedAccount<'info>,
+    /// The vault's token account
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The ledger of the vault for the swept token, created on first use
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + VaultLedger::INIT_SPACE,
+        seeds = [VAULT_LEDGER_SEED, vault.key().as_ref(), pending_sweep.token.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub vault_ledger: Account<'info, VaultLedger>,
 
     /// The token program
     pub token_program: Interface<'info, TokenInterface>,
-    /// The associated token program
-    pub associated_token_program: Program<'info, AssociatedToken>,
     /// The system program
     pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
 }
 
-/// Accounts required for executing a transfer
+/// Accounts required for cancelling a sweep
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(request: TransferRequest)]
-pub struct ExecuteTransfer<'info> {
-
+pub struct CancelSweep<'info> {
     /// The relay depository account
-    /// CHECK: The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The owner of the relay depository (receives the pending sweep rent)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The vault PDA the sweep was requested for
+    /// CHECK: The vault PDA the sweep was requested for
     #[account(
-        seeds = [RELAY_DEPOSITORY_SEED],
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The pending sweep to cancel
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PENDING_SWEEP_SEED, vault.key().as_ref()],
         bump
     )]
+    pub pending_sweep: Account<'info, PendingSweep>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
+}
+
+/// Accounts required for announcing an emergency withdraw
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AnnounceEmergencyWithdraw<'info> {
+    /// The relay depository account
     pub relay_depository: Account<'info, RelayDepository>,
 
-    /// The executor of the transfer
-    /// CHECK: The executor of the transfer
-    #[account(mut)]
-    pub executor: Signer<'info>,
+    /// The vault PDA to drain
+    /// CHECK: The vault PDA to drain
+    #[account(
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The pending emergency withdraw account to be created
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PendingEmergencyWithdraw::INIT_SPACE,
+        seeds = [PENDING_EMERGENCY_WITHDRAW_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub pending_emergency_withdraw: Account<'info, PendingEmergencyWithdraw>,
+
+    /// The owner of the relay depository (also pays for the announcement)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The system program
+    pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
+}
+
+/// Accounts required for executing an emergency withdraw
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
 
-    /// The recipient of the transfer
-    /// CHECK: The recipient of the transfer
+    /// The owner of the relay depository (pays for the vault ledger if needed and
+    /// the executed emergency withdraw marker)
     #[account(mut)]
-    pub recipient: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
 
-    /// The vault PDA that will receive the tokens
-    /// CHECK: The vault PDA that will receive the tokens
+    /// The vault PDA to drain
+    /// CHECK: The vault PDA to drain
     #[account(
         mut,
-        seeds = [VAULT_SEED],
-        bump = relay_depository.vault_bump
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
     )]
     pub vault: UncheckedAccount<'info>,
 
-    /// The mint of the token being transferred
+    /// The announced emergency withdraw
+    #[account(
+        seeds = [PENDING_EMERGENCY_WITHDRAW_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub pending_emergency_withdraw: Account<'info, PendingEmergencyWithdraw>,
+
+    /// The recipient recorded in the announcement
+    /// CHECK: Must match the recipient of the announcement
+    #[account(
+        mut,
+        address = pending_emergency_withdraw.recipient @ CustomError::InvalidRecipient
+    )]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// The mint of the token being withdrawn (None for native SOL)
     pub mint: Option<InterfaceAccount<'info, Mint>>,
 
     /// The recipient's token account
@@ -798,31 +2652,73 @@ pub struct ExecuteTransfer<'info> {
     )]
     pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
-    /// The account that tracks whether a transfer request has been used
-    /// 
-    /// This account is created for each transfer request to prevent replay attacks.
+    /// The ledger of the vault for the withdrawn token, created on first use
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + VaultLedger::INIT_SPACE,
+        seeds = [VAULT_LEDGER_SEED, vault.key().as_ref(), mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub vault_ledger: Account<'info, VaultLedger>,
+
+    /// Marker of the withdrawn asset, preventing a second withdraw of it
     #[account(
         init,
-        payer = executor,
-        space = 8 + UsedRequest::INIT_SPACE,
+        payer = owner,
+        space = 8 + ExecutedEmergencyWithdraw::INIT_SPACE,
         seeds = [
-            USED_REQUEST_SEED,
-            &request.get_hash().to_bytes()[..],
+            EXECUTED_EMERGENCY_WITHDRAW_SEED,
+            vault.key().as_ref(),
+            mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref(),
+            &pending_emergency_withdraw.announced_at.to_le_bytes()
         ],
         bump
     )]
-    pub used_request: Account<'info, UsedRequest>,
-
-    /// The instruction sysvar for ed25519 verification
-    /// CHECK: The instruction sysvar for ed25519 verification
-    pub ix_sysvar: AccountInfo<'info>,
+    pub executed_emergency_withdraw: Account<'info, ExecutedEmergencyWithdraw>,
 
     /// The token program
     pub token_program: Interface<'info, TokenInterface>,
-    /// The associated token program
-    pub associated_token_program: Program<'info, AssociatedToken>,
     /// The system program
     pub system_program: Program<'info, System>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
+}
+
+/// Accounts required for cancelling an emergency withdraw
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelEmergencyWithdraw<'info> {
+    /// The relay depository account
+    pub relay_depository: Account<'info, RelayDepository>,
+
+    /// The owner of the relay depository (receives the announcement rent)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The vault PDA the emergency withdraw was announced for
+    /// CHECK: The vault PDA the emergency withdraw was announced for
+    #[account(
+        address = VaultSeeds::new(&relay_depository, &vault_config).address()? @ CustomError::InvalidVaultAddress
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The announced emergency withdraw to cancel
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PENDING_EMERGENCY_WITHDRAW_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub pending_emergency_withdraw: Account<'info, PendingEmergencyWithdraw>,
+    /// The configuration of the vault (None for the default vault)
+    #[account(
+        constraint = vault_config.relay_depository == relay_depository.key() @ CustomError::InvalidVaultConfig
+    )]
+    pub vault_config: Option<Account<'info, VaultConfig>>,
 }
 
 //----------------------------------------
@@ -846,6 +2742,130 @@ pub struct TransferRequest {
     pub expiration: i64,
     /// The vault address that funds will be withdrawn from
     pub vault_address: Pubkey,
+    /// External reference for the transfer (e.g. the originating deposit id).
+    /// Part of the signed hash: requests serialized without it no longer verify
+    pub reference: [u8; 32],
+}
+
+/// Structure representing a deposit quote signed by the allocator
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Debug)]
+pub struct DepositQuote {
+    /// Domain separator
+    pub domain: [u8; 32],
+    /// The unique identifier of the deposit
+    pub id: [u8; 32],
+    /// The account credited for the deposit
+    pub depositor: Pubkey,
+    /// The token mint (None for native SOL, Some(mint) for SPL tokens)
+    pub token: Option<Pubkey>,
+    /// The minimum amount to be credited by the deposit
+    pub min_amount: u64,
+    /// The expiration timestamp for the quote
+    pub expiration: i64,
+    /// The vault address that funds will be deposited to
+    pub vault_address: Pubkey,
+}
+
+impl DepositQuote {
+    /// Computes a hash of the serialized quote for signature verification
+    pub fn get_hash(&self) -> Hash {
+        hash(&self.try_to_vec().unwrap())
+    }
+}
+
+/// Structure representing a swap request signed by the allocator
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Debug)]
+pub struct SwapRequest {
+    /// Domain separator
+    pub domain: [u8; 32],
+    /// The unique identifier of the deposit of the input
+    pub id: [u8; 32],
+    /// The depositor providing the input
+    pub depositor: Pubkey,
+    /// The input token mint (None for native SOL)
+    pub input_token: Option<Pubkey>,
+    /// The amount of the input to deposit
+    pub input_amount: u64,
+    /// The recipient of the output
+    pub recipient: Pubkey,
+    /// The output token mint (None for native SOL)
+    pub output_token: Option<Pubkey>,
+    /// The amount of the output to pay out
+    pub output_amount: u64,
+    /// A unique nonce
+    pub nonce: u64,
+    /// The expiration timestamp for the request
+    pub expiration: i64,
+    /// The vault address that receives the input and pays out the output
+    pub vault_address: Pubkey,
+}
+
+impl SwapRequest {
+    /// Computes a hash of the serialized request for signature verification
+    /// and used request tracking
+    pub fn get_hash(&self) -> Hash {
+        hash(&self.try_to_vec().unwrap())
+    }
+}
+
+/// Signer seeds of a vault PDA
+///
+/// The vault of the default relay depository is derived from the `VAULT_SEED`
+/// only, and the vault of an instance from the `VAULT_SEED` and the instance.
+/// Additional vaults are derived from the `VAULT_SEED`, the relay depository
+/// and their index.
+pub struct VaultSeeds {
+    /// The relay depository (None for the vault of the default relay depository)
+    relay_depository: Option<Pubkey>,
+    /// The vault index (None for the vault of the relay depository itself)
+    index: Option<[u8; 2]>,
+    /// The bump seed of the vault PDA
+    bump: [u8; 1],
+}
+
+impl VaultSeeds {
+    /// Returns the seeds of the vault selected by the optional vault configuration
+    pub fn new(
+        relay_depository: &Account<RelayDepository>,
+        vault_config: &Option<Account<VaultConfig>>,
+    ) -> Self {
+        match vault_config {
+            None if is_default_relay_depository(&relay_depository.key()) => Self {
+                relay_depository: None,
+                index: None,
+                bump: [relay_depository.vault_bump],
+            },
+            None => Self {
+                relay_depository: Some(relay_depository.key()),
+                index: None,
+                bump: [relay_depository.vault_bump],
+            },
+            Some(vault_config) => Self {
+                relay_depository: Some(relay_depository.key()),
+                index: Some(vault_config.index.to_le_bytes()),
+                bump: [vault_config.vault_bump],
+            },
+        }
+    }
+
+    /// Returns the signer seeds of the vault, including the bump
+    pub fn seeds(&self) -> Vec<&[u8]> {
+        let mut seeds = vec![VAULT_SEED];
+        if let Some(relay_depository) = &self.relay_depository {
+            seeds.push(relay_depository.as_ref());
+        }
+        if let Some(index) = &self.index {
+            seeds.push(index);
+        }
+        seeds.push(&self.bump);
+        seeds
+    }
+
+    /// Returns the address of the vault
+    pub fn address(&self) -> Result<Pubkey> {
+        Pubkey::create_program_address(&self.seeds(), &crate::ID)
+            .map_err(|_| CustomError::InvalidVaultAddress.into())
+    }
 }
 
 impl TransferRequest {
@@ -860,8 +2880,54 @@ impl TransferRequest {
 // Events
 //----------------------------------------
 
+/// Event emitted when a relay depository instance is created
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct InstanceCreatedEvent {
+    /// The relay depository instance
+    pub relay_depository: Pubkey,
+    /// The identifier of the instance
+    pub instance_id: u64,
+    /// The creator of the instance
+    pub creator: Pubkey,
+    /// The owner of the instance
+    pub owner: Pubkey,
+    /// The allocator of the instance
+    pub allocator: Pubkey,
+    /// The vault of the instance
+    pub vault: Pubkey,
+}
+
+/// Event emitted when the owner of a relay depository is updated
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct OwnerUpdatedEvent {
+    /// The relay depository
+    pub relay_depository: Pubkey,
+    /// The previous owner
+    pub previous_owner: Pubkey,
+    /// The new owner
+    pub new_owner: Pubkey,
+}
+
+/// Event emitted when the allocator of a relay depository, or of one of its
+/// additional vaults, is updated
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct AllocatorUpdatedEvent {
+    /// The relay depository
+    pub relay_depository: Pubkey,
+    /// The configuration of the additional vault (None for the relay depository)
+    pub vault_config: Option<Pubkey>,
+    /// The previous allocator
+    pub previous_allocator: Pubkey,
+    /// The new allocator
+    pub new_allocator: Pubkey,
+}
+
 /// Event emitted when a transfer is executed
 #[event]
+#[derive(Clone, PartialEq, Debug)]
 pub struct TransferExecutedEvent {
     /// The transfer request that was executed
     pub request: TransferRequest,
@@ -871,8 +2937,21 @@ pub struct TransferExecutedEvent {
     pub id: Pubkey,
 }
 
+/// Event emitted when a swap is executed
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct SwapExecutedEvent {
+    /// The swap request that was executed
+    pub request: SwapRequest,
+    /// The input amount credited to the vault (net of transfer fees)
+    pub deposited_amount: u64,
+    /// The unique identifier for the used request account
+    pub id: Pubkey,
+}
+
 /// Event emitted when a deposit is made
 #[event]
+#[derive(Clone, PartialEq, Debug)]
 pub struct DepositEvent {
     /// The public key of the depositor
     pub depositor: Pubkey,
@@ -882,6 +2961,114 @@ pub struct DepositEvent {
     pub amount: u64,
     /// A unique identifier for the deposit
     pub id: [u8; 32],
+    /// The relay depository the deposit was made to
+    pub relay_depository: Pubkey,
+    /// The vault that received the deposit
+    pub vault: Pubkey,
+}
+
+/// Event emitted when a treasury update is requested
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct TreasuryUpdateRequestedEvent {
+    /// The requested treasury address
+    pub treasury: Pubkey,
+    /// The timestamp after which the update can be executed
+    pub executable_at: i64,
+}
+
+/// Event emitted when the treasury is updated
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct TreasuryUpdatedEvent {
+    /// The new treasury address
+    pub treasury: Pubkey,
+}
+
+/// Event emitted when a pending treasury update is cancelled
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct TreasuryUpdateCancelledEvent {
+    /// The treasury address that was requested
+    pub treasury: Pubkey,
+}
+
+/// Event emitted when a vault sweep is requested
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct SweepRequestedEvent {
+    /// The vault being swept
+    pub vault: Pubkey,
+    /// The token mint (None for native SOL, Some(mint) for SPL tokens)
+    pub token: Option<Pubkey>,
+    /// The amount to sweep
+    pub amount: u64,
+    /// The treasury receiving the funds
+    pub treasury: Pubkey,
+    /// The timestamp after which the sweep can be executed
+    pub executable_at: i64,
+}
+
+/// Event emitted when a vault sweep is executed
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct SweepExecutedEvent {
+    /// The vault that was swept
+    pub vault: Pubkey,
+    /// The token mint (None for native SOL, Some(mint) for SPL tokens)
+    pub token: Option<Pubkey>,
+    /// The amount swept
+    pub amount: u64,
+    /// The treasury that received the funds
+    pub treasury: Pubkey,
+}
+
+/// Event emitted when a pending vault sweep is cancelled
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct SweepCancelledEvent {
+    /// The vault the sweep was requested for
+    pub vault: Pubkey,
+    /// The token mint (None for native SOL, Some(mint) for SPL tokens)
+    pub token: Option<Pubkey>,
+    /// The amount that was requested
+    pub amount: u64,
+}
+
+/// Event emitted when an emergency withdraw is announced
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct EmergencyWithdrawAnnouncedEvent {
+    /// The vault that will be drained
+    pub vault: Pubkey,
+    /// The account receiving the vault funds
+    pub recipient: Pubkey,
+    /// The timestamp after which the vault can be drained
+    pub executable_at: i64,
+}
+
+/// Event emitted when vault funds are withdrawn in an emergency
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct EmergencyWithdrawExecutedEvent {
+    /// The vault that was drained
+    pub vault: Pubkey,
+    /// The token mint (None for native SOL, Some(mint) for SPL tokens)
+    pub token: Option<Pubkey>,
+    /// The amount withdrawn
+    pub amount: u64,
+    /// The account that received the funds
+    pub recipient: Pubkey,
+}
+
+/// Event emitted when an emergency withdraw is cancelled
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct EmergencyWithdrawCancelledEvent {
+    /// The vault the emergency withdraw was announced for
+    pub vault: Pubkey,
+    /// The recipient of the cancelled emergency withdraw
+    pub recipient: Pubkey,
 }
 
 //----------------------------------------
@@ -974,21 +3161,81 @@ pub enum CustomError {
     /// Thrown when the account size doesn't match expected legacy size
     #[msg("Invalid account size for migration")]
     InvalidAccountSize,
+
+    /// Thrown when a timelocked action is executed before its delay has elapsed
+    #[msg("Timelock has not expired")]
+    TimelockNotExpired,
+
+    /// Thrown when the treasury doesn't match the configured treasury
+    #[msg("Invalid treasury")]
+    InvalidTreasury,
+
+    /// Thrown when the vault configuration doesn't belong to the relay depository
+    #[msg("Invalid vault config")]
+    InvalidVaultConfig,
+
+    /// Thrown when the program data account doesn't belong to the program
+    #[msg("Invalid program data")]
+    InvalidProgramData,
+
+    /// Thrown when the vault balance doesn't exceed the balance expected from its ledger
+    #[msg("No direct deposit to register")]
+    NoDirectDeposit,
+
+    /// Thrown when the depositor doesn't match the quote
+    #[msg("Invalid depositor")]
+    InvalidDepositor,
+
+    /// Thrown when the deposited amount is below the minimum amount of the quote
+    #[msg("Insufficient deposit amount")]
+    InsufficientDepositAmount,
+
+    /// Thrown when the swap output ledger is missing, or given for a same-token swap
+    #[msg("Invalid vault ledger")]
+    InvalidVaultLedger,
 }
 
 //----------------------------------------
 // Helper Functions
 //----------------------------------------
 
+/// Emits an event through a self CPI signed by the event authority
+///
+/// Equivalent to `emit_cpi!`, for the accounts structs shared by several
+/// instructions where `ctx` is not in scope.
+fn emit_cpi_event<E: anchor_lang::Event>(
+    event_authority: &AccountInfo,
+    event_authority_bump: u8,
+    event: &E,
+) -> Result<()> {
+    let data: Vec<u8> = anchor_lang::event::EVENT_IX_TAG_LE
+        .into_iter()
+        .chain(event.data())
+        .collect();
+    let ix = Instruction::new_with_bytes(
+        crate::ID,
+        &data,
+        vec![AccountMeta::new_readonly(event_authority.key(), true)],
+    );
+
+    invoke_signed(
+        &ix,
+        std::slice::from_ref(event_authority),
+        &[&[EVENT_AUTHORITY_SEED, &[event_authority_bump]]],
+    )?;
+
+    Ok(())
+}
+
 /// Validates an Ed25519 signature instruction
 ///
 /// Verifies that the signature instruction is properly formatted,
-/// signed by the expected signer, and matches the expected request.
+/// signed by the expected signer, and matches the expected message hash.
 ///
 /// # Parameters
 /// * `signature_ix` - The signature instruction to validate
 /// * `expected_signer` - The expected signer of the instruction
-/// * `expected_request` - The expected transfer request that was signed
+/// * `expected_hash` - The expected hash of the signed message
 ///
 /// # Returns
 /// * `Ok(())` if the signature is valid
@@ -996,7 +3243,7 @@ pub enum CustomError {
 fn validate_ed25519_signature_instruction(
     signature_ix: &Instruction,
     expected_signer: &Pubkey,
-    expected_request: &TransferRequest,
+    expected_hash: &Hash,
 ) -> Result<()> {
 
     // Taken from:
@@ -1051,19 +3298,217 @@ fn validate_ed25519_signature_instruction(
         CustomError::AllocatorSignerMismatch
     );
 
-    // Verify message hash matches request hash
-    let expected_hash = expected_request.get_hash().to_bytes();
-    if data_msg != expected_hash {
+    // Verify message hash matches the expected hash
+    if data_msg != expected_hash.to_bytes() {
         return Err(CustomError::MessageMismatch.into());
     }
 
     Ok(())
 }
 
+/// Validates a deposit quote against the deposit
+///
+/// Verifies that the quote is signed by the allocator of the vault through the
+/// Ed25519 instruction preceding the current instruction, is not expired, and
+/// matches the domain, vault, depositor and token of the deposit.
+///
+/// # Parameters
+/// * `quote` - The quote accepted by the depositor
+/// * `relay_depository` - The relay depository account
+/// * `vault_config` - The configuration of the vault (None for the default vault)
+/// * `vault` - The vault receiving the deposit
+/// * `depositor` - The account credited for the deposit
+/// * `token` - The token mint of the deposit (None for native SOL)
+/// * `ix_sysvar` - The instruction sysvar
+///
+/// # Returns
+/// * `Ok(())` if the quote is valid
+/// * `Err(error)` if the quote is invalid or doesn't match the deposit
+fn validate_deposit_quote(
+    quote: &DepositQuote,
+    relay_depository: &Account<RelayDepository>,
+    vault_config: &Option<Account<VaultConfig>>,
+    vault: &Pubkey,
+    depositor: &Pubkey,
+    token: Option<Pubkey>,
+    ix_sysvar: &AccountInfo,
+) -> Result<()> {
+    require!(
+        Clock::get()?.unix_timestamp < quote.expiration,
+        CustomError::SignatureExpired
+    );
+
+    if let Some(expected_domain) = relay_depository.domain_separator {
+        require!(
+            quote.domain == expected_domain,
+            CustomError::InvalidDomainSeparator
+        );
+    }
+
+    require_keys_eq!(*vault, quote.vault_address, CustomError::InvalidVaultAddress);
+    require_keys_eq!(*depositor, quote.depositor, CustomError::InvalidDepositor);
+    require!(token == quote.token, CustomError::InvalidMint);
+
+    let cur_index: usize = sysvar::instructions::load_current_index_checked(ix_sysvar)?.into();
+    require!(cur_index > 0, CustomError::MalformedEd25519Data);
+
+    let signature_ix = sysvar::instructions::load_instruction_at_checked(cur_index - 1, ix_sysvar)?;
+
+    let allocator = vault_config
+        .as_ref()
+        .map_or(relay_depository.allocator, |vault_config| vault_config.allocator);
+
+    validate_ed25519_signature_instruction(&signature_ix, &allocator, &quote.get_hash())
+}
+
+/// Transfers native SOL out of the vault
+///
+/// Ensures the vault remains rent-exempt after the transfer.
+///
+/// # Parameters
+/// * `vault` - The vault PDA holding the SOL
+/// * `recipient` - The account receiving the SOL
+/// * `system_program` - The system program
+/// * `amount` - The amount of SOL to transfer
+/// * `vault_seeds` - The signer seeds of the vault PDA
+///
+/// # Returns
+/// * `Ok(())` on success
+/// * `Err(error)` if the vault balance is insufficient
+fn transfer_native_from_vault<'info>(
+    vault: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+    vault_seeds: &[&[u8]],
+) -> Result<()> {
+    // Ensure vault maintains rent-exempt status after transfer
+    let min_rent = Rent::get()?.minimum_balance(0);
+    let max_transferable = vault.lamports().saturating_sub(min_rent);
+    require!(
+        amount <= max_transferable,
+        CustomError::InsufficientVaultBalance
+    );
+
+    invoke_signed(
+        &system_instruction::transfer(vault.key, recipient.key, amount),
+        &[vault.clone(), recipient.clone(), system_program.clone()],
+        &[vault_seeds],
+    )?;
+
+    Ok(())
+}
+
+/// Transfers SPL tokens out of the vault
+///
+/// Ensures the token program is supported and owns the mint before transferring.
+///
+/// # Parameters
+/// * `token_program` - The token program of the mint
+/// * `mint` - The mint of the token being transferred
+/// * `vault_token_account` - The vault's token account
+/// * `recipient_token_account` - The token account receiving the tokens
+/// * `vault` - The vault PDA, authority of the vault token account
+/// * `amount` - The amount of tokens to transfer
+/// * `vault_seeds` - The signer seeds of the vault PDA
+///
+/// # Returns
+/// * `Ok(())` on success
+/// * `Err(error)` if the token program or mint is invalid
+fn transfer_token_from_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    vault_token_account: &InterfaceAccount<'info, TokenAccount>,
+    recipient_token_account: &InterfaceAccount<'info, TokenAccount>,
+    vault: &AccountInfo<'info>,
+    amount: u64,
+    vault_seeds: &[&[u8]],
+) -> Result<()> {
+    // Ensure token program is either SPL Token or SPL Token 2022
+    require!(
+        token_program.key() == anchor_spl::token::ID
+        || token_program.key() == anchor_spl::token_2022::ID,
+        CustomError::InvalidTokenProgram
+    );
+
+    // Ensure mint is owned by the token program
+    require_keys_eq!(
+        *mint.to_account_info().owner,
+        token_program.key(),
+        CustomError::InvalidMint
+    );
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                mint: mint.to_account_info(),
+                from: vault_token_account.to_account_info(),
+                to: recipient_token_account.to_account_info(),
+                authority: vault.clone(),
+            },
+            &[vault_seeds],
+        ),
+        amount,
+        mint.decimals,
+    )?;
+
+    Ok(())
+}
+
+/// Returns the balance of a token account
+///
+/// Reads the amount from the base state of an SPL Token or Token-2022 account,
+/// returning zero for an uninitialized account.
+///
+/// # Parameters
+/// * `token_account` - The token account
+///
+/// # Returns
+/// * The balance of the token account
+fn get_token_account_balance(token_account: &AccountInfo) -> Result<u64> {
+    if token_account.data_is_empty() {
+        return Ok(0);
+    }
+    let data = token_account.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    Ok(state.base.amount)
+}
+
+/// Encodes bytes as a lowercase hex string
+///
+/// # Parameters
+/// * `bytes` - The bytes to encode
+///
+/// # Returns
+/// * The hex encoded string
+fn encode_hex(bytes: &[u8]) -> String {
+    const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
+
+    let mut encoded = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        encoded.push(HEX_CHARS[(byte >> 4) as usize] as char);
+        encoded.push(HEX_CHARS[(byte & 0x0f) as usize] as char);
+    }
+    encoded
+}
+
+/// Returns whether the given account is the default relay depository
+///
+/// The default relay depository is the PDA derived from the `RELAY_DEPOSITORY_SEED`
+/// only; every other relay depository is an instance. Deriving this rather than
+/// storing it keeps the layout of the deployed default relay depository unchanged,
+/// so that it deserializes right after the program upgrade without a migration.
+fn is_default_relay_depository(relay_depository: &Pubkey) -> bool {
+    let (default_relay_depository, _) = find_relay_depository_address(&crate::ID);
+    *relay_depository == default_relay_depository
+}
+
 /// Creates the expected domain separator hash
 ///
 /// Combines name, version, chain_id and program_id into a single hash
-/// for efficient validation and storage.
+/// for efficient validation and storage. Instances pass their own address
+/// as `program_id` so each instance has a distinct domain.
 ///
 /// # Parameters
 /// * `name` - Protocol name (e.g., b"RelayDepository")
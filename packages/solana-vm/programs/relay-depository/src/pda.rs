@@ -0,0 +1,6 @@
+//! Seeds and address derivations of the relay depository accounts
+//!
+//! Re-exported from the `relay-pda` crate, which off-chain code can depend on
+//! without pulling in the program.
+
+pub use relay_pda::depository::*;
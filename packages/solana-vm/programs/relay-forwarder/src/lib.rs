@@ -1,15 +1,40 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::Discriminator;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+    token::{spl_token, Token},
+    token_2022::{
+        spl_token_2022::{
+            self,
+            extension::{
+                transfer_fee::TransferFeeAmount, BaseStateWithExtensions, StateWithExtensions,
+            },
+        },
+        Token2022,
+    },
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
 };
 use relay_depository::program::RelayDepository;
 
+pub mod pda;
+
+use pda::*;
+
 //----------------------------------------
 // Constants
 //----------------------------------------
 
-const RELAY_FORWARDER_SEED: &[u8] = b"relay_forwarder";
+const BASIS_POINTS_DENOMINATOR: u64 = 10_000;
+
+/// Delay between the announcement of a recovery and its execution (24 hours)
+///
+/// Leaves time to notice a recovery of deposited assets to an unexpected recipient.
+pub const RECOVER_TIMELOCK: i64 = 24 * 60 * 60;
 
 //----------------------------------------
 // Program ID
@@ -25,17 +50,210 @@ declare_id!("DPArtTLbEqa6EuXHfL5UFLBZhFjiEXWRudhvXDrjwXUr");
 pub mod relay_forwarder {
     use super::*;
 
+    /// Configures a forwarder, only callable by the upgrade authority of the program
+    ///
+    /// The rent destination applies to every forward of the forwarder: it can't be
+    /// set per depositor, nor by the owner of the relay depository.
+    pub fn set_config(
+        ctx: Context<SetConfig>,
+        rent_destination: Option<Pubkey>,
+        crank_reward: CrankReward,
+    ) -> Result<()> {
+        if let CrankReward::BasisPoints(basis_points) = crank_reward {
+            require!(
+                (basis_points as u64) < BASIS_POINTS_DENOMINATOR,
+                ForwarderError::InvalidCrankReward
+            );
+        }
+
+        let config = &mut ctx.accounts.config;
+        config.rent_destination = rent_destination;
+        config.crank_reward = crank_reward;
+
+        Ok(())
+    }
+
+    /// Sets the minimum amount of a mint (`Pubkey::default()` for native) that can be forwarded
+    /// to a relay depository, and its fixed crank reward, only callable by the owner of the
+    /// relay depository
+    ///
+    /// Balances below the minimum are not forwarded and keep accumulating in the forwarder.
+    /// The fixed token reward is paid on token forwards of the mint when the forwarder is
+    /// configured with a `CrankReward::Fixed` reward. Wrapped native tokens are deposited as
+    /// native tokens, so the native minimum applies to them and they have none of their own.
+    pub fn set_forward_minimum(
+        ctx: Context<SetForwardMinimum>,
+        mint: Pubkey,
+        minimum_amount: u64,
+        fixed_token_reward: u64,
+    ) -> Result<()> {
+        require!(!is_native_mint(&mint), ForwarderError::InvalidMint);
+
+        let owner = load_relay_depository_owner(
+            &ctx.accounts.relay_depository_program,
+            &ctx.accounts.relay_depository,
+        )?;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            owner,
+            ForwarderError::Unauthorized
+        );
+
+        let forward_minimum = &mut ctx.accounts.forward_minimum;
+        forward_minimum.relay_depository = ctx.accounts.relay_depository.key();
+        forward_minimum.mint = mint;
+        forward_minimum.minimum_amount = minimum_amount;
+        forward_minimum.fixed_token_reward = fixed_token_reward;
+
+        Ok(())
+    }
+
+    /// Announces the recovery of a forwarder token account to a recipient, callable by the
+    /// owner of the relay depository the forwarder forwards to, or by a depositor recovering
+    /// to itself
+    ///
+    /// The recovery can be executed by `recover` once `RECOVER_TIMELOCK` has elapsed,
+    /// and only to the announced recipient. Forwarders are shared by all depositors and
+    /// don't record who sent an asset, so the owner can cancel the recoveries announced by
+    /// depositors during the timelock.
+    pub fn announce_recover(ctx: Context<AnnounceRecover>, recipient: Pubkey) -> Result<()> {
+        check_recover_authority(
+            &ctx.accounts.authority,
+            &recipient,
+            &ctx.accounts.forwarder,
+            &ctx.accounts.relay_depository_program,
+            &ctx.accounts.relay_depository,
+        )?;
+
+        let executable_at = Clock::get()?.unix_timestamp + RECOVER_TIMELOCK;
+
+        let pending_recover = &mut ctx.accounts.pending_recover;
+        pending_recover.recipient = recipient;
+        pending_recover.executable_at = executable_at;
+
+        emit_cpi!(RecoverAnnouncedEvent {
+            authority: ctx.accounts.authority.key(),
+            forwarder_token_account: ctx.accounts.forwarder_token_account.key(),
+            mint: ctx.accounts.forwarder_token_account.mint,
+            recipient,
+            executable_at,
+        });
+
+        Ok(())
+    }
+
+    /// Cancels an announced recovery, callable by the owner of the relay depository the
+    /// forwarder forwards to, or by the recipient of the recovery
+    pub fn cancel_recover(ctx: Context<CancelRecover>) -> Result<()> {
+        check_recover_authority(
+            &ctx.accounts.authority,
+            &ctx.accounts.pending_recover.recipient,
+            &ctx.accounts.forwarder,
+            &ctx.accounts.relay_depository_program,
+            &ctx.accounts.relay_depository,
+        )?;
+
+        emit_cpi!(RecoverCancelledEvent {
+            authority: ctx.accounts.authority.key(),
+            forwarder_token_account: ctx.accounts.forwarder_token_account.key(),
+            recipient: ctx.accounts.pending_recover.recipient,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the full balance of a forwarder token account to the announced recipient and
+    /// closes it, callable by the owner of the relay depository the forwarder forwards to, or
+    /// by the recipient, once the timelock of the announcement has elapsed
+    ///
+    /// Used to recover assets that cannot be forwarded, like unsupported mints or NFTs.
+    pub fn recover(ctx: Context<Recover>) -> Result<()> {
+        let forwarder_seeds = check_recover_authority(
+            &ctx.accounts.authority,
+            &ctx.accounts.recipient.key(),
+            &ctx.accounts.forwarder,
+            &ctx.accounts.relay_depository_program,
+            &ctx.accounts.relay_depository,
+        )?;
+
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.pending_recover.executable_at,
+            ForwarderError::TimelockNotExpired
+        );
+
+        let amount = ctx.accounts.forwarder_token_account.amount;
+        let seeds: &[&[&[u8]]] = &[&forwarder_seeds.seeds()];
+
+        if amount > 0 {
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        from: ctx.accounts.forwarder_token_account.to_account_info(),
+                        to: ctx.accounts.recipient_token_account.to_account_info(),
+                        authority: ctx.accounts.forwarder.to_account_info(),
+                    },
+                    seeds,
+                ),
+                amount,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        close_forwarder_token_account(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.forwarder_token_account.to_account_info(),
+            ctx.accounts.recipient.to_account_info(),
+            ctx.accounts.forwarder.to_account_info(),
+            seeds,
+        )?;
+
+        emit_cpi!(RecoverEvent {
+            authority: ctx.accounts.authority.key(),
+            mint: ctx.accounts.mint.key(),
+            forwarder_token_account: ctx.accounts.forwarder_token_account.key(),
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
     /// Forwards native tokens from the forwarder account to the relay depository vault account
     pub fn forward_native(ctx: Context<ForwardNative>, id: [u8; 32]) -> Result<()> {
-        let amount = ctx.accounts.forwarder.lamports();
+        let config = load_config(&ctx.accounts.config)?;
 
-        let rent = Rent::get()?;
-        let min_rent = rent.minimum_balance(0);
+        // Only forward the amount above rent-exempt threshold
+        let amount = get_forwardable_native_amount(
+            &ctx.accounts.forwarder,
+            &ctx.accounts.relay_vault_ledger,
+        )?;
+        require!(
+            amount >= load_forward_minimum(&ctx.accounts.forward_minimum)?.minimum_amount,
+            ForwarderError::BelowForwardMinimum
+        );
+        let reward = config.crank_reward.native_reward(amount);
 
         // Check that the forwarder has more than the minimum required amount
-        require!(amount > min_rent, ForwarderError::InsufficientBalance);
+        require!(amount > reward, ForwarderError::InsufficientBalance);
 
-        let seeds: &[&[&[u8]]] = &[&[RELAY_FORWARDER_SEED, &[ctx.bumps.forwarder]]];
+        let forwarder_seeds = ForwarderSeeds::new(
+            &ctx.accounts.forwarder,
+            &ctx.accounts.relay_depository_program,
+            &ctx.accounts.relay_depository,
+        )?;
+        let seeds: &[&[&[u8]]] = &[&forwarder_seeds.seeds()];
+        let rent_retained = ctx.accounts.forwarder.lamports() - amount;
+
+        pay_native_crank_reward(
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.forwarder.to_account_info(),
+            ctx.accounts.sender.to_account_info(),
+            reward,
+            seeds,
+        )?;
 
         relay_depository::cpi::deposit_native(
             CpiContext::new_with_signer(
@@ -43,20 +261,133 @@ pub mod relay_forwarder {
                 ctx.accounts.into_deposit_accounts(),
                 seeds,
             ),
-            // Only forward the amount above rent-exempt threshold
-            amount - min_rent,
+            amount - reward,
             id,
         )?;
 
+        emit_cpi!(ForwardedEvent {
+            forwarder: ctx.accounts.forwarder.key(),
+            cranker: ctx.accounts.sender.key(),
+            depositor: ctx.accounts.depositor.key(),
+            id,
+            mint: None,
+            amount,
+            rent_retained,
+            crank_reward: reward,
+        });
+
         Ok(())
     }
 
     /// Forwards spl tokens from the forwarder token account to the relay depository vault token account
+    ///
+    /// Wrapped native tokens are unwrapped and deposited as native tokens instead.
     pub fn forward_token(ctx: Context<ForwardToken>, id: [u8; 32]) -> Result<()> {
+        let config = load_config(&ctx.accounts.config)?;
+        require_keys_eq!(
+            ctx.accounts.rent_recipient.key(),
+            config.rent_destination(&ctx.accounts.depositor.key()),
+            ForwarderError::InvalidRentRecipient
+        );
+
+        // Wrapped native tokens are deposited as native tokens, under the native minimum
+        require_keys_eq!(
+            ctx.accounts.forward_minimum.key(),
+            find_mint_forward_minimum_address(
+                &ctx.accounts.relay_depository.key(),
+                &ctx.accounts.mint.key()
+            ),
+            ForwarderError::InvalidForwardMinimum
+        );
+
         let amount = ctx.accounts.forwarder_token_account.amount;
-        require!(amount > 0, ForwarderError::InsufficientBalance);
+        let forward_minimum = load_forward_minimum(&ctx.accounts.forward_minimum)?;
+        require!(
+            amount >= forward_minimum.minimum_amount,
+            ForwarderError::BelowForwardMinimum
+        );
 
-        let seeds: &[&[&[u8]]] = &[&[RELAY_FORWARDER_SEED, &[ctx.bumps.forwarder]]];
+        let forwarder_seeds = ForwarderSeeds::new(
+            &ctx.accounts.forwarder,
+            &ctx.accounts.relay_depository_program,
+            &ctx.accounts.relay_depository,
+        )?;
+        let seeds: &[&[&[u8]]] = &[&forwarder_seeds.seeds()];
+
+        // Wrapped native tokens are unwrapped and deposited as native tokens
+        if is_native_mint(&ctx.accounts.mint.key()) {
+            let reward = config.crank_reward.native_reward(amount);
+            require!(amount > reward, ForwarderError::InsufficientBalance);
+
+            let rent_retained = unwrap_native_tokens(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.forwarder_token_account.to_account_info(),
+                ctx.accounts.forwarder.to_account_info(),
+                ctx.accounts.rent_recipient.to_account_info(),
+                &ctx.accounts.relay_vault_ledger,
+                Option::<u64>::from(ctx.accounts.forwarder_token_account.is_native)
+                    .unwrap_or_default(),
+                seeds,
+            )?;
+
+            pay_native_crank_reward(
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.forwarder.to_account_info(),
+                ctx.accounts.sender.to_account_info(),
+                reward,
+                seeds,
+            )?;
+
+            relay_depository::cpi::deposit_native(
+                CpiContext::new_with_signer(
+                    ctx.accounts.relay_depository_program.to_account_info(),
+                    ctx.accounts.to_native_deposit_accounts(),
+                    seeds,
+                ),
+                amount - reward,
+                id,
+            )?;
+
+            emit_cpi!(ForwardedEvent {
+                forwarder: ctx.accounts.forwarder.key(),
+                cranker: ctx.accounts.sender.key(),
+                depositor: ctx.accounts.depositor.key(),
+                id,
+                mint: Some(ctx.accounts.mint.key()),
+                amount,
+                rent_retained,
+                crank_reward: reward,
+            });
+
+            return Ok(());
+        }
+
+        let reward = config
+            .crank_reward
+            .token_reward(amount, forward_minimum.fixed_token_reward);
+        require!(amount > reward, ForwarderError::InsufficientBalance);
+
+        if reward > 0 {
+            let sender_token_account = ctx
+                .accounts
+                .sender_token_account
+                .as_ref()
+                .ok_or(ForwarderError::InvalidTokenAccount)?;
+
+            pay_token_crank_reward(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.forwarder_token_account.to_account_info(),
+                    to: sender_token_account.to_account_info(),
+                    authority: ctx.accounts.forwarder.to_account_info(),
+                },
+                ctx.accounts.mint.decimals,
+                reward,
+                seeds,
+            )?;
+        }
 
         relay_depository::cpi::deposit_token(
             CpiContext::new_with_signer(
@@ -64,30 +395,557 @@ pub mod relay_forwarder {
                 ctx.accounts.into_deposit_accounts(),
                 seeds,
             ),
-            amount,
+            amount - reward,
             id,
         )?;
 
-        let close_account_cpi_ctx = CpiContext::new_with_signer(
+        let account_rent = ctx.accounts.forwarder_token_account.get_lamports();
+        let closed = close_forwarder_token_account(
             ctx.accounts.token_program.to_account_info(),
-            CloseAccount {
-                account: ctx.accounts.forwarder_token_account.to_account_info(),
-                destination: ctx.accounts.sender.to_account_info(),
-                authority: ctx.accounts.forwarder.to_account_info(),
-            },
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.forwarder_token_account.to_account_info(),
+            ctx.accounts.rent_recipient.to_account_info(),
+            ctx.accounts.forwarder.to_account_info(),
             seeds,
+        )?;
+        let rent_kept =
+            !closed || ctx.accounts.rent_recipient.key() == ctx.accounts.forwarder.key();
+
+        emit_cpi!(ForwardedEvent {
+            forwarder: ctx.accounts.forwarder.key(),
+            cranker: ctx.accounts.sender.key(),
+            depositor: ctx.accounts.depositor.key(),
+            id,
+            mint: Some(ctx.accounts.mint.key()),
+            amount,
+            rent_retained: if rent_kept { account_rent } else { 0 },
+            crank_reward: reward,
+        });
+
+        Ok(())
+    }
+
+    /// Forwards the native balance and multiple spl tokens from the forwarder to the relay depository vault
+    ///
+    /// The spl tokens are passed as `remaining_accounts` groups of (mint, forwarder
+    /// token account, relay vault token account, relay vault ledger, sender token
+    /// account, forward minimum), where the sender token account receives the crank
+    /// reward. Each forwarded asset emits its own deposit event under the same `id`,
+    /// and assets that are empty or below their forward minimum are skipped. Wrapped native
+    /// tokens are unwrapped and deposited as native tokens into the native vault ledger,
+    /// which is then passed as their relay vault ledger.
+    pub fn forward_all<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ForwardAll<'info>>,
+        id: [u8; 32],
+        include_native: bool,
+    ) -> Result<()> {
+        let config = load_config(&ctx.accounts.config)?;
+        require_keys_eq!(
+            ctx.accounts.rent_recipient.key(),
+            config.rent_destination(&ctx.accounts.depositor.key()),
+            ForwarderError::InvalidRentRecipient
+        );
+
+        let token_accounts_groups = ctx.remaining_accounts.chunks_exact(6);
+        require!(
+            token_accounts_groups.remainder().is_empty(),
+            ForwarderError::InvalidRemainingAccounts
         );
-        close_account(close_account_cpi_ctx)?;
+
+        let forwarder_seeds = ForwarderSeeds::new(
+            &ctx.accounts.forwarder,
+            &ctx.accounts.relay_depository_program,
+            &ctx.accounts.relay_depository,
+        )?;
+        let seeds: &[&[&[u8]]] = &[&forwarder_seeds.seeds()];
+        let mut forwarded = false;
+
+        for token_accounts in token_accounts_groups {
+            let [mint, forwarder_token_account, relay_vault_token_account, relay_vault_ledger, sender_token_account, forward_minimum] =
+                token_accounts
+            else {
+                return err!(ForwarderError::InvalidRemainingAccounts);
+            };
+
+            let token_program = if *mint.owner == Token::id() {
+                ctx.accounts.token_program.to_account_info()
+            } else if *mint.owner == Token2022::id() {
+                ctx.accounts.token_2022_program.to_account_info()
+            } else {
+                return err!(ForwarderError::InvalidMint);
+            };
+
+            // Any token account of the mint owned by the forwarder can be forwarded
+            require_keys_eq!(
+                *forwarder_token_account.owner,
+                token_program.key(),
+                ForwarderError::InvalidTokenAccount
+            );
+            let forwarder_token_account_data =
+                InterfaceAccount::<TokenAccount>::try_from(forwarder_token_account)?;
+            require!(
+                forwarder_token_account_data.mint == mint.key()
+                    && forwarder_token_account_data.owner == ctx.accounts.forwarder.key(),
+                ForwarderError::InvalidTokenAccount
+            );
+
+            // Wrapped native tokens are deposited as native tokens, under the native minimum
+            require_keys_eq!(
+                forward_minimum.key(),
+                find_mint_forward_minimum_address(&ctx.accounts.relay_depository.key(), mint.key),
+                ForwarderError::InvalidForwardMinimum
+            );
+
+            let amount = forwarder_token_account_data.amount;
+
+            // Wrapped native tokens are unwrapped and deposited as native tokens
+            if is_native_mint(&mint.key()) {
+                let reward = config.crank_reward.native_reward(amount);
+                if amount <= reward
+                    || amount < load_forward_minimum(forward_minimum)?.minimum_amount
+                {
+                    continue;
+                }
+
+                let rent_retained = unwrap_native_tokens(
+                    token_program,
+                    ctx.accounts.system_program.to_account_info(),
+                    forwarder_token_account.clone(),
+                    ctx.accounts.forwarder.to_account_info(),
+                    ctx.accounts.rent_recipient.to_account_info(),
+                    relay_vault_ledger,
+                    Option::<u64>::from(forwarder_token_account_data.is_native).unwrap_or_default(),
+                    seeds,
+                )?;
+
+                pay_native_crank_reward(
+                    ctx.accounts.system_program.to_account_info(),
+                    ctx.accounts.forwarder.to_account_info(),
+                    ctx.accounts.sender.to_account_info(),
+                    reward,
+                    seeds,
+                )?;
+
+                relay_depository::cpi::deposit_native(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.relay_depository_program.to_account_info(),
+                        relay_depository::cpi::accounts::DepositNative {
+                            relay_depository: ctx.accounts.relay_depository.to_account_info(),
+                            depositor: ctx.accounts.depositor.to_account_info(),
+                            sender: ctx.accounts.forwarder.to_account_info(),
+                            vault: ctx.accounts.relay_vault.to_account_info(),
+                            vault_ledger: relay_vault_ledger.clone(),
+                            system_program: ctx.accounts.system_program.to_account_info(),
+                            vault_config: no_vault_config(&ctx.accounts.relay_depository_program),
+                            event_authority: ctx
+                                .accounts
+                                .relay_depository_event_authority
+                                .to_account_info(),
+                            program: ctx.accounts.relay_depository_program.to_account_info(),
+                        },
+                        seeds,
+                    ),
+                    amount - reward,
+                    id,
+                )?;
+
+                emit_cpi!(ForwardedEvent {
+                    forwarder: ctx.accounts.forwarder.key(),
+                    cranker: ctx.accounts.sender.key(),
+                    depositor: ctx.accounts.depositor.key(),
+                    id,
+                    mint: Some(mint.key()),
+                    amount,
+                    rent_retained,
+                    crank_reward: reward,
+                });
+
+                forwarded = true;
+                continue;
+            }
+
+            let forward_minimum = load_forward_minimum(forward_minimum)?;
+            let reward = config
+                .crank_reward
+                .token_reward(amount, forward_minimum.fixed_token_reward);
+            if amount <= reward || amount < forward_minimum.minimum_amount {
+                continue;
+            }
+
+            if reward > 0 {
+                // The crank reward can only be paid to a token account of the sender
+                let sender_token_account_data =
+                    InterfaceAccount::<TokenAccount>::try_from(sender_token_account)?;
+                require!(
+                    sender_token_account_data.mint == mint.key()
+                        && sender_token_account_data.owner == ctx.accounts.sender.key(),
+                    ForwarderError::InvalidTokenAccount
+                );
+
+                pay_token_crank_reward(
+                    token_program.clone(),
+                    TransferChecked {
+                        mint: mint.clone(),
+                        from: forwarder_token_account.clone(),
+                        to: sender_token_account.clone(),
+                        authority: ctx.accounts.forwarder.to_account_info(),
+                    },
+                    InterfaceAccount::<Mint>::try_from(mint)?.decimals,
+                    reward,
+                    seeds,
+                )?;
+            }
+
+            relay_depository::cpi::deposit_token(
+                CpiContext::new_with_signer(
+                    ctx.accounts.relay_depository_program.to_account_info(),
+                    relay_depository::cpi::accounts::DepositToken {
+                        relay_depository: ctx.accounts.relay_depository.to_account_info(),
+                        depositor: ctx.accounts.depositor.to_account_info(),
+                        sender: ctx.accounts.forwarder.to_account_info(),
+                        mint: mint.clone(),
+                        sender_token_account: forwarder_token_account.clone(),
+                        vault_token_account: relay_vault_token_account.clone(),
+                        vault: ctx.accounts.relay_vault.to_account_info(),
+                        vault_ledger: relay_vault_ledger.clone(),
+                        token_program: token_program.clone(),
+                        associated_token_program: ctx
+                            .accounts
+                            .associated_token_program
+                            .to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        vault_config: no_vault_config(&ctx.accounts.relay_depository_program),
+                        event_authority: ctx
+                            .accounts
+                            .relay_depository_event_authority
+                            .to_account_info(),
+                        program: ctx.accounts.relay_depository_program.to_account_info(),
+                    },
+                    seeds,
+                ),
+                amount - reward,
+                id,
+            )?;
+
+            let account_rent = forwarder_token_account.lamports();
+            let closed = close_forwarder_token_account(
+                token_program,
+                mint.clone(),
+                forwarder_token_account.clone(),
+                ctx.accounts.rent_recipient.to_account_info(),
+                ctx.accounts.forwarder.to_account_info(),
+                seeds,
+            )?;
+            let rent_kept =
+                !closed || ctx.accounts.rent_recipient.key() == ctx.accounts.forwarder.key();
+
+            emit_cpi!(ForwardedEvent {
+                forwarder: ctx.accounts.forwarder.key(),
+                cranker: ctx.accounts.sender.key(),
+                depositor: ctx.accounts.depositor.key(),
+                id,
+                mint: Some(mint.key()),
+                amount,
+                rent_retained: if rent_kept { account_rent } else { 0 },
+                crank_reward: reward,
+            });
+
+            forwarded = true;
+        }
+
+        if include_native {
+            let amount = get_forwardable_native_amount(
+                &ctx.accounts.forwarder,
+                &ctx.accounts.relay_vault_ledger,
+            )?;
+            let reward = config.crank_reward.native_reward(amount);
+            let minimum_amount =
+                load_forward_minimum(&ctx.accounts.native_forward_minimum)?.minimum_amount;
+
+            if amount > reward && amount >= minimum_amount {
+                let rent_retained = ctx.accounts.forwarder.lamports() - amount;
+
+                pay_native_crank_reward(
+                    ctx.accounts.system_program.to_account_info(),
+                    ctx.accounts.forwarder.to_account_info(),
+                    ctx.accounts.sender.to_account_info(),
+                    reward,
+                    seeds,
+                )?;
+
+                relay_depository::cpi::deposit_native(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.relay_depository_program.to_account_info(),
+                        relay_depository::cpi::accounts::DepositNative {
+                            relay_depository: ctx.accounts.relay_depository.to_account_info(),
+                            depositor: ctx.accounts.depositor.to_account_info(),
+                            sender: ctx.accounts.forwarder.to_account_info(),
+                            vault: ctx.accounts.relay_vault.to_account_info(),
+                            vault_ledger: ctx.accounts.relay_vault_ledger.to_account_info(),
+                            system_program: ctx.accounts.system_program.to_account_info(),
+                            vault_config: no_vault_config(&ctx.accounts.relay_depository_program),
+                            event_authority: ctx
+                                .accounts
+                                .relay_depository_event_authority
+                                .to_account_info(),
+                            program: ctx.accounts.relay_depository_program.to_account_info(),
+                        },
+                        seeds,
+                    ),
+                    amount - reward,
+                    id,
+                )?;
+
+                emit_cpi!(ForwardedEvent {
+                    forwarder: ctx.accounts.forwarder.key(),
+                    cranker: ctx.accounts.sender.key(),
+                    depositor: ctx.accounts.depositor.key(),
+                    id,
+                    mint: None,
+                    amount,
+                    rent_retained,
+                    crank_reward: reward,
+                });
+
+                forwarded = true;
+            }
+        }
+
+        require!(forwarded, ForwarderError::InsufficientBalance);
 
         Ok(())
     }
 }
 
+//----------------------------------------
+// Account Structures
+//----------------------------------------
+
+/// Configuration of the forwarder
+///
+/// This account is a PDA derived from the `FORWARDER_CONFIG_SEED` and the forwarder.
+/// Until it is created, the default configuration applies.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct ForwarderConfig {
+    /// The account receiving the rent of closed forwarder token accounts (None for the
+    /// depositor)
+    pub rent_destination: Option<Pubkey>,
+    /// The reward paid to the sender of a forward out of the forwarded balance
+    pub crank_reward: CrankReward,
+}
+
+impl ForwarderConfig {
+    /// Returns the account receiving the rent of closed forwarder token accounts
+    ///
+    /// Without a configured destination the rent goes back to the depositor, who
+    /// usually paid for the forwarder token account.
+    pub fn rent_destination(&self, depositor: &Pubkey) -> Pubkey {
+        self.rent_destination.unwrap_or(*depositor)
+    }
+}
+
+/// Minimum amount of a mint that can be forwarded to a relay depository, and its fixed
+/// crank reward
+///
+/// This account is a PDA derived from the `FORWARD_MINIMUM_SEED`, the relay depository
+/// and the mint (`Pubkey::default()` for native, also applying to wrapped native tokens).
+/// Until it is created, any amount can be forwarded and no fixed reward is paid on token
+/// forwards of the mint.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct ForwardMinimum {
+    /// The relay depository the minimum applies to
+    pub relay_depository: Pubkey,
+    /// The mint of the minimum (`Pubkey::default()` for native)
+    pub mint: Pubkey,
+    /// The minimum amount that can be forwarded
+    pub minimum_amount: u64,
+    /// The crank reward in tokens of the mint paid on token forwards under a
+    /// `CrankReward::Fixed` reward (unused for native)
+    pub fixed_token_reward: u64,
+}
+
+/// Recovery of a forwarder token account announced by the owner of the relay depository
+///
+/// This account is a PDA derived from the `PENDING_RECOVER_SEED` and the forwarder
+/// token account, closed when the recovery is executed or cancelled.
+#[account]
+#[derive(InitSpace)]
+pub struct PendingRecover {
+    /// The recipient of the recovered assets
+    pub recipient: Pubkey,
+    /// The timestamp after which the recovery can be executed
+    pub executable_at: i64,
+}
+
+//----------------------------------------
+// Custom Types
+//----------------------------------------
+
+/// Reward paid to the sender of a forward
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default,
+)]
+pub enum CrankReward {
+    /// No reward
+    #[default]
+    None,
+    /// A fixed amount of lamports on native forwards, and the fixed token reward of the
+    /// forwarded mint (see `ForwardMinimum`) on token forwards
+    Fixed(u64),
+    /// Basis points of the forwarded amount, paid in the forwarded asset
+    BasisPoints(u16),
+}
+
+impl CrankReward {
+    /// Returns the reward for forwarding the given amount of lamports
+    pub fn native_reward(&self, amount: u64) -> u64 {
+        match self {
+            CrankReward::None => 0,
+            CrankReward::Fixed(lamports) => *lamports,
+            CrankReward::BasisPoints(basis_points) => apply_basis_points(amount, *basis_points),
+        }
+    }
+
+    /// Returns the reward for forwarding the given amount of tokens of a mint with the
+    /// given fixed token reward
+    pub fn token_reward(&self, amount: u64, fixed_token_reward: u64) -> u64 {
+        match self {
+            CrankReward::None => 0,
+            CrankReward::Fixed(_) => fixed_token_reward,
+            CrankReward::BasisPoints(basis_points) => apply_basis_points(amount, *basis_points),
+        }
+    }
+}
+
+/// Signer seeds of a forwarder PDA
+///
+/// A forwarder is derived from the `RELAY_FORWARDER_SEED`, the relay depository
+/// program and the relay depository account it forwards to. The legacy forwarder,
+/// derived from the `RELAY_FORWARDER_SEED` only, forwards to the default relay
+/// depository of the relay depository program.
+pub struct ForwarderSeeds {
+    /// The relay depository program and account (None for the legacy forwarder)
+    target: Option<(Pubkey, Pubkey)>,
+    /// The bump seed of the forwarder PDA
+    bump: [u8; 1],
+}
+
+impl ForwarderSeeds {
+    /// Returns the seeds of the forwarder, checking that it forwards to the given relay depository
+    pub fn new(
+        forwarder: &AccountInfo,
+        relay_depository_program: &AccountInfo,
+        relay_depository: &AccountInfo,
+    ) -> Result<Self> {
+        let (address, bump) = find_forwarder_address(
+            &crate::ID,
+            relay_depository_program.key,
+            relay_depository.key,
+        );
+        if forwarder.key() == address {
+            return Ok(Self {
+                target: Some((relay_depository_program.key(), relay_depository.key())),
+                bump: [bump],
+            });
+        }
+
+        let (legacy_address, legacy_bump) = find_legacy_forwarder_address(&crate::ID);
+        let (default_relay_depository, _) = find_relay_depository_address(&RelayDepository::id());
+        require!(
+            forwarder.key() == legacy_address
+                && relay_depository_program.key() == RelayDepository::id()
+                && relay_depository.key() == default_relay_depository,
+            ForwarderError::InvalidForwarder
+        );
+
+        Ok(Self {
+            target: None,
+            bump: [legacy_bump],
+        })
+    }
+
+    /// Returns the signer seeds of the forwarder, including the bump
+    pub fn seeds(&self) -> Vec<&[u8]> {
+        let mut seeds = vec![RELAY_FORWARDER_SEED];
+        if let Some((relay_depository_program, relay_depository)) = &self.target {
+            seeds.push(relay_depository_program.as_ref());
+            seeds.push(relay_depository.as_ref());
+        }
+        seeds.push(&self.bump);
+        seeds
+    }
+}
+
 //----------------------------------------
 // Instruction Contexts
 //----------------------------------------
 
+// Account structure for configuring a forwarder
+#[derive(Accounts)]
+pub struct SetConfig<'info> {
+    // Upgrade authority of the forwarder program
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Forwarder PDA the configuration applies to
+    pub forwarder: UncheckedAccount<'info>,
+
+    // Configuration of the forwarder, created on first use
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ForwarderConfig::INIT_SPACE,
+        seeds = [FORWARDER_CONFIG_SEED, forwarder.key().as_ref()],
+        bump
+    )]
+    pub config: Account<'info, ForwarderConfig>,
+
+    // Forwarder program
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ ForwarderError::InvalidProgramData
+    )]
+    pub program: Program<'info, crate::program::RelayForwarder>,
+
+    // Program data account holding the upgrade authority
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ForwarderError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Account structure for setting the forward minimum of a mint for a relay depository
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetForwardMinimum<'info> {
+    // Owner of the relay depository
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Relay depository program owning the relay depository account
+    pub relay_depository_program: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository account the minimum applies to, holding its owner
+    pub relay_depository: UncheckedAccount<'info>,
+
+    // Forward minimum of the mint for the relay depository, created on first use
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ForwardMinimum::INIT_SPACE,
+        seeds = [FORWARD_MINIMUM_SEED, relay_depository.key().as_ref(), mint.as_ref()],
+        bump
+    )]
+    pub forward_minimum: Account<'info, ForwardMinimum>,
+
+    pub system_program: Program<'info, System>,
+}
+
 // Account structure for forwarding native tokens
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(
     id: [u8; 32],
@@ -100,12 +958,8 @@ pub struct ForwardNative<'info> {
     /// CHECK: Used as public key only
     pub depositor: UncheckedAccount<'info>,
 
-    /// CHECK: Forwarder PDA that will act as the intermediary
-    #[account(
-        mut,
-        seeds = [RELAY_FORWARDER_SEED],
-        bump
-    )]
+    /// CHECK: Forwarder PDA that will act as the intermediary, checked against the target relay depository
+    #[account(mut)]
     pub forwarder: UncheckedAccount<'info>,
 
     /// CHECK: Relay depository program account
@@ -115,7 +969,39 @@ pub struct ForwardNative<'info> {
     #[account(mut)]
     pub relay_vault: UncheckedAccount<'info>,
 
-    pub relay_depository_program: Program<'info, relay_depository::program::RelayDepository>,
+    /// CHECK: Relay depository vault ledger
+    #[account(mut)]
+    pub relay_vault_ledger: UncheckedAccount<'info>,
+
+    /// CHECK: Forwarder configuration PDA, which may not be initialized
+    #[account(
+        seeds = [FORWARDER_CONFIG_SEED, forwarder.key().as_ref()],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Native forward minimum PDA of the relay depository, which may not be initialized
+    #[account(
+        seeds = [
+            FORWARD_MINIMUM_SEED,
+            relay_depository.key().as_ref(),
+            Pubkey::default().as_ref()
+        ],
+        bump
+    )]
+    pub forward_minimum: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository program the forwarder PDA commits to
+    #[account(executable)]
+    pub relay_depository_program: UncheckedAccount<'info>,
+
+    /// CHECK: Event authority of the relay depository program, signing its deposit events
+    #[account(
+        seeds = [EVENT_AUTHORITY_SEED],
+        bump,
+        seeds::program = relay_depository_program.key()
+    )]
+    pub relay_depository_event_authority: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -128,12 +1014,17 @@ impl<'info> ForwardNative<'info> {
             depositor: self.depositor.to_account_info(),
             sender: self.forwarder.to_account_info(),
             vault: self.relay_vault.to_account_info(),
+            vault_ledger: self.relay_vault_ledger.to_account_info(),
             system_program: self.system_program.to_account_info(),
+            vault_config: no_vault_config(&self.relay_depository_program),
+            event_authority: self.relay_depository_event_authority.to_account_info(),
+            program: self.relay_depository_program.to_account_info(),
         }
     }
 }
 
 // Account structure for forwarding spl tokens
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(
     id: [u8; 32],
@@ -146,29 +1037,27 @@ pub struct ForwardToken<'info> {
     /// CHECK: Used as public key only
     pub depositor: UncheckedAccount<'info>,
 
-    /// CHECK: Forwarder PDA that will act as the intermediary
-    #[account(
-        mut,
-        seeds = [RELAY_FORWARDER_SEED],
-        bump
-    )]
+    /// CHECK: Forwarder PDA that will act as the intermediary, checked against the target relay depository
+    #[account(mut)]
     pub forwarder: UncheckedAccount<'info>,
 
     /// CHECK: Relay depository program account
     pub relay_depository: UncheckedAccount<'info>,
 
     /// CHECK: Relay depository vault
+    #[account(mut)]
     pub relay_vault: UncheckedAccount<'info>,
 
-    /// CHECK: Token mint account
+    // Token mint, only passed as writable for Token-2022 mints with the transfer fee
+    // extension, to harvest the withheld transfer fees of the forwarder token account
     pub mint: InterfaceAccount<'info, Mint>,
 
-    /// CHECK: Associated token account for the forwarder PDA
+    // Token account owned by the forwarder PDA, not necessarily its associated token account
     #[account(
         mut,
-        associated_token::mint = mint,
-        associated_token::authority = forwarder,
-        associated_token::token_program = token_program
+        token::mint = mint,
+        token::authority = forwarder,
+        token::token_program = token_program
     )]
     pub forwarder_token_account: InterfaceAccount<'info, TokenAccount>,
 
@@ -176,7 +1065,45 @@ pub struct ForwardToken<'info> {
     #[account(mut)]
     pub relay_vault_token_account: UncheckedAccount<'info>,
 
-    pub relay_depository_program: Program<'info, relay_depository::program::RelayDepository>,
+    /// CHECK: Forwarder configuration PDA, which may not be initialized
+    #[account(
+        seeds = [FORWARDER_CONFIG_SEED, forwarder.key().as_ref()],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the rent of closed forwarder token accounts, checked against the configuration
+    #[account(mut)]
+    pub rent_recipient: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository vault ledger (of native tokens for wrapped native tokens)
+    #[account(mut)]
+    pub relay_vault_ledger: UncheckedAccount<'info>,
+
+    // Token account of the sender receiving the crank reward (only needed if a reward is paid)
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sender,
+        token::token_program = token_program
+    )]
+    pub sender_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Forward minimum PDA of the mint for the relay depository (the native one for
+    /// wrapped native tokens), which may not be initialized, checked in the instruction
+    pub forward_minimum: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository program the forwarder PDA commits to
+    #[account(executable)]
+    pub relay_depository_program: UncheckedAccount<'info>,
+
+    /// CHECK: Event authority of the relay depository program, signing its deposit events
+    #[account(
+        seeds = [EVENT_AUTHORITY_SEED],
+        bump,
+        seeds::program = relay_depository_program.key()
+    )]
+    pub relay_depository_event_authority: UncheckedAccount<'info>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
@@ -194,13 +1121,301 @@ impl<'info> ForwardToken<'info> {
             sender_token_account: self.forwarder_token_account.to_account_info(),
             vault_token_account: self.relay_vault_token_account.to_account_info(),
             vault: self.relay_vault.to_account_info(),
+            vault_ledger: self.relay_vault_ledger.to_account_info(),
             token_program: self.token_program.to_account_info(),
             associated_token_program: self.associated_token_program.to_account_info(),
             system_program: self.system_program.to_account_info(),
+            vault_config: no_vault_config(&self.relay_depository_program),
+            event_authority: self.relay_depository_event_authority.to_account_info(),
+            program: self.relay_depository_program.to_account_info(),
+        }
+    }
+
+    /// Converts `ForwardToken` accounts into `relay_depository::cpi::accounts::DepositNative`
+    /// accounts for depositing unwrapped native tokens
+    fn to_native_deposit_accounts(&self) -> relay_depository::cpi::accounts::DepositNative<'info> {
+        relay_depository::cpi::accounts::DepositNative {
+            relay_depository: self.relay_depository.to_account_info(),
+            depositor: self.depositor.to_account_info(),
+            sender: self.forwarder.to_account_info(),
+            vault: self.relay_vault.to_account_info(),
+            vault_ledger: self.relay_vault_ledger.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            vault_config: no_vault_config(&self.relay_depository_program),
+            event_authority: self.relay_depository_event_authority.to_account_info(),
+            program: self.relay_depository_program.to_account_info(),
         }
     }
 }
 
+// Account structure for forwarding the native balance and multiple spl tokens
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(
+    id: [u8; 32],
+)]
+pub struct ForwardAll<'info> {
+    // User who initiates the forward
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// CHECK: Used as public key only
+    pub depositor: UncheckedAccount<'info>,
+
+    /// CHECK: Forwarder PDA that will act as the intermediary, checked against the target relay depository
+    #[account(mut)]
+    pub forwarder: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository program account
+    pub relay_depository: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository vault
+    #[account(mut)]
+    pub relay_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository vault ledger for native tokens
+    #[account(mut)]
+    pub relay_vault_ledger: UncheckedAccount<'info>,
+
+    /// CHECK: Forwarder configuration PDA, which may not be initialized
+    #[account(
+        seeds = [FORWARDER_CONFIG_SEED, forwarder.key().as_ref()],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the rent of closed forwarder token accounts, checked against the configuration
+    #[account(mut)]
+    pub rent_recipient: UncheckedAccount<'info>,
+
+    /// CHECK: Native forward minimum PDA of the relay depository, which may not be initialized
+    #[account(
+        seeds = [
+            FORWARD_MINIMUM_SEED,
+            relay_depository.key().as_ref(),
+            Pubkey::default().as_ref()
+        ],
+        bump
+    )]
+    pub native_forward_minimum: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository program the forwarder PDA commits to
+    #[account(executable)]
+    pub relay_depository_program: UncheckedAccount<'info>,
+
+    /// CHECK: Event authority of the relay depository program, signing its deposit events
+    #[account(
+        seeds = [EVENT_AUTHORITY_SEED],
+        bump,
+        seeds::program = relay_depository_program.key()
+    )]
+    pub relay_depository_event_authority: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Account structure for announcing the recovery of a forwarder token account
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AnnounceRecover<'info> {
+    // Owner of the relay depository the forwarder forwards to, or the recipient
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Forwarder PDA owning the token account, checked against the target relay depository
+    pub forwarder: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository program the forwarder PDA commits to
+    pub relay_depository_program: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository account the forwarder PDA commits to, holding its owner
+    pub relay_depository: UncheckedAccount<'info>,
+
+    // Token account of the forwarder holding the assets to recover
+    #[account(
+        constraint = forwarder_token_account.owner == forwarder.key() @ ForwarderError::InvalidTokenAccount
+    )]
+    pub forwarder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Pending recovery of the token account, one at a time
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PendingRecover::INIT_SPACE,
+        seeds = [PENDING_RECOVER_SEED, forwarder_token_account.key().as_ref()],
+        bump
+    )]
+    pub pending_recover: Account<'info, PendingRecover>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Account structure for cancelling the recovery of a forwarder token account
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelRecover<'info> {
+    // Owner of the relay depository the forwarder forwards to, or the recipient, refunded
+    // the rent of the pending recovery
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Forwarder PDA owning the token account, checked against the target relay depository
+    pub forwarder: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository program the forwarder PDA commits to
+    pub relay_depository_program: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository account the forwarder PDA commits to, holding its owner
+    pub relay_depository: UncheckedAccount<'info>,
+
+    // Token account of the pending recovery
+    #[account(
+        constraint = forwarder_token_account.owner == forwarder.key() @ ForwarderError::InvalidTokenAccount
+    )]
+    pub forwarder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Pending recovery of the token account
+    #[account(
+        mut,
+        close = authority,
+        seeds = [PENDING_RECOVER_SEED, forwarder_token_account.key().as_ref()],
+        bump
+    )]
+    pub pending_recover: Account<'info, PendingRecover>,
+}
+
+// Account structure for recovering assets from the forwarder
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Recover<'info> {
+    // Owner of the relay depository the forwarder forwards to, or the recipient, refunded
+    // the rent of the pending recovery
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Forwarder PDA owning the recovered token account, checked against the target relay depository
+    pub forwarder: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository program the forwarder PDA commits to
+    pub relay_depository_program: UncheckedAccount<'info>,
+
+    /// CHECK: Relay depository account the forwarder PDA commits to, holding its owner
+    pub relay_depository: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // Token account of the forwarder holding the assets to recover
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = forwarder,
+        token::token_program = token_program
+    )]
+    pub forwarder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Announced recovery of the token account, closed by the recovery
+    #[account(
+        mut,
+        close = authority,
+        seeds = [PENDING_RECOVER_SEED, forwarder_token_account.key().as_ref()],
+        bump
+    )]
+    pub pending_recover: Account<'info, PendingRecover>,
+
+    /// CHECK: Receives the recovered assets and the rent of the closed token account
+    #[account(
+        mut,
+        address = pending_recover.recipient @ ForwarderError::InvalidRecipient
+    )]
+    pub recipient: UncheckedAccount<'info>,
+
+    // Token account of the recipient receiving the recovered assets
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = recipient,
+        token::token_program = token_program
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+//----------------------------------------
+// Events
+//----------------------------------------
+
+/// Event emitted when an asset is forwarded to the relay depository
+///
+/// Emitted next to the `DepositEvent` of the relay depository, whose sender is the forwarder.
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct ForwardedEvent {
+    /// The forwarder account the asset was forwarded from
+    pub forwarder: Pubkey,
+    /// The sender of the forward instruction
+    pub cranker: Pubkey,
+    /// The depositor credited with the deposit
+    pub depositor: Pubkey,
+    /// The unique identifier of the deposit
+    pub id: [u8; 32],
+    /// The forwarded mint (None for native SOL)
+    pub mint: Option<Pubkey>,
+    /// The forwarded amount, including the crank reward
+    pub amount: u64,
+    /// The lamports kept by the forwarder for rent (its own and the vault ledger's),
+    /// or left in the forwarder token account if it couldn't be closed
+    pub rent_retained: u64,
+    /// The crank reward paid to the sender out of the forwarded amount
+    pub crank_reward: u64,
+}
+
+/// Event emitted when the recovery of a forwarder token account is announced
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecoverAnnouncedEvent {
+    /// The authority who announced the recovery, the owner or the recipient
+    pub authority: Pubkey,
+    /// The forwarder token account to recover
+    pub forwarder_token_account: Pubkey,
+    /// The mint of the token account
+    pub mint: Pubkey,
+    /// The recipient of the recovered assets
+    pub recipient: Pubkey,
+    /// The timestamp after which the recovery can be executed
+    pub executable_at: i64,
+}
+
+/// Event emitted when the recovery of a forwarder token account is cancelled
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecoverCancelledEvent {
+    /// The authority who cancelled the recovery, the owner or the recipient
+    pub authority: Pubkey,
+    /// The forwarder token account of the cancelled recovery
+    pub forwarder_token_account: Pubkey,
+    /// The recipient of the cancelled recovery
+    pub recipient: Pubkey,
+}
+
+/// Event emitted when assets are recovered from the forwarder
+#[event]
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecoverEvent {
+    /// The authority who recovered the assets
+    pub authority: Pubkey,
+    /// The mint of the recovered assets
+    pub mint: Pubkey,
+    /// The closed forwarder token account
+    pub forwarder_token_account: Pubkey,
+    /// The recipient of the recovered assets
+    pub recipient: Pubkey,
+    /// The recovered amount
+    pub amount: u64,
+}
+
 //----------------------------------------
 // Error Definitions
 //----------------------------------------
@@ -209,4 +1424,315 @@ impl<'info> ForwardToken<'info> {
 pub enum ForwarderError {
     #[msg("Insufficient balance")]
     InsufficientBalance,
+    #[msg("Invalid remaining accounts")]
+    InvalidRemainingAccounts,
+    #[msg("Invalid mint")]
+    InvalidMint,
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+    #[msg("Invalid rent recipient")]
+    InvalidRentRecipient,
+    #[msg("Invalid program data")]
+    InvalidProgramData,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Invalid crank reward")]
+    InvalidCrankReward,
+    #[msg("Amount below forward minimum")]
+    BelowForwardMinimum,
+    #[msg("Invalid forward minimum")]
+    InvalidForwardMinimum,
+    #[msg("Invalid forwarder")]
+    InvalidForwarder,
+    #[msg("Invalid recipient")]
+    InvalidRecipient,
+    #[msg("Timelock not expired")]
+    TimelockNotExpired,
+    #[msg("Invalid relay depository")]
+    InvalidRelayDepository,
+}
+
+//----------------------------------------
+// Helper Functions
+//----------------------------------------
+
+/// Returns the forwarder configuration, or the default configuration if it is not initialized
+fn load_config(config: &AccountInfo) -> Result<ForwarderConfig> {
+    if config.data_is_empty() {
+        return Ok(ForwarderConfig::default());
+    }
+
+    let data = config.try_borrow_data()?;
+    ForwarderConfig::try_deserialize(&mut &data[..])
+}
+
+/// Returns the forward minimum of a mint, or an empty minimum if it is not initialized
+fn load_forward_minimum(forward_minimum: &AccountInfo) -> Result<ForwardMinimum> {
+    if forward_minimum.data_is_empty() {
+        return Ok(ForwardMinimum::default());
+    }
+
+    let data = forward_minimum.try_borrow_data()?;
+    ForwardMinimum::try_deserialize(&mut &data[..])
+}
+
+/// Returns the address of the forward minimum of a mint forwarded to a relay depository,
+/// the native forward minimum for wrapped native mints which are deposited as native tokens
+fn find_mint_forward_minimum_address(relay_depository: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find_forward_minimum_address(
+        &crate::ID,
+        relay_depository,
+        (!is_native_mint(mint)).then_some(mint),
+    )
+    .0
+}
+
+/// Checks that the authority owns the relay depository the forwarder forwards to, and
+/// returns the seeds of the forwarder
+fn check_relay_depository_owner(
+    authority: &AccountInfo,
+    forwarder: &AccountInfo,
+    relay_depository_program: &AccountInfo,
+    relay_depository: &AccountInfo,
+) -> Result<ForwarderSeeds> {
+    let forwarder_seeds =
+        ForwarderSeeds::new(forwarder, relay_depository_program, relay_depository)?;
+
+    let owner = load_relay_depository_owner(relay_depository_program, relay_depository)?;
+    require_keys_eq!(authority.key(), owner, ForwarderError::Unauthorized);
+
+    Ok(forwarder_seeds)
+}
+
+/// Checks that the authority can recover from the forwarder to the recipient, either as the
+/// owner of the relay depository the forwarder forwards to or as the recipient, and returns
+/// the seeds of the forwarder
+fn check_recover_authority(
+    authority: &AccountInfo,
+    recipient: &Pubkey,
+    forwarder: &AccountInfo,
+    relay_depository_program: &AccountInfo,
+    relay_depository: &AccountInfo,
+) -> Result<ForwarderSeeds> {
+    if authority.key() == *recipient {
+        return ForwarderSeeds::new(forwarder, relay_depository_program, relay_depository);
+    }
+
+    check_relay_depository_owner(
+        authority,
+        forwarder,
+        relay_depository_program,
+        relay_depository,
+    )
+}
+
+/// Returns the owner of a relay depository
+///
+/// The owner is read from the relay depository account, which must be owned by the given
+/// relay depository program.
+fn load_relay_depository_owner(
+    relay_depository_program: &AccountInfo,
+    relay_depository: &AccountInfo,
+) -> Result<Pubkey> {
+    require_keys_eq!(
+        *relay_depository.owner,
+        relay_depository_program.key(),
+        ForwarderError::InvalidRelayDepository
+    );
+    let data = relay_depository.try_borrow_data()?;
+    require!(
+        data.len() >= 8 + 32 && data[..8] == relay_depository::RelayDepository::DISCRIMINATOR,
+        ForwarderError::InvalidRelayDepository
+    );
+
+    // The owner is the first field of the relay depository, also in legacy accounts
+    Ok(Pubkey::new_from_array(data[8..8 + 32].try_into().unwrap()))
+}
+
+/// Returns the placeholder of the absent vault configuration of a deposit
+///
+/// Anchor reads an optional account as absent when its key is the id of the invoked
+/// program, but the CPI client fills a `None` with the id `relay_depository` was
+/// compiled with, which isn't the id of other deployments the forwarder can target.
+fn no_vault_config<'info>(
+    relay_depository_program: &AccountInfo<'info>,
+) -> Option<AccountInfo<'info>> {
+    Some(relay_depository_program.to_account_info())
+}
+
+/// Returns the given basis points of an amount
+fn apply_basis_points(amount: u64, basis_points: u16) -> u64 {
+    (amount as u128 * basis_points as u128 / BASIS_POINTS_DENOMINATOR as u128) as u64
+}
+
+/// Pays the crank reward in lamports from the forwarder to the sender
+fn pay_native_crank_reward<'info>(
+    system_program: AccountInfo<'info>,
+    forwarder: AccountInfo<'info>,
+    sender: AccountInfo<'info>,
+    reward: u64,
+    seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if reward == 0 {
+        return Ok(());
+    }
+
+    transfer(
+        CpiContext::new_with_signer(
+            system_program,
+            Transfer {
+                from: forwarder,
+                to: sender,
+            },
+            seeds,
+        ),
+        reward,
+    )
+}
+
+/// Pays the crank reward in tokens from the forwarder token account to the sender token account
+fn pay_token_crank_reward<'info>(
+    token_program: AccountInfo<'info>,
+    accounts: TransferChecked<'info>,
+    decimals: u8,
+    reward: u64,
+    seeds: &[&[&[u8]]],
+) -> Result<()> {
+    transfer_checked(
+        CpiContext::new_with_signer(token_program, accounts, seeds),
+        reward,
+        decimals,
+    )
+}
+
+/// Closes a forwarder token account, returning whether it was closed
+///
+/// Token-2022 accounts holding withheld transfer fees can't be closed. The fees are
+/// harvested to the mint if it is writable, otherwise the account is left open.
+fn close_forwarder_token_account<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    token_account: AccountInfo<'info>,
+    destination: AccountInfo<'info>,
+    forwarder: AccountInfo<'info>,
+    seeds: &[&[&[u8]]],
+) -> Result<bool> {
+    if get_withheld_amount(&token_account)? > 0 {
+        if !mint.is_writable {
+            return Ok(false);
+        }
+
+        harvest_withheld_tokens_to_mint(
+            CpiContext::new(
+                token_program.clone(),
+                HarvestWithheldTokensToMint {
+                    token_program_id: token_program.clone(),
+                    mint,
+                },
+            ),
+            vec![token_account.clone()],
+        )?;
+    }
+
+    close_account(CpiContext::new_with_signer(
+        token_program,
+        CloseAccount {
+            account: token_account,
+            destination,
+            authority: forwarder,
+        },
+        seeds,
+    ))?;
+
+    Ok(true)
+}
+
+/// Returns the transfer fees withheld in a token account
+fn get_withheld_amount(token_account: &AccountInfo) -> Result<u64> {
+    if *token_account.owner != Token2022::id() {
+        return Ok(0);
+    }
+
+    let data = token_account.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    Ok(account
+        .get_extension::<TransferFeeAmount>()
+        .map(|transfer_fee_amount| u64::from(transfer_fee_amount.withheld_amount))
+        .unwrap_or_default())
+}
+
+/// Returns whether a mint is the wrapped native mint of the token or token 2022 program
+fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+/// Closes a wrapped native token account of the forwarder into the forwarder, unwrapping
+/// its balance, and returns the rent kept by the forwarder
+///
+/// The rent of the closed account stays in the forwarder if it is the rent recipient,
+/// otherwise pays for the vault ledger if it doesn't exist yet and goes to the rent
+/// recipient.
+#[allow(clippy::too_many_arguments)]
+fn unwrap_native_tokens<'info>(
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    token_account: AccountInfo<'info>,
+    forwarder: AccountInfo<'info>,
+    rent_recipient: AccountInfo<'info>,
+    relay_vault_ledger: &AccountInfo<'info>,
+    account_rent: u64,
+    seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    let mut rent_amount = account_rent;
+    if rent_recipient.key() == forwarder.key() {
+        rent_amount = 0;
+    } else if relay_vault_ledger.data_is_empty() {
+        rent_amount = rent_amount.saturating_sub(
+            Rent::get()?.minimum_balance(8 + relay_depository::VaultLedger::INIT_SPACE),
+        );
+    }
+
+    close_account(CpiContext::new_with_signer(
+        token_program,
+        CloseAccount {
+            account: token_account,
+            destination: forwarder.clone(),
+            authority: forwarder.clone(),
+        },
+        seeds,
+    ))?;
+
+    if rent_amount > 0 {
+        transfer(
+            CpiContext::new_with_signer(
+                system_program,
+                Transfer {
+                    from: forwarder,
+                    to: rent_recipient,
+                },
+                seeds,
+            ),
+            rent_amount,
+        )?;
+    }
+
+    Ok(account_rent - rent_amount)
+}
+
+/// Returns the native balance of the forwarder that can be forwarded
+///
+/// The forwarder keeps its rent-exempt minimum, and also pays for the vault
+/// ledger if it doesn't exist yet.
+fn get_forwardable_native_amount(
+    forwarder: &AccountInfo,
+    relay_vault_ledger: &AccountInfo,
+) -> Result<u64> {
+    let rent = Rent::get()?;
+    let mut min_rent = rent.minimum_balance(0);
+
+    if relay_vault_ledger.data_is_empty() {
+        min_rent += rent.minimum_balance(8 + relay_depository::VaultLedger::INIT_SPACE);
+    }
+
+    Ok(forwarder.lamports().saturating_sub(min_rent))
 }
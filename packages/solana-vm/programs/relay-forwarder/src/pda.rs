@@ -0,0 +1,8 @@
+//! Seeds and address derivations of the relay forwarder accounts
+//!
+//! Re-exported from the `relay-pda` crate, which off-chain code can depend on
+//! without pulling in the program. The relay depository derivations are
+//! re-exported as well, so that deposit addresses and their destination vaults
+//! can be derived from a single module.
+
+pub use relay_pda::{depository::*, forwarder::*};
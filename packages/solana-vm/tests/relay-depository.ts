@@ -26,6 +26,10 @@ import { sha256 } from "js-sha256";
 import nacl from "tweetnacl";
 import { createHash } from "crypto";
 
+const MEMO_PROGRAM_ID = new PublicKey(
+  "MemoSq4gqABAXKb96qnH8TyNNYiMwRuozy6KQrb8GG"
+);
+
 import { RelayDepository } from "../target/types/relay_depository";
 
 describe("Relay Depository", () => {
@@ -50,6 +54,12 @@ describe("Relay Depository", () => {
   const recipient = Keypair.generate();
   const wrongRecipient = Keypair.generate();
 
+  // Program data account holding the upgrade authority of the program
+  const [programDataPDA] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
   // PDAs
   let relayDepositoryPDA: PublicKey;
   let vaultPDA: PublicKey;
@@ -224,6 +234,9 @@ describe("Relay Depository", () => {
     program.programId
   );
 
+  // Tag prefixing the data of event CPI instructions (little-endian 0x1d9acb512ea545e4)
+  const EVENT_IX_TAG = Buffer.from("e445a52e51cb9a1d", "hex");
+
   const getEvents = async (signature: string) => {
     await provider.connection.confirmTransaction(signature);
 
@@ -233,16 +246,24 @@ describe("Relay Depository", () => {
       "confirmed"
     );
 
+    // Events are emitted through self CPIs of the program, signed by its event authority
     let events: anchor.Event[] = [];
-    for (const logMessage of depositTxTransaction?.meta?.logMessages || []) {
-      if (!logMessage.startsWith("Program data: ")) {
-        continue;
-      }
-      const event = program.coder.events.decode(
-        logMessage.slice("Program data: ".length)
-      );
-      if (event) {
-        events.push(event);
+    for (const innerInstructions of depositTxTransaction?.meta
+      ?.innerInstructions || []) {
+      for (const ix of innerInstructions.instructions) {
+        if (!ix.programId.equals(program.programId) || !("data" in ix)) {
+          continue;
+        }
+        const data = Buffer.from(anchor.utils.bytes.bs58.decode(ix.data));
+        if (!data.subarray(0, 8).equals(EVENT_IX_TAG)) {
+          continue;
+        }
+        const event = program.coder.events.decode(
+          anchor.utils.bytes.base64.encode(data.subarray(8))
+        );
+        if (event) {
+          events.push(event);
+        }
       }
     }
 
@@ -258,6 +279,8 @@ describe("Relay Depository", () => {
           vault: vaultPDA,
           owner: fakeOwner.publicKey,
           allocator: allocator.publicKey,
+          program: program.programId,
+          programData: programDataPDA,
           systemProgram: SystemProgram.programId,
         })
         .signers([fakeOwner])
@@ -270,16 +293,26 @@ describe("Relay Depository", () => {
   });
 
   it("Should successfully initialize with correct owner", async () => {
+    // The program is deployed by the provider wallet, which is its upgrade authority
     await program.methods
       .initialize("solana-mainnet")
       .accountsPartial({
         relayDepository: relayDepositoryPDA,
         vault: vaultPDA,
-        owner: owner.publicKey,
+        owner: provider.wallet.publicKey,
         allocator: allocator.publicKey,
+        program: program.programId,
+        programData: programDataPDA,
         systemProgram: SystemProgram.programId,
       })
-      .signers([owner])
+      .rpc();
+
+    await program.methods
+      .setOwner(owner.publicKey)
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        owner: provider.wallet.publicKey,
+      })
       .rpc();
 
     // Verify initialization
@@ -296,7 +329,7 @@ describe("Relay Depository", () => {
     const newAllocator = Keypair.generate();
 
     // Call set_allocator as owner
-    await program.methods
+    const setAllocatorTx = await program.methods
       .setAllocator(newAllocator.publicKey)
       .accountsPartial({
         relayDepository: relayDepositoryPDA,
@@ -311,6 +344,16 @@ describe("Relay Depository", () => {
     );
     assert.ok(relayDepositoryAccount.allocator.equals(newAllocator.publicKey));
 
+    // The update takes effect immediately, the event lets it be watched
+    const allocatorUpdatedEvent = (await getEvents(setAllocatorTx)).find(
+      (event) => event.name === "allocatorUpdatedEvent"
+    );
+    assert.exists(allocatorUpdatedEvent, "Allocator updated event should exist");
+    assert.ok(allocatorUpdatedEvent.data.relayDepository.equals(relayDepositoryPDA));
+    assert.isNull(allocatorUpdatedEvent.data.vaultConfig);
+    assert.ok(allocatorUpdatedEvent.data.previousAllocator.equals(allocator.publicKey));
+    assert.ok(allocatorUpdatedEvent.data.newAllocator.equals(newAllocator.publicKey));
+
     // Reset allocator back to original for other tests
     await program.methods
       .setAllocator(allocator.publicKey)
@@ -369,7 +412,7 @@ describe("Relay Depository", () => {
     );
 
     // Call set_owner as current owner
-    await program.methods
+    const setOwnerTx = await program.methods
       .setOwner(newOwner.publicKey)
       .accountsPartial({
         relayDepository: relayDepositoryPDA,
@@ -384,6 +427,13 @@ describe("Relay Depository", () => {
     );
     assert.ok(relayDepositoryAccount.owner.equals(newOwner.publicKey));
 
+    const ownerUpdatedEvent = (await getEvents(setOwnerTx)).find(
+      (event) => event.name === "ownerUpdatedEvent"
+    );
+    assert.exists(ownerUpdatedEvent, "Owner updated event should exist");
+    assert.ok(ownerUpdatedEvent.data.previousOwner.equals(owner.publicKey));
+    assert.ok(ownerUpdatedEvent.data.newOwner.equals(newOwner.publicKey));
+
     // Reset owner back to original for other tests
     await program.methods
       .setOwner(owner.publicKey)
@@ -451,6 +501,7 @@ describe("Relay Depository", () => {
         sender: user.publicKey,
         depositor: user.publicKey,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, null),
         systemProgram: SystemProgram.programId,
       })
       .signers([user])
@@ -483,6 +534,8 @@ describe("Relay Depository", () => {
     assert.equal(depositEvent.data.token, null);
     assert.equal(depositEvent.data.amount.toNumber(), depositAmount);
     assert.equal(depositEvent.data.id.toString(), id.toString());
+    assert.ok(depositEvent.data.relayDepository.equals(relayDepositoryPDA));
+    assert.ok(depositEvent.data.vault.equals(vaultPDA));
   });
 
   it("Deposit token", async () => {
@@ -521,6 +574,7 @@ describe("Relay Depository", () => {
         depositor: user.publicKey,
         vaultTokenAccount: vaultTokenAccount,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, mintPubkey),
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -560,6 +614,8 @@ describe("Relay Depository", () => {
     assert.equal(depositEvent.data.token.toBase58(), mintPubkey.toBase58());
     assert.equal(depositEvent.data.amount.toNumber(), depositAmount);
     assert.equal(depositEvent.data.id.toString(), id.toString());
+    assert.ok(depositEvent.data.relayDepository.equals(relayDepositoryPDA));
+    assert.ok(depositEvent.data.vault.equals(vaultPDA));
   });
 
   it("Deposit token2022", async () => {
@@ -592,6 +648,7 @@ describe("Relay Depository", () => {
         depositor: user.publicKey,
         vaultTokenAccount: vault2022TokenAccount,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, mint2022Pubkey),
         tokenProgram: TOKEN_2022_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -720,6 +777,7 @@ describe("Relay Depository", () => {
         depositor: user.publicKey,
         vaultTokenAccount: vaultFeeTokenAccount,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, mintWithFeePubkey),
         tokenProgram: TOKEN_2022_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -807,6 +865,7 @@ describe("Relay Depository", () => {
           depositor: user.publicKey,
           vaultTokenAccount: wrongVaultTokenAccount, // Use wrong vault token account
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, mintPubkey),
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -868,6 +927,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -924,6 +984,7 @@ describe("Relay Depository", () => {
         executor: provider.wallet.publicKey,
         recipient: recipient.publicKey,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
         usedRequest: requestPDA,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1019,6 +1080,7 @@ describe("Relay Depository", () => {
         executor: provider.wallet.publicKey,
         recipient: recipient.publicKey,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
         usedRequest: requestPDA,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1093,6 +1155,7 @@ describe("Relay Depository", () => {
         executor: provider.wallet.publicKey,
         recipient: recipient.publicKey,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
         usedRequest: requestPDA,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1164,6 +1227,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1224,6 +1288,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1264,6 +1329,7 @@ describe("Relay Depository", () => {
         executor: provider.wallet.publicKey,
         recipient: recipient.publicKey,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
         usedRequest: requestPDA,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1291,6 +1357,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1341,6 +1408,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: wrongRecipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1398,6 +1466,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: wrongRecipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1439,6 +1508,7 @@ describe("Relay Depository", () => {
       nonce: new anchor.BN(Date.now() + Math.floor(Math.random() * 1000)),
       expiration: new anchor.BN(Math.floor(Date.now() / 1000) - 300), // Expired 5 minutes ago
       vaultAddress: vaultPDA,
+      reference: Array(32).fill(0),
     };
 
     const messagHash = hashRequest(request);
@@ -1456,6 +1526,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1502,6 +1573,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1554,6 +1626,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, submittedRequest.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1587,6 +1660,7 @@ describe("Relay Depository", () => {
           sender: user.publicKey,
           depositor: user.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, null),
           systemProgram: SystemProgram.programId,
         })
         .signers([user])
@@ -1615,6 +1689,7 @@ describe("Relay Depository", () => {
           depositor: user.publicKey,
           vaultTokenAccount: vaultTokenAccount,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, mintPubkey),
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -1662,6 +1737,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1697,6 +1773,7 @@ describe("Relay Depository", () => {
         sender: user.publicKey,
         depositor: differentDepositor.publicKey, // Different from sender
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, null),
         systemProgram: SystemProgram.programId,
       })
       .signers([user])
@@ -1740,6 +1817,7 @@ describe("Relay Depository", () => {
         depositor: differentDepositor.publicKey, // Different from sender
         vaultTokenAccount: vaultTokenAccount,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, mintPubkey),
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -1833,6 +1911,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -1871,6 +1950,8 @@ describe("Relay Depository", () => {
           vault: vaultPDA,
           owner: owner.publicKey,
           allocator: newAllocator.publicKey,
+          program: program.programId,
+          programData: programDataPDA,
           systemProgram: SystemProgram.programId,
         })
         .signers([owner])
@@ -1912,6 +1993,7 @@ describe("Relay Depository", () => {
           depositor: user.publicKey,
           vaultTokenAccount: vaultTokenAccount,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, mintPubkey),
           tokenProgram: TOKEN_2022_PROGRAM_ID, // Wrong program - using Token2022 for SPL Token
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -1959,6 +2041,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_2022_PROGRAM_ID, // Wrong program
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -2052,6 +2135,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request1.token),
           usedRequest: requestPDA1,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -2081,6 +2165,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request2.token),
           usedRequest: requestPDA2,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -2206,6 +2291,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request1.token),
           usedRequest: requestPDA1,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -2235,6 +2321,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, request2.token),
           usedRequest: requestPDA2,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -2295,6 +2382,7 @@ describe("Relay Depository", () => {
       nonce: sharedNonce,
       expiration: sharedExpiration,
       vaultAddress: vaultPDA,
+      reference: Array(32).fill(0),
     };
 
     const mainnetMessageHash = hashRequest(mainnetRequest);
@@ -2317,6 +2405,7 @@ describe("Relay Depository", () => {
         executor: provider.wallet.publicKey,
         recipient: recipient.publicKey,
         vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, mainnetRequest.token),
         usedRequest: mainnetRequestPDA,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -2347,6 +2436,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA, // Use correct vault in accounts but wrong vault in request
+          vaultLedger: getVaultLedgerPDA(vaultPDA, testnetRequest.token),
           usedRequest: testnetRequestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -2390,6 +2480,7 @@ describe("Relay Depository", () => {
       nonce: new anchor.BN(Date.now() + Math.floor(Math.random() * 1000)),
       expiration: new anchor.BN(Math.floor(Date.now() / 1000) + 300),
       vaultAddress: fakeVaultAddress, // Wrong vault address
+      reference: Array(32).fill(0),
     };
 
     const messageHash = hashRequest(requestWithWrongVault);
@@ -2407,6 +2498,7 @@ describe("Relay Depository", () => {
           executor: provider.wallet.publicKey,
           recipient: recipient.publicKey,
           vault: vaultPDA, // Actual vault PDA
+          vaultLedger: getVaultLedgerPDA(vaultPDA, requestWithWrongVault.token),
           usedRequest: requestPDA,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -2436,37 +2528,1328 @@ describe("Relay Depository", () => {
     }
   });
 
-  const createTransferRequest = (
-    recipient: PublicKey,
-    token: PublicKey | null,
-    amount: anchor.BN,
-    nonce: anchor.BN,
-    expiration: anchor.BN
-  ) => {
-    const domain = createDomainSeparator(
-      "RelayDepository",
-      "1",
-      "solana-mainnet",
+  it("Execute native transfer with reference and memo", async () => {
+    const transferAmount = LAMPORTS_PER_SOL / 100;
+    const reference = Array.from(Keypair.generate().publicKey.toBytes());
+
+    const request = createTransferRequest(
+      recipient.publicKey,
+      null,
+      new anchor.BN(transferAmount),
+      new anchor.BN(Date.now() + Math.floor(Math.random() * 1000)),
+      new anchor.BN(Math.floor(Date.now() / 1000) + 300),
+      reference
+    );
+
+    const messageHash = hashRequest(request);
+    const signature = nacl.sign.detached(messageHash, allocator.secretKey);
+    const requestPDA = await getUsedRequestPDA(request);
+
+    const txSignature = await program.methods
+      .executeTransfer(request)
+      .accountsPartial({
+        mint: null,
+        vaultTokenAccount: null,
+        recipientTokenAccount: null,
+        relayDepository: relayDepositoryPDA,
+        executor: provider.wallet.publicKey,
+        recipient: recipient.publicKey,
+        vault: vaultPDA,
+        vaultLedger: getVaultLedgerPDA(vaultPDA, request.token),
+        usedRequest: requestPDA,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        memoProgram: MEMO_PROGRAM_ID,
+      })
+      .preInstructions([
+        anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+          publicKey: allocator.publicKey.toBytes(),
+          message: messageHash,
+          signature: signature,
+        }),
+      ])
+      .rpc();
+
+    const events = await getEvents(txSignature);
+    const transferExecutedEvent = events.find(
+      (e) => e.name === "transferExecutedEvent"
+    );
+    assert.deepEqual(
+      Array.from(transferExecutedEvent.data.request.reference),
+      reference,
+      "Incorrect event reference"
+    );
+
+    const transaction = await provider.connection.getTransaction(txSignature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const referenceHex = Buffer.from(reference).toString("hex");
+    assert.isTrue(
+      transaction.meta.logMessages.some((log) => log.includes(referenceHex)),
+      "Memo with the request reference should be logged"
+    );
+  });
+
+  it("Non-owner cannot set treasury", async () => {
+    const [treasuryConfigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), relayDepositoryPDA.toBuffer()],
       program.programId
     );
-    
-    return {
-      domain: Array.from(domain),
-      recipient,
-      token,
-      amount,
-      nonce,
-      expiration,
-      vaultAddress: vaultPDA,
-    };
-  };
 
-  const hashRequest = (request: any) => {
-    const message = program.coder.types.encode("transferRequest", request);
-    const hashData = sha256.create();
-    hashData.update(message);
-    return Buffer.from(hashData.array());
-  };
+    try {
+      await program.methods
+        .setTreasury(fakeOwner.publicKey)
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          treasuryConfig: treasuryConfigPDA,
+          owner: fakeOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([fakeOwner])
+        .rpc();
+
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+  });
+
+  it("Treasury updates and sweeps are timelocked", async () => {
+    const treasury = Keypair.generate();
+    const sweepAmount = new anchor.BN(LAMPORTS_PER_SOL / 100);
+
+    const [treasuryConfigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), relayDepositoryPDA.toBuffer()],
+      program.programId
+    );
+    const [pendingSweepPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pending_sweep"), vaultPDA.toBuffer()],
+      program.programId
+    );
+
+    const setSignature = await program.methods
+      .setTreasury(treasury.publicKey)
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        treasuryConfig: treasuryConfigPDA,
+        owner: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const events = await getEvents(setSignature);
+    const updateRequestedEvent = events.find(
+      (e) => e.name === "treasuryUpdateRequestedEvent"
+    );
+    assert.exists(updateRequestedEvent);
+    assert.ok(updateRequestedEvent.data.treasury.equals(treasury.publicKey));
+
+    // The treasury only changes once the timelock has elapsed
+    const treasuryConfig = await program.account.treasuryConfig.fetch(
+      treasuryConfigPDA
+    );
+    assert.ok(treasuryConfig.treasury.equals(PublicKey.default));
+    assert.ok(treasuryConfig.pendingTreasury.equals(treasury.publicKey));
+    // Same 14 day delay as an emergency withdraw
+    assert.isAtLeast(
+      treasuryConfig.executableAt.toNumber(),
+      Math.floor(Date.now() / 1000) + 14 * 24 * 60 * 60 - 60
+    );
+
+    try {
+      await program.methods
+        .executeTreasuryUpdate()
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          treasuryConfig: treasuryConfigPDA,
+          owner: owner.publicKey,
+        })
+        .signers([owner])
+        .rpc();
+
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "TimelockNotExpired");
+    }
+
+    // No sweep can be requested until a treasury is in effect
+    try {
+      await program.methods
+        .requestSweep(null, sweepAmount)
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          treasuryConfig: treasuryConfigPDA,
+          vault: vaultPDA,
+          pendingSweep: pendingSweepPDA,
+          owner: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "InvalidTreasury");
+    }
+
+    await program.methods
+      .cancelTreasuryUpdate()
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        treasuryConfig: treasuryConfigPDA,
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+
+    const cancelledTreasuryConfig = await program.account.treasuryConfig.fetch(
+      treasuryConfigPDA
+    );
+    assert.isNull(cancelledTreasuryConfig.pendingTreasury);
+  });
+
+  it("Owner can announce and cancel an emergency withdraw", async () => {
+    const emergencyRecipient = Keypair.generate();
+
+    const [pendingEmergencyWithdrawPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pending_emergency_withdraw"), vaultPDA.toBuffer()],
+      program.programId
+    );
+
+    // Non-owner cannot announce an emergency withdraw
+    try {
+      await program.methods
+        .announceEmergencyWithdraw(fakeOwner.publicKey)
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          vault: vaultPDA,
+          pendingEmergencyWithdraw: pendingEmergencyWithdrawPDA,
+          owner: fakeOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([fakeOwner])
+        .rpc();
+
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+
+    const announceSignature = await program.methods
+      .announceEmergencyWithdraw(emergencyRecipient.publicKey)
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        vault: vaultPDA,
+        pendingEmergencyWithdraw: pendingEmergencyWithdrawPDA,
+        owner: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const events = await getEvents(announceSignature);
+    const announcedEvent = events.find(
+      (e) => e.name === "emergencyWithdrawAnnouncedEvent"
+    );
+    assert.exists(announcedEvent);
+    assert.ok(
+      announcedEvent.data.recipient.equals(emergencyRecipient.publicKey)
+    );
+
+    const pendingEmergencyWithdraw =
+      await program.account.pendingEmergencyWithdraw.fetch(
+        pendingEmergencyWithdrawPDA
+      );
+    assert.equal(
+      pendingEmergencyWithdraw.executableAt.toNumber() -
+        pendingEmergencyWithdraw.announcedAt.toNumber(),
+      14 * 24 * 60 * 60
+    );
+
+    const [executedEmergencyWithdrawPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("executed_emergency_withdraw"),
+        vaultPDA.toBuffer(),
+        PublicKey.default.toBuffer(),
+        pendingEmergencyWithdraw.announcedAt.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    // The vault cannot be drained before the timelock expires
+    try {
+      await program.methods
+        .emergencyWithdraw()
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          owner: owner.publicKey,
+          vault: vaultPDA,
+          pendingEmergencyWithdraw: pendingEmergencyWithdrawPDA,
+          recipient: emergencyRecipient.publicKey,
+          mint: null,
+          recipientTokenAccount: null,
+          vaultTokenAccount: null,
+          executedEmergencyWithdraw: executedEmergencyWithdrawPDA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "TimelockNotExpired");
+    }
+
+    await program.methods
+      .cancelEmergencyWithdraw()
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        owner: owner.publicKey,
+        vault: vaultPDA,
+        pendingEmergencyWithdraw: pendingEmergencyWithdrawPDA,
+      })
+      .signers([owner])
+      .rpc();
+
+    const pendingEmergencyWithdrawInfo =
+      await provider.connection.getAccountInfo(pendingEmergencyWithdrawPDA);
+    assert.isNull(
+      pendingEmergencyWithdrawInfo,
+      "Pending emergency withdraw should be closed"
+    );
+  });
+
+  it("Additional vault is controlled by its own allocator", async () => {
+    const vaultIndex = 1;
+    const vaultAllocator = Keypair.generate();
+    const depositAmount = LAMPORTS_PER_SOL / 2;
+    const transferAmount = LAMPORTS_PER_SOL / 10;
+
+    const indexBuffer = Buffer.alloc(2);
+    indexBuffer.writeUInt16LE(vaultIndex);
+    const [vaultConfigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_config"), relayDepositoryPDA.toBuffer(), indexBuffer],
+      program.programId
+    );
+    const [indexedVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), relayDepositoryPDA.toBuffer(), indexBuffer],
+      program.programId
+    );
+
+    await program.methods
+      .createVault(vaultIndex, vaultAllocator.publicKey)
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        vaultConfig: vaultConfigPDA,
+        vault: indexedVaultPDA,
+        owner: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const vaultConfig = await program.account.vaultConfig.fetch(vaultConfigPDA);
+    assert.equal(vaultConfig.index, vaultIndex);
+    assert.ok(vaultConfig.allocator.equals(vaultAllocator.publicKey));
+
+    await program.methods
+      .depositNative(
+        new anchor.BN(depositAmount),
+        Array.from(Keypair.generate().publicKey.toBytes())
+      )
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        sender: user.publicKey,
+        depositor: user.publicKey,
+        vault: indexedVaultPDA,
+        vaultLedger: getVaultLedgerPDA(indexedVaultPDA, null),
+        systemProgram: SystemProgram.programId,
+        vaultConfig: vaultConfigPDA,
+      })
+      .signers([user])
+      .rpc();
+
+    assert.equal(
+      await provider.connection.getBalance(indexedVaultPDA),
+      depositAmount
+    );
+
+    const request = {
+      ...createTransferRequest(
+        recipient.publicKey,
+        null,
+        new anchor.BN(transferAmount),
+        new anchor.BN(Date.now() + Math.floor(Math.random() * 1000)),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 300)
+      ),
+      vaultAddress: indexedVaultPDA,
+    };
+    const messageHash = hashRequest(request);
+    const requestPDA = await getUsedRequestPDA(request);
+
+    const executeTransfer = (signer: Keypair) =>
+      program.methods
+        .executeTransfer(request)
+        .accountsPartial({
+          mint: null,
+          vaultTokenAccount: null,
+          recipientTokenAccount: null,
+          relayDepository: relayDepositoryPDA,
+          executor: provider.wallet.publicKey,
+          recipient: recipient.publicKey,
+          vault: indexedVaultPDA,
+          vaultLedger: getVaultLedgerPDA(indexedVaultPDA, request.token),
+          usedRequest: requestPDA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          memoProgram: null,
+          vaultConfig: vaultConfigPDA,
+        })
+        .preInstructions([
+          anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: signer.publicKey.toBytes(),
+            message: messageHash,
+            signature: nacl.sign.detached(messageHash, signer.secretKey),
+          }),
+        ])
+        .rpc();
+
+    // The default vault allocator cannot sign for the additional vault
+    try {
+      await executeTransfer(allocator);
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "AllocatorSignerMismatch");
+    }
+
+    const recipientBalanceBefore = await provider.connection.getBalance(
+      recipient.publicKey
+    );
+    await executeTransfer(vaultAllocator);
+    const recipientBalanceAfter = await provider.connection.getBalance(
+      recipient.publicKey
+    );
+
+    assert.equal(recipientBalanceAfter - recipientBalanceBefore, transferAmount);
+    assert.equal(
+      await provider.connection.getBalance(indexedVaultPDA),
+      depositAmount - transferAmount
+    );
+  });
+
+  it("Anyone can create an isolated relay depository instance", async () => {
+    const creator = Keypair.generate();
+    const instanceOwner = Keypair.generate();
+    const instanceAllocator = Keypair.generate();
+    const instanceId = new anchor.BN(1);
+    const depositAmount = LAMPORTS_PER_SOL / 2;
+    const transferAmount = LAMPORTS_PER_SOL / 10;
+
+    const airdropSignature = await provider.connection.requestAirdrop(
+      creator.publicKey,
+      LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdropSignature);
+
+    const [instancePDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("relay_depository"),
+        creator.publicKey.toBuffer(),
+        instanceId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const [instanceVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), instancePDA.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createInstance(
+        instanceId,
+        instanceOwner.publicKey,
+        instanceAllocator.publicKey,
+        "solana-mainnet"
+      )
+      .accountsPartial({
+        relayDepository: instancePDA,
+        vault: instanceVaultPDA,
+        creator: creator.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    const instance = await program.account.relayDepository.fetch(instancePDA);
+    const instanceDomainSeparator = createDomainSeparator(
+      "RelayDepository",
+      "1",
+      "solana-mainnet",
+      instancePDA
+    );
+    assert.ok(instance.owner.equals(instanceOwner.publicKey));
+    assert.ok(instance.allocator.equals(instanceAllocator.publicKey));
+    assert.deepEqual(
+      new Uint8Array(instance.domainSeparator),
+      instanceDomainSeparator
+    );
+
+    // The owner of the default relay depository has no authority over the instance
+    try {
+      await program.methods
+        .setAllocator(owner.publicKey)
+        .accountsPartial({
+          relayDepository: instancePDA,
+          owner: owner.publicKey,
+        })
+        .signers([owner])
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+
+    await program.methods
+      .depositNative(
+        new anchor.BN(depositAmount),
+        Array.from(Keypair.generate().publicKey.toBytes())
+      )
+      .accountsPartial({
+        relayDepository: instancePDA,
+        sender: user.publicKey,
+        depositor: user.publicKey,
+        vault: instanceVaultPDA,
+        vaultLedger: getVaultLedgerPDA(instanceVaultPDA, null),
+        systemProgram: SystemProgram.programId,
+        vaultConfig: null,
+      })
+      .signers([user])
+      .rpc();
+
+    // Deposits to the default vault cannot be credited to the instance
+    try {
+      await program.methods
+        .depositNative(
+          new anchor.BN(depositAmount),
+          Array.from(Keypair.generate().publicKey.toBytes())
+        )
+        .accountsPartial({
+          relayDepository: instancePDA,
+          sender: user.publicKey,
+          depositor: user.publicKey,
+          vault: vaultPDA,
+          vaultLedger: getVaultLedgerPDA(vaultPDA, null),
+          systemProgram: SystemProgram.programId,
+          vaultConfig: null,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "InvalidVaultAddress");
+    }
+
+    const request = {
+      ...createTransferRequest(
+        recipient.publicKey,
+        null,
+        new anchor.BN(transferAmount),
+        new anchor.BN(Date.now() + Math.floor(Math.random() * 1000)),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 300)
+      ),
+      domain: Array.from(instanceDomainSeparator),
+      vaultAddress: instanceVaultPDA,
+    };
+    const messageHash = hashRequest(request);
+
+    const recipientBalanceBefore = await provider.connection.getBalance(
+      recipient.publicKey
+    );
+    await program.methods
+      .executeTransfer(request)
+      .accountsPartial({
+        mint: null,
+        vaultTokenAccount: null,
+        recipientTokenAccount: null,
+        relayDepository: instancePDA,
+        executor: provider.wallet.publicKey,
+        recipient: recipient.publicKey,
+        vault: instanceVaultPDA,
+        vaultLedger: getVaultLedgerPDA(instanceVaultPDA, request.token),
+        usedRequest: await getUsedRequestPDA(request),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        memoProgram: null,
+        vaultConfig: null,
+      })
+      .preInstructions([
+        anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+          publicKey: instanceAllocator.publicKey.toBytes(),
+          message: messageHash,
+          signature: nacl.sign.detached(
+            messageHash,
+            instanceAllocator.secretKey
+          ),
+        }),
+      ])
+      .rpc();
+    const recipientBalanceAfter = await provider.connection.getBalance(
+      recipient.publicKey
+    );
+
+    assert.equal(recipientBalanceAfter - recipientBalanceBefore, transferAmount);
+    assert.equal(
+      await provider.connection.getBalance(instanceVaultPDA),
+      depositAmount - transferAmount
+    );
+  });
+
+  it("Vault ledger tracks native deposits and withdrawals", async () => {
+    const depositAmount = LAMPORTS_PER_SOL / 2;
+    const transferAmount = LAMPORTS_PER_SOL / 10;
+    const vaultLedgerPDA = getVaultLedgerPDA(vaultPDA, null);
+
+    const ledgerBefore = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+
+    await program.methods
+      .depositNative(
+        new anchor.BN(depositAmount),
+        Array.from(Keypair.generate().publicKey.toBytes())
+      )
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        sender: user.publicKey,
+        depositor: user.publicKey,
+        vault: vaultPDA,
+        vaultLedger: vaultLedgerPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    const request = createTransferRequest(
+      recipient.publicKey,
+      null,
+      new anchor.BN(transferAmount),
+      new anchor.BN(Date.now() + Math.floor(Math.random() * 1000)),
+      new anchor.BN(Math.floor(Date.now() / 1000) + 300)
+    );
+    const messageHash = hashRequest(request);
+
+    await program.methods
+      .executeTransfer(request)
+      .accountsPartial({
+        mint: null,
+        vaultTokenAccount: null,
+        recipientTokenAccount: null,
+        relayDepository: relayDepositoryPDA,
+        executor: provider.wallet.publicKey,
+        recipient: recipient.publicKey,
+        vault: vaultPDA,
+        vaultLedger: vaultLedgerPDA,
+        usedRequest: await getUsedRequestPDA(request),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .preInstructions([
+        anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+          publicKey: allocator.publicKey.toBytes(),
+          message: messageHash,
+          signature: nacl.sign.detached(messageHash, allocator.secretKey),
+        }),
+      ])
+      .rpc();
+
+    const ledgerAfter = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+    assert.ok(ledgerAfter.vault.equals(vaultPDA));
+    assert.isNull(ledgerAfter.token);
+    assert.equal(
+      ledgerAfter.totalDeposited.sub(ledgerBefore.totalDeposited).toNumber(),
+      depositAmount
+    );
+    assert.equal(
+      ledgerAfter.totalWithdrawn.sub(ledgerBefore.totalWithdrawn).toNumber(),
+      transferAmount
+    );
+
+    // The ledger accounts for the whole vault balance
+    const expectedBalance = ledgerAfter.openingBalance
+      .add(ledgerAfter.totalDeposited)
+      .sub(ledgerAfter.totalWithdrawn);
+    assert.equal(
+      expectedBalance.toNumber(),
+      await provider.connection.getBalance(vaultPDA)
+    );
+  });
+
+  it("Owner can open a vault ledger so depositors don't pay for it", async () => {
+    const depositAmount = LAMPORTS_PER_SOL / 10;
+    const newMintPubkey = await createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      9
+    );
+    const newUserTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      owner,
+      newMintPubkey,
+      user.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      owner,
+      newMintPubkey,
+      newUserTokenAccount,
+      owner,
+      depositAmount
+    );
+    const newVaultTokenAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      owner,
+      newMintPubkey,
+      vaultPDA,
+      undefined,
+      undefined,
+      undefined,
+      true
+    );
+    const vaultLedgerPDA = getVaultLedgerPDA(vaultPDA, newMintPubkey);
+
+    // Only the owner can open a ledger
+    try {
+      await program.methods
+        .openLedger()
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          owner: fakeOwner.publicKey,
+          vault: vaultPDA,
+          mint: newMintPubkey,
+          vaultTokenAccount: newVaultTokenAccount,
+          vaultLedger: vaultLedgerPDA,
+          systemProgram: SystemProgram.programId,
+          vaultConfig: null,
+        })
+        .signers([fakeOwner])
+        .rpc();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+
+    await program.methods
+      .openLedger()
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        owner: owner.publicKey,
+        vault: vaultPDA,
+        mint: newMintPubkey,
+        vaultTokenAccount: newVaultTokenAccount,
+        vaultLedger: vaultLedgerPDA,
+        systemProgram: SystemProgram.programId,
+        vaultConfig: null,
+      })
+      .signers([owner])
+      .rpc();
+
+    const ledger = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+    assert.ok(ledger.vault.equals(vaultPDA));
+    assert.ok(ledger.token.equals(newMintPubkey));
+    assert.equal(ledger.openingBalance.toNumber(), 0);
+
+    // The depositor pays no rent (the provider wallet pays the transaction fee)
+    const userBalanceBefore = await provider.connection.getBalance(
+      user.publicKey
+    );
+    await program.methods
+      .depositToken(
+        new anchor.BN(depositAmount),
+        Array.from(Keypair.generate().publicKey.toBytes())
+      )
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        mint: newMintPubkey,
+        sender: user.publicKey,
+        senderTokenAccount: newUserTokenAccount,
+        depositor: user.publicKey,
+        vaultTokenAccount: newVaultTokenAccount,
+        vault: vaultPDA,
+        vaultLedger: vaultLedgerPDA,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+    const userBalanceAfter = await provider.connection.getBalance(
+      user.publicKey
+    );
+    assert.equal(userBalanceAfter, userBalanceBefore);
+
+    const ledgerAfter = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+    assert.equal(ledgerAfter.totalDeposited.toNumber(), depositAmount);
+  });
+
+  it("Allocator can register SOL sent directly to the vault", async () => {
+    const directAmount = LAMPORTS_PER_SOL / 4;
+    const id = Array.from(Keypair.generate().publicKey.toBytes());
+    const vaultLedgerPDA = getVaultLedgerPDA(vaultPDA, null);
+
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: vaultPDA,
+          lamports: directAmount,
+        })
+      )
+    );
+
+    const registerDirectDeposit = (authority: Keypair, id: number[]) =>
+      program.methods
+        .registerDirectDeposit(user.publicKey, id)
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          authority: authority.publicKey,
+          vault: vaultPDA,
+          mint: null,
+          vaultTokenAccount: null,
+          vaultLedger: vaultLedgerPDA,
+          usedDirectDeposit: getUsedDirectDepositPDA(vaultPDA, id),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    try {
+      await registerDirectDeposit(fakeOwner, id);
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+
+    // The registered amount is the vault balance not accounted for by the ledger
+    const ledgerBefore = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+    const unregisteredAmount = new anchor.BN(
+      await provider.connection.getBalance(vaultPDA)
+    )
+      .sub(ledgerBefore.openingBalance)
+      .sub(ledgerBefore.totalDeposited)
+      .add(ledgerBefore.totalWithdrawn);
+    assert.isAtLeast(unregisteredAmount.toNumber(), directAmount);
+
+    const txSignature = await registerDirectDeposit(allocator, id);
+    const ledgerAfter = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+
+    assert.equal(
+      ledgerAfter.totalDeposited.sub(ledgerBefore.totalDeposited).toString(),
+      unregisteredAmount.toString()
+    );
+
+    const events = await getEvents(txSignature);
+    const depositEvent = events.find((e) => e.name === "depositEvent");
+    assert.ok(depositEvent.data.depositor.equals(user.publicKey));
+    assert.isNull(depositEvent.data.token);
+    assert.equal(depositEvent.data.amount.toString(), unregisteredAmount.toString());
+    assert.deepEqual(Array.from(depositEvent.data.id), id);
+    assert.ok(depositEvent.data.relayDepository.equals(relayDepositoryPDA));
+    assert.ok(depositEvent.data.vault.equals(vaultPDA));
+
+    // The same funds can't be registered twice
+    try {
+      await registerDirectDeposit(
+        allocator,
+        Array.from(Keypair.generate().publicKey.toBytes())
+      );
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "NoDirectDeposit");
+    }
+
+    // Nor can the id be reused
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: vaultPDA,
+          lamports: directAmount,
+        })
+      )
+    );
+    try {
+      await registerDirectDeposit(allocator, id);
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "already in use");
+    }
+  });
+
+  it("Owner can register tokens sent directly to the vault", async () => {
+    const directAmount = 5 * LAMPORTS_PER_SOL;
+    const id = Array.from(Keypair.generate().publicKey.toBytes());
+    const vaultLedgerPDA = getVaultLedgerPDA(vaultPDA, mintPubkey);
+
+    const ledgerBefore = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+    const vaultBalanceBefore = await provider.connection
+      .getTokenAccountBalance(vaultTokenAccount)
+      .then((res) => new anchor.BN(res.value.amount));
+    const unregisteredAmount = vaultBalanceBefore
+      .sub(ledgerBefore.openingBalance)
+      .sub(ledgerBefore.totalDeposited)
+      .add(ledgerBefore.totalWithdrawn)
+      .add(new anchor.BN(directAmount));
+
+    // Tokens sent straight to the vault token account
+    await mintTo(
+      provider.connection,
+      owner,
+      mintPubkey,
+      vaultTokenAccount,
+      owner,
+      directAmount
+    );
+
+    const txSignature = await program.methods
+      .registerDirectDeposit(user.publicKey, id)
+      .accountsPartial({
+        relayDepository: relayDepositoryPDA,
+        authority: owner.publicKey,
+        vault: vaultPDA,
+        mint: mintPubkey,
+        vaultTokenAccount,
+        vaultLedger: vaultLedgerPDA,
+        usedDirectDeposit: getUsedDirectDepositPDA(vaultPDA, id),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const vaultLedger = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+    assert.equal(
+      vaultLedger.totalDeposited.sub(ledgerBefore.totalDeposited).toString(),
+      unregisteredAmount.toString()
+    );
+
+    const events = await getEvents(txSignature);
+    const depositEvent = events.find((e) => e.name === "depositEvent");
+    assert.ok(depositEvent.data.token.equals(mintPubkey));
+    assert.equal(depositEvent.data.amount.toString(), unregisteredAmount.toString());
+
+    // The ledger now accounts for the whole vault balance
+    const expectedBalance = vaultLedger.openingBalance
+      .add(vaultLedger.totalDeposited)
+      .sub(vaultLedger.totalWithdrawn);
+    assert.equal(
+      expectedBalance.toString(),
+      await provider.connection
+        .getTokenAccountBalance(vaultTokenAccount)
+        .then((res) => res.value.amount)
+    );
+  });
+
+  it("Each vault registers direct deposits under its own ids", async () => {
+    const directAmount = LAMPORTS_PER_SOL / 10;
+    const id = Array.from(Keypair.generate().publicKey.toBytes());
+
+    const indexBuffer = Buffer.alloc(2);
+    indexBuffer.writeUInt16LE(1);
+    const [vaultConfigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_config"), relayDepositoryPDA.toBuffer(), indexBuffer],
+      program.programId
+    );
+    const [indexedVaultPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), relayDepositoryPDA.toBuffer(), indexBuffer],
+      program.programId
+    );
+
+    const registerDirectDeposit = (
+      vault: PublicKey,
+      vaultConfig: PublicKey | null
+    ) =>
+      program.methods
+        .registerDirectDeposit(user.publicKey, id)
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          authority: owner.publicKey,
+          vault,
+          mint: null,
+          vaultTokenAccount: null,
+          vaultLedger: getVaultLedgerPDA(vault, null),
+          usedDirectDeposit: getUsedDirectDepositPDA(vault, id),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          vaultConfig,
+        })
+        .signers([owner])
+        .rpc();
+
+    await provider.sendAndConfirm(
+      new Transaction()
+        .add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: vaultPDA,
+            lamports: directAmount,
+          })
+        )
+        .add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: indexedVaultPDA,
+            lamports: directAmount,
+          })
+        )
+    );
+
+    // Registering an id on one vault doesn't block it on another
+    await registerDirectDeposit(vaultPDA, null);
+    await registerDirectDeposit(indexedVaultPDA, vaultConfigPDA);
+
+    assert.isNotNull(
+      await provider.connection.getAccountInfo(
+        getUsedDirectDepositPDA(vaultPDA, id)
+      )
+    );
+    assert.isNotNull(
+      await provider.connection.getAccountInfo(
+        getUsedDirectDepositPDA(indexedVaultPDA, id)
+      )
+    );
+  });
+
+  it("Deposit native against an allocator-signed quote", async () => {
+    const depositAmount = LAMPORTS_PER_SOL / 10;
+
+    const createQuote = (overrides: any = {}) => ({
+      domain: Array.from(domainSeparator),
+      id: Array.from(Keypair.generate().publicKey.toBytes()),
+      depositor: user.publicKey,
+      token: null,
+      minAmount: new anchor.BN(depositAmount),
+      expiration: new anchor.BN(Math.floor(Date.now() / 1000) + 300),
+      vaultAddress: vaultPDA,
+      ...overrides,
+    });
+
+    const depositWithQuote = (quote: any, amount: number) => {
+      const quoteHash = Buffer.from(
+        sha256.array(program.coder.types.encode("depositQuote", quote))
+      );
+      return program.methods
+        .depositNativeWithQuote(quote, new anchor.BN(amount))
+        .accountsPartial({
+          deposit: {
+            relayDepository: relayDepositoryPDA,
+            sender: user.publicKey,
+            depositor: user.publicKey,
+            vault: vaultPDA,
+            vaultLedger: getVaultLedgerPDA(vaultPDA, null),
+            systemProgram: SystemProgram.programId,
+            vaultConfig: null,
+          },
+          usedQuote: PublicKey.findProgramAddressSync(
+            [Buffer.from("used_quote"), quoteHash],
+            program.programId
+          )[0],
+          ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([
+          anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: allocator.publicKey.toBytes(),
+            message: quoteHash,
+            signature: nacl.sign.detached(quoteHash, allocator.secretKey),
+          }),
+        ])
+        .signers([user])
+        .rpc();
+    };
+
+    const failingCases: [any, number, string][] = [
+      [
+        createQuote({ expiration: new anchor.BN(Math.floor(Date.now() / 1000) - 1) }),
+        depositAmount,
+        "SignatureExpired",
+      ],
+      [
+        createQuote({ depositor: wrongRecipient.publicKey }),
+        depositAmount,
+        "InvalidDepositor",
+      ],
+      [createQuote({ token: mintPubkey }), depositAmount, "InvalidMint"],
+      [createQuote(), depositAmount - 1, "InsufficientDepositAmount"],
+    ];
+    for (const [quote, amount, error] of failingCases) {
+      try {
+        await depositWithQuote(quote, amount);
+        assert.fail("Should have thrown error");
+      } catch (err) {
+        assert.include(err.message, error);
+      }
+    }
+
+    const quote = createQuote();
+    const vaultBalanceBefore = await provider.connection.getBalance(vaultPDA);
+    const txSignature = await depositWithQuote(quote, depositAmount);
+    const vaultBalanceAfter = await provider.connection.getBalance(vaultPDA);
+
+    assert.equal(vaultBalanceAfter - vaultBalanceBefore, depositAmount);
+
+    const events = await getEvents(txSignature);
+    const depositEvent = events.find((e) => e.name === "depositEvent");
+    assert.deepEqual(Array.from(depositEvent.data.id), quote.id);
+    assert.equal(depositEvent.data.amount.toNumber(), depositAmount);
+
+    // The same quote can't be used for a second deposit
+    try {
+      await depositWithQuote(quote, depositAmount);
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "already in use");
+    }
+    assert.equal(await provider.connection.getBalance(vaultPDA), vaultBalanceAfter);
+  });
+
+  it("Swap native input for token output atomically", async () => {
+    const inputAmount = LAMPORTS_PER_SOL / 10;
+    const outputAmount = 1_000;
+
+    const request = {
+      domain: Array.from(domainSeparator),
+      id: Array.from(Keypair.generate().publicKey.toBytes()),
+      depositor: user.publicKey,
+      inputToken: null,
+      inputAmount: new anchor.BN(inputAmount),
+      recipient: recipient.publicKey,
+      outputToken: mintPubkey,
+      outputAmount: new anchor.BN(outputAmount),
+      nonce: new anchor.BN(Date.now() + Math.floor(Math.random() * 1000)),
+      expiration: new anchor.BN(Math.floor(Date.now() / 1000) + 300),
+      vaultAddress: vaultPDA,
+    };
+    const requestHash = Buffer.from(
+      sha256.array(program.coder.types.encode("swapRequest", request))
+    );
+    const [usedRequestPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("used_request"), requestHash],
+      program.programId
+    );
+
+    const swap = () =>
+      program.methods
+        .swap(request)
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          depositor: user.publicKey,
+          recipient: recipient.publicKey,
+          vault: vaultPDA,
+          usedRequest: usedRequestPDA,
+          inputMint: null,
+          depositorTokenAccount: null,
+          vaultInputTokenAccount: null,
+          inputVaultLedger: getVaultLedgerPDA(vaultPDA, null),
+          outputMint: mintPubkey,
+          recipientTokenAccount: recipientTokenAccount,
+          vaultOutputTokenAccount: vaultTokenAccount,
+          outputVaultLedger: getVaultLedgerPDA(vaultPDA, mintPubkey),
+          ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          vaultConfig: null,
+        })
+        .preInstructions([
+          anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: allocator.publicKey.toBytes(),
+            message: requestHash,
+            signature: nacl.sign.detached(requestHash, allocator.secretKey),
+          }),
+        ])
+        .signers([user])
+        .rpc();
+
+    const vaultBalanceBefore = await provider.connection.getBalance(vaultPDA);
+    const recipientTokenBalanceBefore =
+      await provider.connection.getTokenAccountBalance(recipientTokenAccount);
+
+    const txSignature = await swap();
+
+    const vaultBalanceAfter = await provider.connection.getBalance(vaultPDA);
+    const recipientTokenBalanceAfter =
+      await provider.connection.getTokenAccountBalance(recipientTokenAccount);
+
+    assert.equal(vaultBalanceAfter - vaultBalanceBefore, inputAmount);
+    assert.equal(
+      Number(recipientTokenBalanceAfter.value.amount) -
+        Number(recipientTokenBalanceBefore.value.amount),
+      outputAmount
+    );
+
+    // Both legs are reported by the swap event only
+    const events = await getEvents(txSignature);
+    assert.notExists(events.find((e) => e.name === "depositEvent"));
+    const swapExecutedEvent = events.find((e) => e.name === "swapExecutedEvent");
+    assert.deepEqual(Array.from(swapExecutedEvent.data.request.id), request.id);
+    assert.equal(swapExecutedEvent.data.depositedAmount.toNumber(), inputAmount);
+    assert.equal(
+      swapExecutedEvent.data.request.outputAmount.toNumber(),
+      outputAmount
+    );
+
+    // The swap request cannot be replayed
+    try {
+      await swap();
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "already in use");
+    }
+  });
+
+  it("Same-token swap records both legs on the input ledger", async () => {
+    const inputAmount = LAMPORTS_PER_SOL / 10;
+    const outputAmount = LAMPORTS_PER_SOL / 20;
+    const vaultLedgerPDA = getVaultLedgerPDA(vaultPDA, null);
+
+    const request = {
+      domain: Array.from(domainSeparator),
+      id: Array.from(Keypair.generate().publicKey.toBytes()),
+      depositor: user.publicKey,
+      inputToken: null,
+      inputAmount: new anchor.BN(inputAmount),
+      recipient: recipient.publicKey,
+      outputToken: null,
+      outputAmount: new anchor.BN(outputAmount),
+      nonce: new anchor.BN(Date.now() + Math.floor(Math.random() * 1000)),
+      expiration: new anchor.BN(Math.floor(Date.now() / 1000) + 300),
+      vaultAddress: vaultPDA,
+    };
+    const requestHash = Buffer.from(
+      sha256.array(program.coder.types.encode("swapRequest", request))
+    );
+
+    const swap = (outputVaultLedger: PublicKey | null) =>
+      program.methods
+        .swap(request)
+        .accountsPartial({
+          relayDepository: relayDepositoryPDA,
+          depositor: user.publicKey,
+          recipient: recipient.publicKey,
+          vault: vaultPDA,
+          usedRequest: PublicKey.findProgramAddressSync(
+            [Buffer.from("used_request"), requestHash],
+            program.programId
+          )[0],
+          inputMint: null,
+          depositorTokenAccount: null,
+          vaultInputTokenAccount: null,
+          inputVaultLedger: vaultLedgerPDA,
+          outputMint: null,
+          recipientTokenAccount: null,
+          vaultOutputTokenAccount: null,
+          outputVaultLedger,
+          ixSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          inputTokenProgram: TOKEN_PROGRAM_ID,
+          outputTokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          vaultConfig: null,
+        })
+        .preInstructions([
+          anchor.web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: allocator.publicKey.toBytes(),
+            message: requestHash,
+            signature: nacl.sign.detached(requestHash, allocator.secretKey),
+          }),
+        ])
+        .signers([user])
+        .rpc();
+
+    // The input ledger is the output ledger, so it can't be passed twice
+    try {
+      await swap(vaultLedgerPDA);
+      assert.fail("Should have thrown error");
+    } catch (err) {
+      assert.include(err.message, "InvalidVaultLedger");
+    }
+
+    const ledgerBefore = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+    await swap(null);
+    const ledgerAfter = await program.account.vaultLedger.fetch(vaultLedgerPDA);
+
+    assert.equal(
+      ledgerAfter.totalDeposited.sub(ledgerBefore.totalDeposited).toNumber(),
+      inputAmount
+    );
+    assert.equal(
+      ledgerAfter.totalWithdrawn.sub(ledgerBefore.totalWithdrawn).toNumber(),
+      outputAmount
+    );
+  });
+
+  const createTransferRequest = (
+    recipient: PublicKey,
+    token: PublicKey | null,
+    amount: anchor.BN,
+    nonce: anchor.BN,
+    expiration: anchor.BN,
+    reference: number[] = Array(32).fill(0)
+  ) => {
+    const domain = createDomainSeparator(
+      "RelayDepository",
+      "1",
+      "solana-mainnet",
+      program.programId
+    );
+    
+    return {
+      domain: Array.from(domain),
+      recipient,
+      token,
+      amount,
+      nonce,
+      expiration,
+      vaultAddress: vaultPDA,
+      reference,
+    };
+  };
+
+  const hashRequest = (request: any) => {
+    const message = program.coder.types.encode("transferRequest", request);
+    const hashData = sha256.create();
+    hashData.update(message);
+    return Buffer.from(hashData.array());
+  };
+
+  const getVaultLedgerPDA = (vault: PublicKey, token: PublicKey | null) => {
+    const [pda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault_ledger"),
+        vault.toBuffer(),
+        (token ?? PublicKey.default).toBuffer(),
+      ],
+      program.programId
+    );
+    return pda;
+  };
+
+  const getUsedDirectDepositPDA = (vault: PublicKey, id: number[]) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("used_direct_deposit"), vault.toBuffer(), Buffer.from(id)],
+      program.programId
+    )[0];
 
   const getUsedRequestPDA = async (request: any) => {
     const requestHash = hashRequest(request);
@@ -2,18 +2,32 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   createMint,
   createAssociatedTokenAccount,
+  createAccount,
   mintTo,
   getAssociatedTokenAddress,
   createAssociatedTokenAccountInstruction,
   createSyncNativeInstruction,
   getAccount,
   NATIVE_MINT,
+  NATIVE_MINT_2022,
+  createCreateNativeMintInstruction,
   createTransferInstruction,
+  createTransferCheckedInstruction,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  ExtensionType,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { execFileSync } from "child_process";
+import * as fs from "fs";
+import * as os from "os";
+import * as path from "path";
 
 import { RelayForwarder } from "../target/types/relay_forwarder";
 import { RelayDepository } from "../target/types/relay_depository";
@@ -42,6 +56,153 @@ describe("Relay Forwarder", () => {
   let senderAta: anchor.web3.PublicKey;
   let vaultAta: anchor.web3.PublicKey;
 
+  const getVaultLedgerPDA = (token: anchor.web3.PublicKey | null) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault_ledger"),
+        vault.toBuffer(),
+        (token ?? anchor.web3.PublicKey.default).toBuffer(),
+      ],
+      depositoryProgram.programId
+    )[0];
+
+  const getForwardMinimumPDA = (
+    token: anchor.web3.PublicKey | null,
+    depository: anchor.web3.PublicKey = relayDepository
+  ) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("forward_minimum"),
+        depository.toBuffer(),
+        (token ?? anchor.web3.PublicKey.default).toBuffer(),
+      ],
+      forwarderProgram.programId
+    )[0];
+
+  // Creates a relay depository instance owned by the sender, with the token
+  // account and the ledgers of its vault, and returns it with its forwarder
+  const createSenderInstance = async () => {
+    const instanceId = new BN(Date.now());
+    const [instance] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("relay_depository"),
+        sender.publicKey.toBuffer(),
+        instanceId.toArrayLike(Buffer, "le", 8),
+      ],
+      depositoryProgram.programId
+    );
+    const [instanceVault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), instance.toBuffer()],
+      depositoryProgram.programId
+    );
+    const instanceVaultAta = getAssociatedTokenAddressSync(
+      mint,
+      instanceVault,
+      true
+    );
+    const getInstanceVaultLedgerPDA = (token: anchor.web3.PublicKey | null) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("vault_ledger"),
+          instanceVault.toBuffer(),
+          (token ?? anchor.web3.PublicKey.default).toBuffer(),
+        ],
+        depositoryProgram.programId
+      )[0];
+
+    await depositoryProgram.methods
+      .createInstance(
+        instanceId,
+        sender.publicKey,
+        sender.publicKey,
+        "solana-mainnet"
+      )
+      .accountsPartial({
+        relayDepository: instance,
+        vault: instanceVault,
+        creator: sender.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        createAssociatedTokenAccountInstruction(
+          sender.publicKey,
+          instanceVaultAta,
+          instanceVault,
+          mint
+        )
+      )
+    );
+    for (const token of [null, mint]) {
+      await depositoryProgram.methods
+        .openLedger()
+        .accountsPartial({
+          relayDepository: instance,
+          owner: sender.publicKey,
+          vault: instanceVault,
+          mint: token,
+          vaultTokenAccount: token ? instanceVaultAta : null,
+          vaultLedger: getInstanceVaultLedgerPDA(token),
+          systemProgram: anchor.web3.SystemProgram.programId,
+          vaultConfig: null,
+        })
+        .rpc();
+    }
+
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("relay_forwarder"),
+        depositoryProgram.programId.toBuffer(),
+        instance.toBuffer(),
+      ],
+      forwarderProgram.programId
+    );
+
+    return {
+      instance,
+      instanceVault,
+      instanceVaultAta,
+      getInstanceVaultLedgerPDA,
+      forwarderPda,
+    };
+  };
+
+  const [programDataPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+    [forwarderProgram.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  // Tag prefixing the data of event CPI instructions (little-endian 0x1d9acb512ea545e4)
+  const EVENT_IX_TAG = Buffer.from("e445a52e51cb9a1d", "hex");
+
+  // Decodes the events emitted by a program through self CPIs in a transaction
+  const getCpiEvents = (
+    transaction: anchor.web3.ParsedTransactionWithMeta | null,
+    program: Program<RelayDepository> | Program<RelayForwarder>
+  ) => {
+    let events: any[] = [];
+    for (const innerInstructions of transaction?.meta?.innerInstructions ||
+      []) {
+      for (const ix of innerInstructions.instructions) {
+        if (!ix.programId.equals(program.programId) || !("data" in ix)) {
+          continue;
+        }
+        const data = Buffer.from(anchor.utils.bytes.bs58.decode(ix.data));
+        if (!data.subarray(0, 8).equals(EVENT_IX_TAG)) {
+          continue;
+        }
+        const event = program.coder.events.decode(
+          anchor.utils.bytes.base64.encode(data.subarray(8))
+        );
+        if (event) {
+          events.push(event);
+        }
+      }
+    }
+    return events;
+  };
+
   before(async () => {
     // Airdrop SOL to test accounts
     const airdropPromises = [
@@ -99,6 +260,11 @@ describe("Relay Forwarder", () => {
     //     vault,
     //     owner: wallet.publicKey,
     //     allocator: wallet.publicKey,
+    //     program: depositoryProgram.programId,
+    //     programData: anchor.web3.PublicKey.findProgramAddressSync(
+    //       [depositoryProgram.programId.toBuffer()],
+    //       new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    //     )[0],
     //     systemProgram: anchor.web3.SystemProgram.programId,
     //   })
     //   .rpc();
@@ -193,6 +359,8 @@ describe("Relay Forwarder", () => {
         forwarder: forwarderPda,
         relayDepository,
         relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(null),
+        forwardMinimum: getForwardMinimumPDA(null),
         relayDepositoryProgram: depositoryProgram.programId,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
@@ -237,18 +405,7 @@ describe("Relay Forwarder", () => {
       }
     );
 
-    let events: any[] = [];
-    for (const logMessage of depositTxTransaction?.meta?.logMessages || []) {
-      if (!logMessage.startsWith("Program data: ")) {
-        continue;
-      }
-      const event = depositoryProgram.coder.events.decode(
-        logMessage.slice("Program data: ".length)
-      );
-      if (event) {
-        events.push(event);
-      }
-    }
+    const events = getCpiEvents(depositTxTransaction, depositoryProgram);
 
     const DepositEvent = events.find((event) => event.name === "depositEvent");
     assert.exists(DepositEvent);
@@ -258,6 +415,305 @@ describe("Relay Forwarder", () => {
       depositor.publicKey.toBase58()
     );
     assert.equal(DepositEvent?.data.id.toString(), id.toString());
+
+    // The forwarder emits its own event with the cranker
+    const ForwardedEvent = getCpiEvents(
+      depositTxTransaction,
+      forwarderProgram
+    ).find((event) => event.name === "forwardedEvent");
+    assert.exists(ForwardedEvent);
+    assert.ok(ForwardedEvent?.data.forwarder.equals(forwarderPda));
+    assert.ok(ForwardedEvent?.data.cranker.equals(sender.publicKey));
+    assert.ok(ForwardedEvent?.data.depositor.equals(depositor.publicKey));
+    assert.deepEqual(Array.from(ForwardedEvent?.data.id), id);
+    assert.isNull(ForwardedEvent?.data.mint);
+    assert.equal(ForwardedEvent?.data.amount.toNumber(), depositAmount);
+    assert.isAtLeast(ForwardedEvent?.data.rentRetained.toNumber(), 890880);
+    assert.equal(ForwardedEvent?.data.crankReward.toNumber(), 0);
+  });
+
+  it("Forward native through a forwarder bound to a relay depository", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+
+    // The forwarder commits to the relay depository program and account
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("relay_forwarder"),
+        depositoryProgram.programId.toBuffer(),
+        relayDepository.toBuffer(),
+      ],
+      forwarderProgram.programId
+    );
+
+    const depositAmount = anchor.web3.LAMPORTS_PER_SOL / 10;
+    const rentExemptBalance =
+      await provider.connection.getMinimumBalanceForRentExemption(0);
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: sender.publicKey,
+          toPubkey: forwarderPda,
+          lamports: depositAmount + rentExemptBalance,
+        })
+      )
+    );
+
+    const forwardNative = (relayDepositoryAccount: anchor.web3.PublicKey) =>
+      forwarderProgram.methods
+        .forwardNative(id)
+        .accountsPartial({
+          sender: sender.publicKey,
+          depositor: depositor.publicKey,
+          forwarder: forwarderPda,
+          relayDepository: relayDepositoryAccount,
+          relayVault: vault,
+          relayVaultLedger: getVaultLedgerPDA(null),
+          forwardMinimum: getForwardMinimumPDA(null, relayDepositoryAccount),
+          relayDepositoryProgram: depositoryProgram.programId,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    // Funds can't be forwarded to another relay depository
+    try {
+      await forwardNative(anchor.web3.Keypair.generate().publicKey);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Invalid forwarder");
+    }
+
+    const vaultBalanceBefore = await provider.connection.getBalance(vault);
+    await forwardNative(relayDepository);
+    const vaultBalanceAfter = await provider.connection.getBalance(vault);
+
+    assert.equal(vaultBalanceAfter - vaultBalanceBefore, depositAmount);
+  });
+
+  it("Forward native and token into a second relay depository deployment", async () => {
+    // Deploy the relay depository program a second time, under a program id patched
+    // into the binary in place of the declared one
+    const secondProgramKeypair = anchor.web3.Keypair.generate();
+    const secondProgramId = secondProgramKeypair.publicKey;
+    const elf = fs.readFileSync("target/deploy/relay_depository.so");
+    const declaredId = depositoryProgram.programId.toBuffer();
+    let offset = elf.indexOf(declaredId);
+    assert.notEqual(offset, -1);
+    while (offset !== -1) {
+      secondProgramId.toBuffer().copy(elf, offset);
+      offset = elf.indexOf(declaredId, offset + declaredId.length);
+    }
+
+    const deployDir = fs.mkdtempSync(
+      path.join(os.tmpdir(), "relay-depository-")
+    );
+    const elfPath = path.join(deployDir, "relay_depository.so");
+    const programKeypairPath = path.join(deployDir, "program-keypair.json");
+    fs.writeFileSync(elfPath, elf);
+    fs.writeFileSync(
+      programKeypairPath,
+      JSON.stringify(Array.from(secondProgramKeypair.secretKey))
+    );
+    execFileSync("solana", [
+      "program",
+      "deploy",
+      "--url",
+      provider.connection.rpcEndpoint,
+      "--keypair",
+      process.env.ANCHOR_WALLET,
+      "--program-id",
+      programKeypairPath,
+      elfPath,
+    ]);
+    // Programs can only be invoked from the slot after their deployment
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+
+    const secondDepositoryProgram = new Program<RelayDepository>(
+      { ...depositoryProgram.idl, address: secondProgramId.toBase58() },
+      provider
+    );
+
+    // A relay depository instance of the second deployment
+    const instanceId = new BN(Date.now());
+    const [instance] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("relay_depository"),
+        sender.publicKey.toBuffer(),
+        instanceId.toArrayLike(Buffer, "le", 8),
+      ],
+      secondProgramId
+    );
+    const [instanceVault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), instance.toBuffer()],
+      secondProgramId
+    );
+    const instanceVaultAta = getAssociatedTokenAddressSync(
+      mint,
+      instanceVault,
+      true
+    );
+    const getInstanceVaultLedgerPDA = (token: anchor.web3.PublicKey | null) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("vault_ledger"),
+          instanceVault.toBuffer(),
+          (token ?? anchor.web3.PublicKey.default).toBuffer(),
+        ],
+        secondProgramId
+      )[0];
+
+    await secondDepositoryProgram.methods
+      .createInstance(
+        instanceId,
+        sender.publicKey,
+        sender.publicKey,
+        "solana-mainnet"
+      )
+      .accountsPartial({
+        relayDepository: instance,
+        vault: instanceVault,
+        creator: sender.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        createAssociatedTokenAccountInstruction(
+          sender.publicKey,
+          instanceVaultAta,
+          instanceVault,
+          mint
+        )
+      )
+    );
+
+    // The owner opens the ledgers, so that the forwarder pays for none of them
+    for (const token of [null, mint]) {
+      await secondDepositoryProgram.methods
+        .openLedger()
+        .accountsPartial({
+          relayDepository: instance,
+          owner: sender.publicKey,
+          vault: instanceVault,
+          mint: token,
+          vaultTokenAccount: token ? instanceVaultAta : null,
+          vaultLedger: getInstanceVaultLedgerPDA(token),
+          systemProgram: anchor.web3.SystemProgram.programId,
+          vaultConfig: null,
+        })
+        .rpc();
+    }
+
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("relay_forwarder"),
+        secondProgramId.toBuffer(),
+        instance.toBuffer(),
+      ],
+      forwarderProgram.programId
+    );
+    const forwarderAta = getAssociatedTokenAddressSync(
+      mint,
+      forwarderPda,
+      true
+    );
+
+    const depositAmount = 1_000_000;
+    const rentExemptBalance =
+      await provider.connection.getMinimumBalanceForRentExemption(0);
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          anchor.web3.SystemProgram.transfer({
+            fromPubkey: sender.publicKey,
+            toPubkey: forwarderPda,
+            lamports: depositAmount + rentExemptBalance,
+          })
+        )
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderAta,
+            forwarderPda,
+            mint
+          )
+        )
+        .add(
+          createTransferInstruction(
+            senderAta,
+            forwarderAta,
+            sender.publicKey,
+            depositAmount
+          )
+        )
+    );
+
+    const vaultBalanceBefore = await provider.connection.getBalance(
+      instanceVault
+    );
+    const forwardNativeTx = await forwarderProgram.methods
+      .forwardNative(
+        Array.from(anchor.web3.Keypair.generate().publicKey.toBytes())
+      )
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository: instance,
+        relayVault: instanceVault,
+        relayVaultLedger: getInstanceVaultLedgerPDA(null),
+        forwardMinimum: getForwardMinimumPDA(null, instance),
+        relayDepositoryProgram: secondProgramId,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const vaultBalanceAfter = await provider.connection.getBalance(
+      instanceVault
+    );
+    assert.equal(vaultBalanceAfter - vaultBalanceBefore, depositAmount);
+
+    const forwardTokenTx = await forwarderProgram.methods
+      .forwardToken(
+        Array.from(anchor.web3.Keypair.generate().publicKey.toBytes())
+      )
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository: instance,
+        relayVault: instanceVault,
+        relayVaultLedger: getInstanceVaultLedgerPDA(mint),
+        forwardMinimum: getForwardMinimumPDA(mint, instance),
+        mint,
+        forwarderTokenAccount: forwarderAta,
+        relayVaultTokenAccount: instanceVaultAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: null,
+        relayDepositoryProgram: secondProgramId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const vaultTokenBalance = await provider.connection
+      .getTokenAccountBalance(instanceVaultAta)
+      .then((res) => res.value.amount);
+    assert.equal(vaultTokenBalance, depositAmount.toString());
+
+    // Both deposits are made to, and emitted by, the second deployment
+    for (const tx of [forwardNativeTx, forwardTokenTx]) {
+      const transaction = await provider.connection.getParsedTransaction(tx, {
+        commitment: "confirmed",
+      });
+      const DepositEvent = getCpiEvents(
+        transaction,
+        secondDepositoryProgram
+      ).find((event) => event.name === "depositEvent");
+      assert.exists(DepositEvent);
+      assert.equal(DepositEvent?.data.amount.toNumber(), depositAmount);
+      assert.ok(DepositEvent?.data.depositor.equals(depositor.publicKey));
+      assert.ok(DepositEvent?.data.relayDepository.equals(instance));
+      assert.ok(DepositEvent?.data.vault.equals(instanceVault));
+    }
   });
 
   it("Forward token", async () => {
@@ -315,11 +771,16 @@ describe("Relay Forwarder", () => {
       .accountsPartial({
         sender: sender.publicKey,
         depositor: depositor.publicKey,
+        forwarder: forwarderPda,
         relayDepository,
         relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(mint),
+        forwardMinimum: getForwardMinimumPDA(mint),
         mint,
         forwarderTokenAccount: forwarderAta,
         relayVaultTokenAccount: vaultAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: null,
         relayDepositoryProgram: depositoryProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -335,18 +796,7 @@ describe("Relay Forwarder", () => {
         commitment: "confirmed",
       }
     );
-    let events: any[] = [];
-    for (const logMessage of depositTxTransaction?.meta?.logMessages || []) {
-      if (!logMessage.startsWith("Program data: ")) {
-        continue;
-      }
-      const event = depositoryProgram.coder.events.decode(
-        logMessage.slice("Program data: ".length)
-      );
-      if (event) {
-        events.push(event);
-      }
-    }
+    const events = getCpiEvents(depositTxTransaction, depositoryProgram);
 
     const DepositEvent = events.find((event) => event.name === "depositEvent");
     assert.exists(DepositEvent);
@@ -384,75 +834,67 @@ describe("Relay Forwarder", () => {
     );
   });
 
-  it("Forward wrapped-native and close account successfully", async () => {
+  it("Should fail to send the rent to an unconfigured recipient", async () => {
     const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
-
-    // Get forwarder PDA and its wrapped SOL account
     const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
       [Buffer.from("relay_forwarder")],
       forwarderProgram.programId
     );
-
-    // Get PDA's wrapped SOL account
-    const forwarderWsolAta = await getAssociatedTokenAddress(
-      NATIVE_MINT,
+    const forwarderAta = await getAssociatedTokenAddress(
+      mint,
       forwarderPda,
       true
     );
 
-    // Get vault's wrapped SOL account
-    const vaultWsolAta = await getAssociatedTokenAddress(
-      NATIVE_MINT,
-      vault,
-      true
-    );
-
-    // Amount to wrap and forward
-    const wrapAmount = 1 * anchor.web3.LAMPORTS_PER_SOL;
-
-    // Create vault's wrapped SOL account if it doesn't exist
-    try {
-      await getAccount(provider.connection, vaultWsolAta);
-    } catch (err) {
-      const tx = new anchor.web3.Transaction().add(
-        createAssociatedTokenAccountInstruction(
-          sender.publicKey,
-          vaultWsolAta,
-          vault,
-          NATIVE_MINT
-        )
-      );
-      await provider.sendAndConfirm(tx);
-    }
-
-    // Get initial vault balance
-    const vaultWsolBefore = await provider.connection
-      .getTokenAccountBalance(vaultWsolAta)
-      .then((res) => new BN(res.value.amount))
-      .catch(() => new BN(0));
-
-    // Create PDA's wrapped SOL account and transfer SOL directly from sender
     await provider.sendAndConfirm(
       new anchor.web3.Transaction()
         .add(
           createAssociatedTokenAccountInstruction(
             sender.publicKey,
-            forwarderWsolAta,
+            forwarderAta,
             forwarderPda,
-            NATIVE_MINT
+            mint
           )
         )
         .add(
-          anchor.web3.SystemProgram.transfer({
-            fromPubkey: sender.publicKey,
-            toPubkey: forwarderWsolAta,
-            lamports: wrapAmount,
-          })
+          createTransferInstruction(
+            senderAta,
+            forwarderAta,
+            sender.publicKey,
+            1_000_000
+          )
         )
-        .add(createSyncNativeInstruction(forwarderWsolAta))
     );
 
-    // Forward wrapped SOL and close account
+    // Without a configured rent destination, only the depositor can receive the rent
+    try {
+      await forwarderProgram.methods
+        .forwardToken(id)
+        .accountsPartial({
+          sender: sender.publicKey,
+          depositor: depositor.publicKey,
+          forwarder: forwarderPda,
+          relayDepository,
+          relayVault: vault,
+          relayVaultLedger: getVaultLedgerPDA(mint),
+          forwardMinimum: getForwardMinimumPDA(mint),
+          mint,
+          forwarderTokenAccount: forwarderAta,
+          relayVaultTokenAccount: vaultAta,
+          rentRecipient: sender.publicKey,
+          senderTokenAccount: null,
+          relayDepositoryProgram: depositoryProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "InvalidRentRecipient");
+    }
+
+    // The balance is forwarded once the rent goes to the depositor
     await forwarderProgram.methods
       .forwardToken(id)
       .accountsPartial({
@@ -461,23 +903,244 @@ describe("Relay Forwarder", () => {
         forwarder: forwarderPda,
         relayDepository,
         relayVault: vault,
-        mint: NATIVE_MINT,
-        forwarderTokenAccount: forwarderWsolAta,
-        relayVaultTokenAccount: vaultWsolAta,
+        relayVaultLedger: getVaultLedgerPDA(mint),
+        forwardMinimum: getForwardMinimumPDA(mint),
+        mint,
+        forwarderTokenAccount: forwarderAta,
+        relayVaultTokenAccount: vaultAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: null,
         relayDepositoryProgram: depositoryProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
+  });
 
-    // Wait for transaction confirmation
-    await new Promise((resolve) => setTimeout(resolve, 3000));
-
-    // Get final vault balance
-    const vaultWsolAfter = await provider.connection
-      .getTokenAccountBalance(vaultWsolAta)
-      .then((res) => new BN(res.value.amount));
+  it("Forward token from a non-associated token account", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("relay_forwarder")],
+      forwarderProgram.programId
+    );
+
+    // A token account owned by the forwarder, like the ones created by cex withdrawals
+    const forwarderTokenAccount = await createAccount(
+      provider.connection,
+      sender.payer,
+      mint,
+      forwarderPda,
+      anchor.web3.Keypair.generate()
+    );
+
+    const depositAmount = 1_000_000;
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        createTransferInstruction(
+          senderAta,
+          forwarderTokenAccount,
+          sender.publicKey,
+          depositAmount
+        )
+      )
+    );
+
+    const vaultTokenBalanceBefore = await provider.connection
+      .getTokenAccountBalance(vaultAta)
+      .then((res) => res.value.amount);
+
+    await forwarderProgram.methods
+      .forwardToken(id)
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository,
+        relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(mint),
+        forwardMinimum: getForwardMinimumPDA(mint),
+        mint,
+        forwarderTokenAccount,
+        relayVaultTokenAccount: vaultAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: null,
+        relayDepositoryProgram: depositoryProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const vaultTokenBalanceAfter = await provider.connection
+      .getTokenAccountBalance(vaultAta)
+      .then((res) => res.value.amount);
+    assert.equal(
+      Number(vaultTokenBalanceAfter) - Number(vaultTokenBalanceBefore),
+      depositAmount
+    );
+
+    // The forwarder token account is closed after forwarding
+    assert.isNull(
+      await provider.connection.getAccountInfo(forwarderTokenAccount)
+    );
+  });
+
+  it("Forward token with a read-only mint", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("relay_forwarder")],
+      forwarderProgram.programId
+    );
+    const forwarderAta = await getAssociatedTokenAddress(
+      mint,
+      forwarderPda,
+      true
+    );
+
+    const depositAmount = 1_000_000;
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderAta,
+            forwarderPda,
+            mint
+          )
+        )
+        .add(
+          createTransferInstruction(
+            senderAta,
+            forwarderAta,
+            sender.publicKey,
+            depositAmount
+          )
+        )
+    );
+
+    const forwardTokenIx = await forwarderProgram.methods
+      .forwardToken(id)
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository,
+        relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(mint),
+        forwardMinimum: getForwardMinimumPDA(mint),
+        mint,
+        forwarderTokenAccount: forwarderAta,
+        relayVaultTokenAccount: vaultAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: null,
+        relayDepositoryProgram: depositoryProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .instruction();
+
+    // An SPL mint is not write-locked by forwards
+    const mintMeta = forwardTokenIx.keys.find((key) => key.pubkey.equals(mint));
+    assert.isFalse(mintMeta.isWritable);
+
+    const vaultTokenBalanceBefore = await provider.connection
+      .getTokenAccountBalance(vaultAta)
+      .then((res) => res.value.amount);
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(forwardTokenIx)
+    );
+
+    const vaultTokenBalanceAfter = await provider.connection
+      .getTokenAccountBalance(vaultAta)
+      .then((res) => res.value.amount);
+    assert.equal(
+      Number(vaultTokenBalanceAfter) - Number(vaultTokenBalanceBefore),
+      depositAmount
+    );
+    assert.isNull(await provider.connection.getAccountInfo(forwarderAta));
+  });
+
+  it("Forward wrapped-native as native and close account successfully", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+
+    // Get forwarder PDA and its wrapped SOL account
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("relay_forwarder")],
+      forwarderProgram.programId
+    );
+
+    // Get PDA's wrapped SOL account
+    const forwarderWsolAta = await getAssociatedTokenAddress(
+      NATIVE_MINT,
+      forwarderPda,
+      true
+    );
+
+    // Get vault's wrapped SOL account
+    const vaultWsolAta = await getAssociatedTokenAddress(
+      NATIVE_MINT,
+      vault,
+      true
+    );
+
+    // Amount to wrap and forward
+    const wrapAmount = 1 * anchor.web3.LAMPORTS_PER_SOL;
+
+    // Create PDA's wrapped SOL account and transfer SOL directly from sender
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderWsolAta,
+            forwarderPda,
+            NATIVE_MINT
+          )
+        )
+        .add(
+          anchor.web3.SystemProgram.transfer({
+            fromPubkey: sender.publicKey,
+            toPubkey: forwarderWsolAta,
+            lamports: wrapAmount,
+          })
+        )
+        .add(createSyncNativeInstruction(forwarderWsolAta))
+    );
+
+    // Get initial balances
+    const vaultBalanceBefore = await provider.connection.getBalance(vault);
+    const depositorBalanceBefore = await provider.connection.getBalance(
+      depositor.publicKey
+    );
+
+    // Forward wrapped SOL and close account, the native vault ledger is used
+    const forwardTx = await forwarderProgram.methods
+      .forwardToken(id)
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository,
+        relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(null),
+        forwardMinimum: getForwardMinimumPDA(null),
+        mint: NATIVE_MINT,
+        forwarderTokenAccount: forwarderWsolAta,
+        relayVaultTokenAccount: vaultWsolAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: null,
+        relayDepositoryProgram: depositoryProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Wait for transaction confirmation
+    await new Promise((resolve) => setTimeout(resolve, 3000));
 
     // Verify wrapped SOL account is closed
     try {
@@ -487,12 +1150,1088 @@ describe("Relay Forwarder", () => {
       assert.include(err.toString(), "TokenAccountNotFoundError");
     }
 
-    // Verify vault received all wrapped SOL
+    // Verify vault received all wrapped SOL as native SOL
+    const vaultBalanceAfter = await provider.connection.getBalance(vault);
+    assert.equal(
+      vaultBalanceAfter - vaultBalanceBefore,
+      wrapAmount,
+      "Vault should receive all wrapped SOL as native SOL"
+    );
+
+    // The rent of the closed account goes to the depositor
+    assert.equal(
+      (await provider.connection.getBalance(depositor.publicKey)) -
+        depositorBalanceBefore,
+      await provider.connection.getMinimumBalanceForRentExemption(165)
+    );
+
+    // The deposit is credited as native SOL
+    const forwardTxTransaction = await provider.connection.getParsedTransaction(
+      forwardTx,
+      { commitment: "confirmed" }
+    );
+    const DepositEvent = getCpiEvents(
+      forwardTxTransaction,
+      depositoryProgram
+    ).find((event) => event.name === "depositEvent");
+    assert.exists(DepositEvent);
+    assert.equal(DepositEvent?.data.amount.toNumber(), wrapAmount);
+    assert.isNull(DepositEvent?.data.token);
+  });
+
+  it("Forward token-2022 wrapped-native as native", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("relay_forwarder")],
+      forwarderProgram.programId
+    );
+
+    // The token-2022 native mint isn't created by default
+    if (!(await provider.connection.getAccountInfo(NATIVE_MINT_2022))) {
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          createCreateNativeMintInstruction(sender.publicKey)
+        )
+      );
+    }
+
+    const forwarderWsolAta = getAssociatedTokenAddressSync(
+      NATIVE_MINT_2022,
+      forwarderPda,
+      true,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const vaultWsolAta = getAssociatedTokenAddressSync(
+      NATIVE_MINT_2022,
+      vault,
+      true,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const wrapAmount = anchor.web3.LAMPORTS_PER_SOL / 2;
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderWsolAta,
+            forwarderPda,
+            NATIVE_MINT_2022,
+            TOKEN_2022_PROGRAM_ID
+          )
+        )
+        .add(
+          anchor.web3.SystemProgram.transfer({
+            fromPubkey: sender.publicKey,
+            toPubkey: forwarderWsolAta,
+            lamports: wrapAmount,
+          })
+        )
+        .add(createSyncNativeInstruction(forwarderWsolAta, TOKEN_2022_PROGRAM_ID))
+    );
+
+    const vaultBalanceBefore = await provider.connection.getBalance(vault);
+
+    const forwardTx = await forwarderProgram.methods
+      .forwardToken(id)
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository,
+        relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(null),
+        forwardMinimum: getForwardMinimumPDA(null),
+        mint: NATIVE_MINT_2022,
+        forwarderTokenAccount: forwarderWsolAta,
+        relayVaultTokenAccount: vaultWsolAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: null,
+        relayDepositoryProgram: depositoryProgram.programId,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // The wrapped SOL account is closed and the vault holds no token-2022 wrapped SOL
+    assert.isNull(await provider.connection.getAccountInfo(forwarderWsolAta));
+    assert.isNull(await provider.connection.getAccountInfo(vaultWsolAta));
     assert.equal(
-      vaultWsolAfter.sub(vaultWsolBefore).toString(),
-      new BN(wrapAmount).toString(),
-      "Vault should receive all wrapped SOL"
+      (await provider.connection.getBalance(vault)) - vaultBalanceBefore,
+      wrapAmount
+    );
+
+    const forwardTxTransaction = await provider.connection.getParsedTransaction(
+      forwardTx,
+      { commitment: "confirmed" }
+    );
+    const DepositEvent = getCpiEvents(
+      forwardTxTransaction,
+      depositoryProgram
+    ).find((event) => event.name === "depositEvent");
+    assert.exists(DepositEvent);
+    assert.equal(DepositEvent?.data.amount.toNumber(), wrapAmount);
+    assert.isNull(DepositEvent?.data.token);
+  });
+
+  it("Forward native and token balances in one instruction", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("relay_forwarder")],
+      forwarderProgram.programId
     );
+    const forwarderAta = await getAssociatedTokenAddress(
+      mint,
+      forwarderPda,
+      true
+    );
+
+    const nativeAmount = anchor.web3.LAMPORTS_PER_SOL / 10;
+    const tokenAmount = 1_000_000;
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          anchor.web3.SystemProgram.transfer({
+            fromPubkey: sender.publicKey,
+            toPubkey: forwarderPda,
+            lamports: nativeAmount,
+          })
+        )
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderAta,
+            forwarderPda,
+            mint
+          )
+        )
+        .add(
+          createTransferInstruction(
+            senderAta,
+            forwarderAta,
+            sender.publicKey,
+            tokenAmount
+          )
+        )
+    );
+
+    const vaultBalanceBefore = await provider.connection.getBalance(vault);
+    const vaultTokenBalanceBefore = await provider.connection
+      .getTokenAccountBalance(vaultAta)
+      .then((res) => res.value.amount);
+    const depositorBalanceBefore = await provider.connection.getBalance(
+      depositor.publicKey
+    );
+
+    const forwardTx = await forwarderProgram.methods
+      .forwardAll(id, true)
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository,
+        relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(null),
+        rentRecipient: depositor.publicKey,
+        nativeForwardMinimum: getForwardMinimumPDA(null),
+        relayDepositoryProgram: depositoryProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: mint, isWritable: false, isSigner: false },
+        { pubkey: forwarderAta, isWritable: true, isSigner: false },
+        { pubkey: vaultAta, isWritable: true, isSigner: false },
+        { pubkey: getVaultLedgerPDA(mint), isWritable: true, isSigner: false },
+        { pubkey: senderAta, isWritable: true, isSigner: false },
+        { pubkey: getForwardMinimumPDA(mint), isWritable: false, isSigner: false },
+      ])
+      .rpc();
+
+    const vaultBalanceAfter = await provider.connection.getBalance(vault);
+    const vaultTokenBalanceAfter = await provider.connection
+      .getTokenAccountBalance(vaultAta)
+      .then((res) => res.value.amount);
+
+    assert.equal(vaultBalanceAfter - vaultBalanceBefore, nativeAmount);
+    assert.equal(
+      Number(vaultTokenBalanceAfter) - Number(vaultTokenBalanceBefore),
+      tokenAmount
+    );
+
+    // The forwarder token account is closed, and its rent goes to the depositor
+    assert.isNull(await provider.connection.getAccountInfo(forwarderAta));
+    assert.equal(
+      (await provider.connection.getBalance(depositor.publicKey)) -
+        depositorBalanceBefore,
+      await provider.connection.getMinimumBalanceForRentExemption(165)
+    );
+
+    // One deposit event per asset, all under the same id
+    const forwardTxTransaction = await provider.connection.getParsedTransaction(
+      forwardTx,
+      { commitment: "confirmed" }
+    );
+    const events = getCpiEvents(
+      forwardTxTransaction,
+      depositoryProgram
+    ).filter((event) => event.name === "depositEvent");
+
+    assert.equal(events.length, 2);
+    for (const event of events) {
+      assert.deepEqual(Array.from(event.data.id), id);
+      assert.ok(event.data.depositor.equals(depositor.publicKey));
+    }
+  });
+
+  it("Forward token-2022 with transfer fees and close account", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("relay_forwarder")],
+      forwarderProgram.programId
+    );
+
+    // Create a token-2022 mint with a 1% transfer fee
+    const feeMint = anchor.web3.Keypair.generate();
+    const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          anchor.web3.SystemProgram.createAccount({
+            fromPubkey: sender.publicKey,
+            newAccountPubkey: feeMint.publicKey,
+            space: mintLen,
+            lamports:
+              await provider.connection.getMinimumBalanceForRentExemption(
+                mintLen
+              ),
+            programId: TOKEN_2022_PROGRAM_ID,
+          })
+        )
+        .add(
+          createInitializeTransferFeeConfigInstruction(
+            feeMint.publicKey,
+            sender.publicKey,
+            sender.publicKey,
+            100,
+            BigInt(1_000_000_000),
+            TOKEN_2022_PROGRAM_ID
+          )
+        )
+        .add(
+          createInitializeMintInstruction(
+            feeMint.publicKey,
+            9,
+            sender.publicKey,
+            null,
+            TOKEN_2022_PROGRAM_ID
+          )
+        ),
+      [feeMint]
+    );
+
+    const senderFeeAta = await createAssociatedTokenAccount(
+      provider.connection,
+      sender.payer,
+      feeMint.publicKey,
+      sender.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      provider.connection,
+      sender.payer,
+      feeMint.publicKey,
+      senderFeeAta,
+      sender.publicKey,
+      10_000_000,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    const forwarderFeeAta = getAssociatedTokenAddressSync(
+      feeMint.publicKey,
+      forwarderPda,
+      true,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const vaultFeeAta = getAssociatedTokenAddressSync(
+      feeMint.publicKey,
+      vault,
+      true,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    // The forwarder token account receives 9_900_000 tokens, and 100_000 are withheld
+    const transferAmount = 10_000_000;
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderFeeAta,
+            forwarderPda,
+            feeMint.publicKey,
+            TOKEN_2022_PROGRAM_ID
+          )
+        )
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            vaultFeeAta,
+            vault,
+            feeMint.publicKey,
+            TOKEN_2022_PROGRAM_ID
+          )
+        )
+        .add(
+          createTransferCheckedInstruction(
+            senderFeeAta,
+            feeMint.publicKey,
+            forwarderFeeAta,
+            sender.publicKey,
+            transferAmount,
+            9,
+            [],
+            TOKEN_2022_PROGRAM_ID
+          )
+        )
+    );
+
+    // The mint is writable so that the withheld fees can be harvested before closing
+    await forwarderProgram.methods
+      .forwardAll(id, false)
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository,
+        relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(null),
+        rentRecipient: depositor.publicKey,
+        nativeForwardMinimum: getForwardMinimumPDA(null),
+        relayDepositoryProgram: depositoryProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: feeMint.publicKey, isWritable: true, isSigner: false },
+        { pubkey: forwarderFeeAta, isWritable: true, isSigner: false },
+        { pubkey: vaultFeeAta, isWritable: true, isSigner: false },
+        {
+          pubkey: getVaultLedgerPDA(feeMint.publicKey),
+          isWritable: true,
+          isSigner: false,
+        },
+        { pubkey: senderFeeAta, isWritable: true, isSigner: false },
+        {
+          pubkey: getForwardMinimumPDA(feeMint.publicKey),
+          isWritable: false,
+          isSigner: false,
+        },
+      ])
+      .rpc();
+
+    // The vault receives the forwarded amount minus the fee of the second transfer
+    const vaultTokenBalance = await provider.connection
+      .getTokenAccountBalance(vaultFeeAta)
+      .then((res) => res.value.amount);
+    assert.equal(Number(vaultTokenBalance), 9_900_000 - 99_000);
+
+    assert.isNull(await provider.connection.getAccountInfo(forwarderFeeAta));
+  });
+
+  it("Pay the crank reward to the sender", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("relay_forwarder")],
+      forwarderProgram.programId
+    );
+    const forwarderAta = await getAssociatedTokenAddress(
+      mint,
+      forwarderPda,
+      true
+    );
+    // Only the upgrade authority can configure the crank reward
+    const unauthorized = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        unauthorized.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+    try {
+      await forwarderProgram.methods
+        .setConfig(null, { basisPoints: { 0: 100 } })
+        .accountsPartial({
+          authority: unauthorized.publicKey,
+          forwarder: forwarderPda,
+          program: forwarderProgram.programId,
+          programData: programDataPDA,
+        })
+        .signers([unauthorized])
+        .rpc();
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+
+    // 1% of the forwarded balance goes to the sender
+    await forwarderProgram.methods
+      .setConfig(null, { basisPoints: { 0: 100 } })
+      .accountsPartial({
+        authority: sender.publicKey,
+        forwarder: forwarderPda,
+        program: forwarderProgram.programId,
+        programData: programDataPDA,
+      })
+      .rpc();
+
+    const tokenAmount = 1_000_000;
+    const reward = tokenAmount / 100;
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderAta,
+            forwarderPda,
+            mint
+          )
+        )
+        .add(
+          createTransferInstruction(
+            senderAta,
+            forwarderAta,
+            sender.publicKey,
+            tokenAmount
+          )
+        )
+    );
+
+    const vaultTokenBalanceBefore = await provider.connection
+      .getTokenAccountBalance(vaultAta)
+      .then((res) => res.value.amount);
+    const senderTokenBalanceBefore = await provider.connection
+      .getTokenAccountBalance(senderAta)
+      .then((res) => res.value.amount);
+
+    await forwarderProgram.methods
+      .forwardToken(id)
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository,
+        relayVault: vault,
+        relayVaultLedger: getVaultLedgerPDA(mint),
+        forwardMinimum: getForwardMinimumPDA(mint),
+        mint,
+        forwarderTokenAccount: forwarderAta,
+        relayVaultTokenAccount: vaultAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: senderAta,
+        relayDepositoryProgram: depositoryProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const vaultTokenBalanceAfter = await provider.connection
+      .getTokenAccountBalance(vaultAta)
+      .then((res) => res.value.amount);
+    const senderTokenBalanceAfter = await provider.connection
+      .getTokenAccountBalance(senderAta)
+      .then((res) => res.value.amount);
+
+    assert.equal(
+      Number(vaultTokenBalanceAfter) - Number(vaultTokenBalanceBefore),
+      tokenAmount - reward
+    );
+    assert.equal(
+      Number(senderTokenBalanceAfter) - Number(senderTokenBalanceBefore),
+      reward
+    );
+
+    // Reset the configuration for the other tests
+    await forwarderProgram.methods
+      .setConfig(null, { none: {} })
+      .accountsPartial({
+        authority: sender.publicKey,
+        forwarder: forwarderPda,
+        program: forwarderProgram.programId,
+        programData: programDataPDA,
+      })
+      .rpc();
+  });
+
+  it("Pay a fixed token crank reward set per mint", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    // Forward minimums are set by the owner of the relay depository they apply to
+    const {
+      instance,
+      instanceVault,
+      instanceVaultAta,
+      getInstanceVaultLedgerPDA,
+      forwarderPda,
+    } = await createSenderInstance();
+    const forwarderAta = await getAssociatedTokenAddress(
+      mint,
+      forwarderPda,
+      true
+    );
+
+    const tokenAmount = 1_000_000;
+    const reward = 5_000;
+
+    // The fixed lamports reward only applies to native forwards, token forwards
+    // pay the fixed reward of the mint
+    await forwarderProgram.methods
+      .setConfig(null, { fixed: { 0: new BN(10_000) } })
+      .accountsPartial({
+        authority: sender.publicKey,
+        forwarder: forwarderPda,
+        program: forwarderProgram.programId,
+        programData: programDataPDA,
+      })
+      .rpc();
+    await forwarderProgram.methods
+      .setForwardMinimum(mint, new BN(0), new BN(reward))
+      .accountsPartial({
+        authority: sender.publicKey,
+        relayDepositoryProgram: depositoryProgram.programId,
+        relayDepository: instance,
+        forwardMinimum: getForwardMinimumPDA(mint, instance),
+      })
+      .rpc();
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderAta,
+            forwarderPda,
+            mint
+          )
+        )
+        .add(
+          createTransferInstruction(
+            senderAta,
+            forwarderAta,
+            sender.publicKey,
+            tokenAmount
+          )
+        )
+    );
+
+    const vaultTokenBalanceBefore = await provider.connection
+      .getTokenAccountBalance(instanceVaultAta)
+      .then((res) => res.value.amount);
+    const senderTokenBalanceBefore = await provider.connection
+      .getTokenAccountBalance(senderAta)
+      .then((res) => res.value.amount);
+
+    await forwarderProgram.methods
+      .forwardToken(id)
+      .accountsPartial({
+        sender: sender.publicKey,
+        depositor: depositor.publicKey,
+        forwarder: forwarderPda,
+        relayDepository: instance,
+        relayVault: instanceVault,
+        relayVaultLedger: getInstanceVaultLedgerPDA(mint),
+        forwardMinimum: getForwardMinimumPDA(mint, instance),
+        mint,
+        forwarderTokenAccount: forwarderAta,
+        relayVaultTokenAccount: instanceVaultAta,
+        rentRecipient: depositor.publicKey,
+        senderTokenAccount: senderAta,
+        relayDepositoryProgram: depositoryProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const vaultTokenBalanceAfter = await provider.connection
+      .getTokenAccountBalance(instanceVaultAta)
+      .then((res) => res.value.amount);
+    const senderTokenBalanceAfter = await provider.connection
+      .getTokenAccountBalance(senderAta)
+      .then((res) => res.value.amount);
+
+    assert.equal(
+      Number(vaultTokenBalanceAfter) - Number(vaultTokenBalanceBefore),
+      tokenAmount - reward
+    );
+    assert.equal(
+      Number(senderTokenBalanceAfter) - Number(senderTokenBalanceBefore),
+      reward
+    );
+  });
+
+  it("Keep accumulating balances below the forward minimum", async () => {
+    const id = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+    const {
+      instance,
+      instanceVault,
+      instanceVaultAta,
+      getInstanceVaultLedgerPDA,
+      forwarderPda,
+    } = await createSenderInstance();
+    const forwarderAta = await getAssociatedTokenAddress(
+      mint,
+      forwarderPda,
+      true
+    );
+
+    const minimumAmount = 2_000_000;
+    const tokenAmount = 1_000_000;
+
+    const setForwardMinimum = (
+      authority: anchor.web3.Keypair,
+      token: anchor.web3.PublicKey,
+      amount: number
+    ) =>
+      forwarderProgram.methods
+        .setForwardMinimum(token, new BN(amount), new BN(0))
+        .accountsPartial({
+          authority: authority.publicKey,
+          relayDepositoryProgram: depositoryProgram.programId,
+          relayDepository: instance,
+          forwardMinimum: getForwardMinimumPDA(token, instance),
+        })
+        .signers([authority])
+        .rpc();
+
+    // Only the owner of the relay depository can set its forward minimums
+    const unauthorized = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        unauthorized.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+    try {
+      await setForwardMinimum(unauthorized, mint, minimumAmount);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+
+    await setForwardMinimum(sender.payer, mint, minimumAmount);
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderAta,
+            forwarderPda,
+            mint
+          )
+        )
+        .add(
+          createTransferInstruction(
+            senderAta,
+            forwarderAta,
+            sender.publicKey,
+            tokenAmount
+          )
+        )
+    );
+
+    const forwardToken = () =>
+      forwarderProgram.methods
+        .forwardToken(id)
+        .accountsPartial({
+          sender: sender.publicKey,
+          depositor: depositor.publicKey,
+          forwarder: forwarderPda,
+          relayDepository: instance,
+          relayVault: instanceVault,
+          relayVaultLedger: getInstanceVaultLedgerPDA(mint),
+          forwardMinimum: getForwardMinimumPDA(mint, instance),
+          mint,
+          forwarderTokenAccount: forwarderAta,
+          relayVaultTokenAccount: instanceVaultAta,
+          rentRecipient: depositor.publicKey,
+          senderTokenAccount: null,
+          relayDepositoryProgram: depositoryProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    try {
+      await forwardToken();
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Amount below forward minimum");
+    }
+
+    // Once the balance reaches the minimum, it is forwarded
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        createTransferInstruction(
+          senderAta,
+          forwarderAta,
+          sender.publicKey,
+          tokenAmount
+        )
+      )
+    );
+
+    const vaultTokenBalanceBefore = await provider.connection
+      .getTokenAccountBalance(instanceVaultAta)
+      .then((res) => res.value.amount);
+
+    await forwardToken();
+
+    const vaultTokenBalanceAfter = await provider.connection
+      .getTokenAccountBalance(instanceVaultAta)
+      .then((res) => res.value.amount);
+    assert.equal(
+      Number(vaultTokenBalanceAfter) - Number(vaultTokenBalanceBefore),
+      2 * tokenAmount
+    );
+
+    // Wrapped native tokens are forwarded under the native minimum, and have no
+    // minimum of their own
+    try {
+      await setForwardMinimum(sender.payer, NATIVE_MINT, 0);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Invalid mint");
+    }
+    await setForwardMinimum(
+      sender.payer,
+      anchor.web3.PublicKey.default,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+
+    const forwarderWsolAta = getAssociatedTokenAddressSync(
+      NATIVE_MINT,
+      forwarderPda,
+      true
+    );
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction()
+        .add(
+          createAssociatedTokenAccountInstruction(
+            sender.publicKey,
+            forwarderWsolAta,
+            forwarderPda,
+            NATIVE_MINT
+          )
+        )
+        .add(
+          anchor.web3.SystemProgram.transfer({
+            fromPubkey: sender.publicKey,
+            toPubkey: forwarderWsolAta,
+            lamports: anchor.web3.LAMPORTS_PER_SOL / 10,
+          })
+        )
+        .add(createSyncNativeInstruction(forwarderWsolAta))
+    );
+
+    const forwardWrappedNative = (forwardMinimum: anchor.web3.PublicKey) =>
+      forwarderProgram.methods
+        .forwardToken(id)
+        .accountsPartial({
+          sender: sender.publicKey,
+          depositor: depositor.publicKey,
+          forwarder: forwarderPda,
+          relayDepository: instance,
+          relayVault: instanceVault,
+          relayVaultLedger: getInstanceVaultLedgerPDA(null),
+          forwardMinimum,
+          mint: NATIVE_MINT,
+          forwarderTokenAccount: forwarderWsolAta,
+          relayVaultTokenAccount: getAssociatedTokenAddressSync(
+            NATIVE_MINT,
+            instanceVault,
+            true
+          ),
+          rentRecipient: depositor.publicKey,
+          senderTokenAccount: null,
+          relayDepositoryProgram: depositoryProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    try {
+      await forwardWrappedNative(getForwardMinimumPDA(NATIVE_MINT, instance));
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Invalid forward minimum");
+    }
+    try {
+      await forwardWrappedNative(getForwardMinimumPDA(null, instance));
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Amount below forward minimum");
+    }
+  });
+
+  it("Recovering assets that cannot be forwarded is timelocked", async () => {
+    // A relay depository instance whose owner authorizes the recoveries of its forwarder
+    const owner = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        owner.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    const instanceId = new BN(Date.now());
+    const [instance] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("relay_depository"),
+        sender.publicKey.toBuffer(),
+        instanceId.toArrayLike(Buffer, "le", 8),
+      ],
+      depositoryProgram.programId
+    );
+    const [instanceVault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), instance.toBuffer()],
+      depositoryProgram.programId
+    );
+    await depositoryProgram.methods
+      .createInstance(
+        instanceId,
+        owner.publicKey,
+        sender.publicKey,
+        "solana-mainnet"
+      )
+      .accountsPartial({
+        relayDepository: instance,
+        vault: instanceVault,
+        creator: sender.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const [forwarderPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("relay_forwarder"),
+        depositoryProgram.programId.toBuffer(),
+        instance.toBuffer(),
+      ],
+      forwarderProgram.programId
+    );
+
+    // An nft sent to the forwarder by mistake
+    const nftMint = await createMint(
+      provider.connection,
+      sender.payer,
+      sender.publicKey,
+      null,
+      0
+    );
+    const forwarderAta = await createAssociatedTokenAccount(
+      provider.connection,
+      sender.payer,
+      nftMint,
+      forwarderPda,
+      undefined,
+      undefined,
+      undefined,
+      true
+    );
+    await mintTo(
+      provider.connection,
+      sender.payer,
+      nftMint,
+      forwarderAta,
+      sender.publicKey,
+      1
+    );
+
+    const recipient = anchor.web3.Keypair.generate();
+    const recipientAta = await createAssociatedTokenAccount(
+      provider.connection,
+      sender.payer,
+      nftMint,
+      recipient.publicKey
+    );
+
+    const unauthorized = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        unauthorized.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    const [pendingRecoverPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("pending_recover"), forwarderAta.toBuffer()],
+      forwarderProgram.programId
+    );
+
+    const announceRecover = (authority: anchor.web3.Keypair) =>
+      forwarderProgram.methods
+        .announceRecover(recipient.publicKey)
+        .accountsPartial({
+          authority: authority.publicKey,
+          forwarder: forwarderPda,
+          relayDepositoryProgram: depositoryProgram.programId,
+          relayDepository: instance,
+          forwarderTokenAccount: forwarderAta,
+          pendingRecover: pendingRecoverPda,
+          program: forwarderProgram.programId,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    const recover = (authority: anchor.web3.Keypair) =>
+      forwarderProgram.methods
+        .recover()
+        .accountsPartial({
+          authority: authority.publicKey,
+          forwarder: forwarderPda,
+          relayDepositoryProgram: depositoryProgram.programId,
+          relayDepository: instance,
+          mint: nftMint,
+          forwarderTokenAccount: forwarderAta,
+          pendingRecover: pendingRecoverPda,
+          recipient: recipient.publicKey,
+          recipientTokenAccount: recipientAta,
+          program: forwarderProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    const cancelRecover = (authority: anchor.web3.Keypair) =>
+      forwarderProgram.methods
+        .cancelRecover()
+        .accountsPartial({
+          authority: authority.publicKey,
+          forwarder: forwarderPda,
+          relayDepositoryProgram: depositoryProgram.programId,
+          relayDepository: instance,
+          forwarderTokenAccount: forwarderAta,
+          pendingRecover: pendingRecoverPda,
+          program: forwarderProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+    // Only the owner of the relay depository can announce a recovery to another
+    // recipient, not even the upgrade authority of the forwarder
+    try {
+      await announceRecover(sender.payer);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+    try {
+      await announceRecover(unauthorized);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+
+    // Nothing can be recovered without an announcement
+    try {
+      await recover(owner);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "AccountNotInitialized");
+    }
+
+    const announceTx = await announceRecover(owner);
+    const announceTxTransaction =
+      await provider.connection.getParsedTransaction(announceTx, {
+        commitment: "confirmed",
+      });
+    const RecoverAnnouncedEvent = getCpiEvents(
+      announceTxTransaction,
+      forwarderProgram
+    ).find((event) => event.name === "recoverAnnouncedEvent");
+    assert.exists(RecoverAnnouncedEvent);
+    assert.ok(RecoverAnnouncedEvent?.data.mint.equals(nftMint));
+    assert.ok(
+      RecoverAnnouncedEvent?.data.recipient.equals(recipient.publicKey)
+    );
+
+    // The recovery can't happen before the timelock has elapsed
+    try {
+      await recover(owner);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "TimelockNotExpired");
+    }
+
+    const cancelTx = await cancelRecover(owner);
+    const cancelTxTransaction = await provider.connection.getParsedTransaction(
+      cancelTx,
+      { commitment: "confirmed" }
+    );
+    assert.exists(
+      getCpiEvents(cancelTxTransaction, forwarderProgram).find(
+        (event) => event.name === "recoverCancelledEvent"
+      )
+    );
+    assert.isNull(await provider.connection.getAccountInfo(pendingRecoverPda));
+
+    // The asset stays in the forwarder
+    const forwarderTokenBalance = await provider.connection
+      .getTokenAccountBalance(forwarderAta)
+      .then((res) => res.value.amount);
+    assert.equal(forwarderTokenBalance, "1");
+
+    // A depositor can announce the recovery of an asset to itself, which the owner
+    // cancels during the timelock if the asset isn't the depositor's
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        recipient.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+    const depositorAnnounceTx = await announceRecover(recipient);
+    const depositorAnnounceTxTransaction =
+      await provider.connection.getParsedTransaction(depositorAnnounceTx, {
+        commitment: "confirmed",
+      });
+    const depositorAnnouncedEvent = getCpiEvents(
+      depositorAnnounceTxTransaction,
+      forwarderProgram
+    ).find((event) => event.name === "recoverAnnouncedEvent");
+    assert.ok(
+      depositorAnnouncedEvent?.data.authority.equals(recipient.publicKey)
+    );
+
+    try {
+      await recover(recipient);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "TimelockNotExpired");
+    }
+    try {
+      await cancelRecover(unauthorized);
+      assert.fail("Expected transaction to fail");
+    } catch (err) {
+      assert.include(err.message, "Unauthorized");
+    }
+
+    await cancelRecover(owner);
+    assert.isNull(await provider.connection.getAccountInfo(pendingRecoverPda));
   });
 
   it("Should fail with insufficient balance", async () => {
@@ -511,6 +2250,8 @@ describe("Relay Forwarder", () => {
           forwarder: forwarderPda,
           relayDepository,
           relayVault: vault,
+          relayVaultLedger: getVaultLedgerPDA(null),
+          forwardMinimum: getForwardMinimumPDA(null),
           relayDepositoryProgram: depositoryProgram.programId,
           systemProgram: anchor.web3.SystemProgram.programId,
         })